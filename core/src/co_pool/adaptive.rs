@@ -0,0 +1,399 @@
+use crate::info;
+
+/// The configuration of the adaptive controller, which periodically resizes the coroutine pools
+/// and the number of active event-loops of a runtime according to its load.
+///
+/// The controller is disabled until the interval is set.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct AdaptiveConfig {
+    interval: u64,
+    min_coroutines: usize,
+    max_coroutines: usize,
+    min_event_loops: usize,
+    max_event_loops: usize,
+    target_latency: u64,
+    high_utilization: u64,
+    low_utilization: u64,
+    syscall_park_ratio: u64,
+    damping: u64,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            interval: 0,
+            min_coroutines: 1,
+            max_coroutines: 65536,
+            min_event_loops: 0,
+            max_event_loops: 0,
+            target_latency: 10_000_000,
+            high_utilization: 80,
+            low_utilization: 20,
+            syscall_park_ratio: 50,
+            damping: 50,
+        }
+    }
+}
+
+impl AdaptiveConfig {
+    /// Returns `true` if the controller is enabled.
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.interval > 0
+    }
+
+    /// Get the interval in ns between two decisions.
+    #[must_use]
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Get the lower bound of the max coroutines of each event-loop.
+    #[must_use]
+    pub fn min_coroutines(&self) -> usize {
+        self.min_coroutines
+    }
+
+    /// Get the upper bound of the max coroutines of each event-loop.
+    #[must_use]
+    pub fn max_coroutines(&self) -> usize {
+        self.max_coroutines
+    }
+
+    /// Get the lower bound of the active event-loops.
+    #[must_use]
+    pub fn min_event_loops(&self) -> usize {
+        self.min_event_loops
+    }
+
+    /// Get the upper bound of the active event-loops, 0 keeps all event-loops active.
+    #[must_use]
+    pub fn max_event_loops(&self) -> usize {
+        self.max_event_loops
+    }
+
+    /// Get the queue latency in ns above which the runtime is considered overloaded.
+    #[must_use]
+    pub fn target_latency(&self) -> u64 {
+        self.target_latency
+    }
+
+    /// Get the utilization percentage to grow above.
+    #[must_use]
+    pub fn high_utilization(&self) -> u64 {
+        self.high_utilization
+    }
+
+    /// Get the utilization percentage to shrink below.
+    #[must_use]
+    pub fn low_utilization(&self) -> u64 {
+        self.low_utilization
+    }
+
+    /// Get the percentage of the busy coroutines parked in syscalls, above which the
+    /// controller adds coroutines instead of event-loops.
+    #[must_use]
+    pub fn syscall_park_ratio(&self) -> u64 {
+        self.syscall_park_ratio
+    }
+
+    /// Get the percentage of the history kept when smoothing the sampled load.
+    #[must_use]
+    pub fn damping(&self) -> u64 {
+        self.damping
+    }
+
+    /// Set the interval in ns between two decisions, 0 disables the controller.
+    pub fn set_interval(&mut self, interval: u64) -> &mut Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the bounds of the max coroutines of each event-loop.
+    pub fn set_coroutine_bounds(&mut self, min: usize, max: usize) -> &mut Self {
+        assert!(min > 0, "min coroutines must be greater than 0");
+        assert!(
+            max >= min,
+            "max coroutines must be greater than or equal to min coroutines"
+        );
+        self.min_coroutines = min;
+        self.max_coroutines = max;
+        self
+    }
+
+    /// Set the bounds of the active event-loops, `(0, 0)` keeps all event-loops active.
+    pub fn set_event_loop_bounds(&mut self, min: usize, max: usize) -> &mut Self {
+        assert!(
+            max >= min,
+            "max event-loops must be greater than or equal to min event-loops"
+        );
+        self.min_event_loops = min;
+        self.max_event_loops = max;
+        self
+    }
+
+    /// Set the queue latency in ns above which the runtime is considered overloaded.
+    pub fn set_target_latency(&mut self, target_latency: u64) -> &mut Self {
+        assert!(target_latency > 0, "target_latency must be greater than 0");
+        self.target_latency = target_latency;
+        self
+    }
+
+    /// Set the utilization percentages to grow above and to shrink below.
+    pub fn set_utilization_bounds(&mut self, low: u64, high: u64) -> &mut Self {
+        assert!(
+            low < high && high <= 100,
+            "utilization bounds must satisfy low < high <= 100"
+        );
+        self.low_utilization = low;
+        self.high_utilization = high;
+        self
+    }
+
+    /// Set the percentage of the running coroutines parked in syscalls, above which the
+    /// controller adds coroutines instead of event-loops.
+    pub fn set_syscall_park_ratio(&mut self, syscall_park_ratio: u64) -> &mut Self {
+        assert!(
+            syscall_park_ratio <= 100,
+            "syscall_park_ratio must be less than or equal to 100"
+        );
+        self.syscall_park_ratio = syscall_park_ratio;
+        self
+    }
+
+    /// Set the percentage of the history kept when smoothing the sampled load, the higher the
+    /// damping the slower the controller reacts.
+    pub fn set_damping(&mut self, damping: u64) -> &mut Self {
+        assert!(damping < 100, "damping must be less than 100");
+        self.damping = damping;
+        self
+    }
+}
+
+/// The load of a runtime sampled by the adaptive controller.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct LoadSignals {
+    /// The time in ns the oldest queued task or coroutine has waited.
+    pub latency: u64,
+    /// The percentage of the max coroutines running tasks.
+    pub utilization: u64,
+    /// The percentage of the busy coroutines parked in syscalls.
+    pub syscall_park: u64,
+    /// The number of coroutines running tasks, including the ones parked in syscalls.
+    pub busy: usize,
+}
+
+impl LoadSignals {
+    // keep `damping`% of the history
+    fn smooth(self, history: Self, damping: u64) -> Self {
+        let mix = |old: u64, new: u64| {
+            old.saturating_mul(damping)
+                .saturating_add(new.saturating_mul(100 - damping))
+                / 100
+        };
+        Self {
+            latency: mix(history.latency, self.latency),
+            utilization: mix(history.utilization, self.utilization),
+            syscall_park: mix(history.syscall_park, self.syscall_park),
+            busy: usize::try_from(mix(history.busy as u64, self.busy as u64)).unwrap_or(usize::MAX),
+        }
+    }
+}
+
+/// The sizes applied by the adaptive controller.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Decision {
+    /// The min coroutines of each event-loop.
+    pub min_size: usize,
+    /// The max coroutines of each event-loop.
+    pub max_size: usize,
+    /// The number of event-loops accepting new submissions.
+    pub event_loops: usize,
+}
+
+/// The adaptive controller of a runtime, it smooths the sampled load and decides the sizes.
+#[derive(Debug)]
+pub struct AdaptiveController {
+    name: String,
+    config: AdaptiveConfig,
+    // the bounds of the active event-loops
+    event_loops: (usize, usize),
+    smoothed: Option<LoadSignals>,
+    current: Decision,
+}
+
+impl AdaptiveController {
+    /// Create a new controller with the `current` sizes of the runtime.
+    #[must_use]
+    pub fn new(
+        name: String,
+        config: AdaptiveConfig,
+        current: Decision,
+        event_loop_size: usize,
+    ) -> Self {
+        let event_loops = if config.max_event_loops() == 0 {
+            (event_loop_size, event_loop_size)
+        } else {
+            let max = config.max_event_loops().min(event_loop_size);
+            (config.min_event_loops().clamp(1, max), max)
+        };
+        Self {
+            name,
+            config,
+            event_loops,
+            smoothed: None,
+            current,
+        }
+    }
+
+    /// Get the name of this controller.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Decide the sizes with the sampled load, returns `None` if nothing changes.
+    pub fn decide(&mut self, sample: LoadSignals) -> Option<Decision> {
+        let signals = self.smoothed.map_or(sample, |history| {
+            sample.smooth(history, self.config.damping())
+        });
+        self.smoothed = Some(signals);
+        let (min_loops, max_loops) = self.event_loops;
+        let mut next = self.current;
+        next.event_loops = next.event_loops.clamp(min_loops, max_loops);
+        if signals.latency > self.config.target_latency()
+            || signals.utilization >= self.config.high_utilization()
+        {
+            // the coroutines parked in syscalls don't need more CPU
+            if signals.syscall_park < self.config.syscall_park_ratio()
+                && next.event_loops < max_loops
+            {
+                next.event_loops += 1;
+            } else {
+                next.max_size = next.max_size.saturating_mul(2);
+            }
+        } else if signals.latency <= self.config.target_latency() / 2
+            && signals.utilization <= self.config.low_utilization()
+        {
+            next.event_loops = next.event_loops.saturating_sub(1).max(min_loops);
+            next.max_size /= 2;
+        }
+        next.max_size = next
+            .max_size
+            .clamp(self.config.min_coroutines(), self.config.max_coroutines());
+        // keep the coroutines for the steady load alive
+        next.min_size = (signals.busy / next.event_loops.max(1)).min(next.max_size);
+        if next == self.current {
+            return None;
+        }
+        info!(
+            "{} adaptive resize from {:?} to {:?} due to {:?}",
+            self.name, self.current, next, signals
+        );
+        self.current = next;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller(config: AdaptiveConfig) -> AdaptiveController {
+        AdaptiveController::new(
+            String::from("test"),
+            config,
+            Decision {
+                min_size: 0,
+                max_size: 16,
+                event_loops: 2,
+            },
+            4,
+        )
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut config = AdaptiveConfig::default();
+        _ = config
+            .set_interval(1)
+            .set_coroutine_bounds(4, 32)
+            .set_event_loop_bounds(1, 3)
+            .set_damping(0);
+        let mut controller = controller(config);
+        let overloaded = LoadSignals {
+            latency: 20_000_000,
+            utilization: 90,
+            syscall_park: 0,
+            busy: 30,
+        };
+        // CPU bound, add event-loops first
+        let decision = controller.decide(overloaded).expect("should grow");
+        assert_eq!(3, decision.event_loops);
+        assert_eq!(16, decision.max_size);
+        assert_eq!(10, decision.min_size);
+        let decision = controller.decide(overloaded).expect("should grow");
+        assert_eq!((3, 32), (decision.event_loops, decision.max_size));
+        // the bounds are reached
+        assert_eq!(None, controller.decide(overloaded));
+    }
+
+    #[test]
+    fn test_syscall_park() {
+        let mut config = AdaptiveConfig::default();
+        _ = config
+            .set_interval(1)
+            .set_event_loop_bounds(1, 4)
+            .set_damping(0);
+        let mut controller = controller(config);
+        let decision = controller
+            .decide(LoadSignals {
+                latency: 20_000_000,
+                utilization: 50,
+                syscall_park: 80,
+                busy: 16,
+            })
+            .expect("should grow");
+        // the coroutines are blocked in syscalls, add coroutines instead of event-loops
+        assert_eq!((2, 32), (decision.event_loops, decision.max_size));
+    }
+
+    #[test]
+    fn test_shrink_with_damping() {
+        let mut config = AdaptiveConfig::default();
+        _ = config
+            .set_interval(1)
+            .set_coroutine_bounds(4, 32)
+            .set_event_loop_bounds(1, 4);
+        let mut controller = controller(config);
+        let overloaded = LoadSignals {
+            latency: 20_000_000,
+            utilization: 100,
+            syscall_park: 0,
+            busy: 32,
+        };
+        _ = controller.decide(overloaded);
+        // the history is not forgotten at once, only the min size follows the load
+        let mut decision = controller
+            .decide(LoadSignals::default())
+            .expect("should change");
+        assert_eq!(
+            (3, 16, 5),
+            (decision.event_loops, decision.max_size, decision.min_size)
+        );
+        while let Some(next) = controller.decide(LoadSignals::default()) {
+            decision = next;
+        }
+        assert_eq!(
+            Decision {
+                min_size: 0,
+                max_size: 4,
+                event_loops: 1,
+            },
+            decision
+        );
+    }
+}
@@ -1,21 +1,50 @@
+use crate::co_pool::task::TaskState;
 use crate::co_pool::CoroutinePool;
-use crate::common::constants::CoroutineState;
+use crate::common::constants::{CoroutineState, COROUTINE_OWNER, COROUTINE_TASK};
 use crate::coroutine::listener::Listener;
 use crate::coroutine::local::CoroutineLocal;
 use crate::scheduler::SchedulableCoroutineState;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[repr(C)]
 #[derive(Debug, Default)]
 pub(crate) struct CoroutineCreator {}
 
+impl CoroutineCreator {
+    /// Decrease the running counter of the pool owns the coroutine.
+    fn recycle(local: &CoroutineLocal) {
+        if let Some(running) = local.remove::<Arc<AtomicUsize>>(COROUTINE_OWNER) {
+            _ = running.fetch_update(Ordering::AcqRel, Ordering::Acquire, |running| {
+                Some(running.saturating_sub(1))
+            });
+        } else if let Some(pool) = CoroutinePool::current() {
+            pool.running
+                .store(pool.get_running_size().saturating_sub(1), Ordering::Release);
+        }
+    }
+}
+
 impl Listener<(), Option<usize>> for CoroutineCreator {
     fn on_state_changed(
         &self,
-        _: &CoroutineLocal,
+        local: &CoroutineLocal,
         _: SchedulableCoroutineState,
         new_state: SchedulableCoroutineState,
     ) {
+        if let Some(task_id) = local.get::<u64>(COROUTINE_TASK) {
+            let state = match new_state {
+                CoroutineState::Running => Some(TaskState::Running),
+                CoroutineState::Ready | CoroutineState::Suspend((), _) => {
+                    Some(TaskState::Suspended)
+                }
+                CoroutineState::Syscall((), syscall, _) => Some(TaskState::Syscall(syscall)),
+                _ => None,
+            };
+            if let (Some(state), Some(pool)) = (state, CoroutinePool::current()) {
+                pool.set_task_state(*task_id, state);
+            }
+        }
         match new_state {
             CoroutineState::Suspend((), _) | CoroutineState::Syscall((), _, _) => {
                 if let Some(pool) = CoroutinePool::current() {
@@ -23,21 +52,29 @@ impl Listener<(), Option<usize>> for CoroutineCreator {
                 }
             }
             CoroutineState::Complete(_) => {
-                if let Some(pool) = CoroutinePool::current() {
-                    //worker协程正常退出
-                    pool.running
-                        .store(pool.get_running_size().saturating_sub(1), Ordering::Release);
-                }
+                //worker协程正常退出
+                Self::recycle(local);
             }
             CoroutineState::Cancelled | CoroutineState::Error(_) => {
+                //worker协程异常退出，需要先回收再创建
+                Self::recycle(local);
                 if let Some(pool) = CoroutinePool::current() {
-                    //worker协程异常退出，需要先回收再创建
-                    pool.running
-                        .store(pool.get_running_size().saturating_sub(1), Ordering::Release);
                     _ = pool.try_grow();
                 }
             }
             _ => {}
         }
     }
+
+    fn on_migrate(&self, local: &CoroutineLocal, _: &str) {
+        if let Some(pool) = CoroutinePool::current() {
+            // transfer the coroutine to the current pool
+            if let Some(running) = local.put(COROUTINE_OWNER, pool.running.clone()) {
+                _ = running.fetch_update(Ordering::AcqRel, Ordering::Acquire, |running| {
+                    Some(running.saturating_sub(1))
+                });
+            }
+            _ = pool.running.fetch_add(1, Ordering::Release);
+        }
+    }
 }
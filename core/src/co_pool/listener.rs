@@ -0,0 +1,161 @@
+use std::ffi::{c_char, c_void};
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// A trait for observing the tasks of the coroutine pool.
+#[allow(unused_variables)]
+pub trait TaskListener: Debug {
+    /// Callback when a task missed its absolute `deadline` in ns. `started` is `false` if the
+    /// deadline has passed before the task started, otherwise the task finished too late.
+    fn on_deadline_missed(&self, task_id: u64, task_name: &str, deadline: u64, started: bool) {}
+
+    /// Callback when a task is submitted to a full task queue, before the
+    /// [`crate::co_pool::task::RejectionPolicy`] applies.
+    fn on_rejected(&self, task_id: u64, task_name: &str) {}
+
+    /// Callback when a fixed-rate schedule falls behind, `missed` is the number of runs missed
+    /// since the last fire, see [`crate::co_pool::schedule::OverrunPolicy`].
+    fn on_overrun(&self, schedule_id: u64, task_name: &str, missed: u64) {}
+
+    /// Callback after each attempt of a task, `attempt` starts from 1. `retry_delay` is the wait
+    /// time before the next attempt, `None` if the task is not retried.
+    fn on_attempt(
+        &self,
+        task_id: u64,
+        task_name: &str,
+        attempt: u32,
+        result: &Result<Option<usize>, &str>,
+        retry_delay: Option<Duration>,
+    ) {
+    }
+}
+
+/// The attempt of a task passed to [`RawTaskListener::on_attempt`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawAttempt {
+    /// The attempt starts from 1.
+    pub attempt: u32,
+    /// Whether the attempt succeeded.
+    pub succeeded: bool,
+    /// Whether the succeeded attempt has a result.
+    pub has_result: bool,
+    /// The result of the succeeded attempt, ignored if `has_result` is `false`.
+    pub result: usize,
+    /// The error of the failed attempt, it's not nul-terminated.
+    pub error: *const c_char,
+    /// The byte length of the error.
+    pub error_len: usize,
+    /// Whether the task is retried.
+    pub retry: bool,
+    /// The wait time in ns before the next attempt, ignored if `retry` is `false`.
+    pub retry_delay: u64,
+}
+
+/// The C compatible [`TaskListener`], the callbacks are called with the `context`, and the
+/// task names passed to them are not nul-terminated. The missing callbacks are skipped.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawTaskListener {
+    /// The context of the callbacks.
+    pub context: *mut c_void,
+    /// See [`TaskListener::on_deadline_missed`].
+    pub on_deadline_missed:
+        Option<extern "C" fn(*mut c_void, u64, *const c_char, usize, u64, bool)>,
+    /// See [`TaskListener::on_rejected`].
+    pub on_rejected: Option<extern "C" fn(*mut c_void, u64, *const c_char, usize)>,
+    /// See [`TaskListener::on_overrun`].
+    pub on_overrun: Option<extern "C" fn(*mut c_void, u64, *const c_char, usize, u64)>,
+    /// See [`TaskListener::on_attempt`].
+    pub on_attempt: Option<extern "C" fn(*mut c_void, u64, *const c_char, usize, &RawAttempt)>,
+    /// Free the `context` once the listener is dropped.
+    pub free: Option<extern "C" fn(*mut c_void)>,
+}
+
+impl RawTaskListener {
+    /// Create the C compatible listener which calls the `listener`.
+    pub fn new<L: TaskListener + 'static>(listener: L) -> Self {
+        RawTaskListener {
+            context: Box::into_raw(Box::new(listener)).cast::<c_void>(),
+            on_deadline_missed: Some(on_deadline_missed::<L>),
+            on_rejected: Some(on_rejected::<L>),
+            on_overrun: Some(on_overrun::<L>),
+            on_attempt: Some(on_attempt::<L>),
+            free: Some(free::<L>),
+        }
+    }
+}
+
+// the string passed to the callbacks, it's empty if it's not utf-8
+unsafe fn to_str<'s>(s: *const c_char, len: usize) -> &'s str {
+    if s.is_null() {
+        return "";
+    }
+    std::str::from_utf8(std::slice::from_raw_parts(s.cast::<u8>(), len)).unwrap_or_default()
+}
+
+extern "C" fn on_deadline_missed<L: TaskListener>(
+    context: *mut c_void,
+    task_id: u64,
+    task_name: *const c_char,
+    len: usize,
+    deadline: u64,
+    started: bool,
+) {
+    let listener = unsafe { &*context.cast::<L>() };
+    listener.on_deadline_missed(
+        task_id,
+        unsafe { to_str(task_name, len) },
+        deadline,
+        started,
+    );
+}
+
+extern "C" fn on_rejected<L: TaskListener>(
+    context: *mut c_void,
+    task_id: u64,
+    task_name: *const c_char,
+    len: usize,
+) {
+    let listener = unsafe { &*context.cast::<L>() };
+    listener.on_rejected(task_id, unsafe { to_str(task_name, len) });
+}
+
+extern "C" fn on_overrun<L: TaskListener>(
+    context: *mut c_void,
+    schedule_id: u64,
+    task_name: *const c_char,
+    len: usize,
+    missed: u64,
+) {
+    let listener = unsafe { &*context.cast::<L>() };
+    listener.on_overrun(schedule_id, unsafe { to_str(task_name, len) }, missed);
+}
+
+extern "C" fn on_attempt<L: TaskListener>(
+    context: *mut c_void,
+    task_id: u64,
+    task_name: *const c_char,
+    len: usize,
+    attempt: &RawAttempt,
+) {
+    let listener = unsafe { &*context.cast::<L>() };
+    let result = if attempt.succeeded {
+        Ok(attempt.has_result.then_some(attempt.result))
+    } else {
+        Err(unsafe { to_str(attempt.error, attempt.error_len) })
+    };
+    listener.on_attempt(
+        task_id,
+        unsafe { to_str(task_name, len) },
+        attempt.attempt,
+        &result,
+        attempt
+            .retry
+            .then(|| Duration::from_nanos(attempt.retry_delay)),
+    );
+}
+
+extern "C" fn free<L>(context: *mut c_void) {
+    drop(unsafe { Box::from_raw(context.cast::<L>()) });
+}
@@ -1,35 +1,53 @@
 use crate::co_pool::creator::CoroutineCreator;
-use crate::co_pool::task::Task;
+use crate::co_pool::listener::TaskListener;
+use crate::co_pool::registry::{TaskOwner, TaskRegistry};
+use crate::co_pool::schedule::{OverrunPolicy, Period, Schedule};
+use crate::co_pool::task::{
+    DeadlineMissPolicy, RejectionPolicy, RetryPolicy, Task, TaskInfo, TaskState, TimeoutFrom,
+    TIMEOUT_ERROR,
+};
 use crate::common::beans::BeanFactory;
-use crate::common::constants::PoolState;
-use crate::common::ordered_work_steal::{OrderedLocalQueue, OrderedWorkStealQueue};
+use crate::common::constants::{
+    runtime_bean, PoolState, COROUTINE_OWNER, COROUTINE_TASK, TASK_GLOBAL_QUEUE_BEAN,
+    TASK_REGISTRY_BEAN,
+};
+use crate::common::ordered_work_steal::{
+    Ordered, OrderedLocalQueue, OrderedWorkStealQueue, DEFAULT_PRECEDENCE,
+};
+use crate::common::timing_wheel::TimingWheel;
 use crate::common::{get_timeout_time, now, CondvarBlocker};
 use crate::coroutine::suspender::Suspender;
-use crate::scheduler::{SchedulableCoroutine, Scheduler};
-use crate::{error, impl_current_for, impl_display_by_debug, impl_for_named, trace, warn};
-use dashmap::{DashMap, DashSet};
-use once_cell::sync::Lazy;
+use crate::scheduler::{SchedulableCoroutine, SchedulableSuspender, Scheduler};
+use crate::{co, error, impl_current_for, impl_display_by_debug, impl_for_named, trace, warn};
+use dashmap::DashMap;
 use std::cell::Cell;
 use std::ffi::c_longlong;
 use std::io::{Error, ErrorKind};
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::time::Duration;
 
 /// Task abstraction and impl.
 pub mod task;
 
+/// Task listener abstraction.
+pub mod listener;
+
+/// Delayed and periodic task abstraction and impl.
+pub mod schedule;
+
+/// Adaptive pool sizing abstraction and impl.
+pub mod adaptive;
+
 /// Coroutine pool state abstraction and impl.
 mod state;
 
 /// Creator for coroutine pool.
 mod creator;
 
-/// `task_id` -> `co_id`
-static RUNNING_TASKS: Lazy<DashMap<u64, u64>> = Lazy::new(DashMap::new);
-
-static CANCEL_TASKS: Lazy<DashSet<u64>> = Lazy::new(DashSet::new);
+/// Task registry and task owner impls.
+mod registry;
 
 /// The coroutine pool impls.
 #[repr(C)]
@@ -43,7 +61,9 @@ pub struct CoroutinePool<'p> {
     //工作协程组
     workers: Scheduler<'p>,
     //当前协程数
-    running: AtomicUsize,
+    running: Arc<AtomicUsize>,
+    //正在执行任务的协程数，包括陷入系统调用的
+    busy: AtomicUsize,
     //尝试取出任务失败的次数
     pop_fail_times: AtomicUsize,
     //最小协程数，即核心协程数
@@ -54,11 +74,28 @@ pub struct CoroutinePool<'p> {
     keep_alive_time: AtomicU64,
     //阻滞器
     blocker: Arc<CondvarBlocker>,
-    //正在等待结果的
-    waits: DashMap<u64, Arc<(Mutex<bool>, Condvar)>>,
-    //任务执行结果
-    results: DashMap<u64, Result<Option<usize>, &'p str>>,
-    no_waits: DashSet<u64>,
+    //运行时内存活的任务
+    registry: &'p TaskRegistry<'p>,
+    //本池提交的任务的排队数及执行结果
+    owner: Arc<TaskOwner<'p>>,
+    //任务监听器
+    task_listeners: RwLock<Vec<Arc<dyn TaskListener + 'p>>>,
+    //错过截止时间的任务数
+    deadline_misses: AtomicU64,
+    //任务队列容量，0表示无界
+    task_capacity: AtomicUsize,
+    //任务队列满时的拒绝策略
+    rejection_policy: RwLock<RejectionPolicy>,
+    //被拒绝的任务数
+    rejections: AtomicU64,
+    //超时被取消的任务数
+    timeouts: AtomicU64,
+    //延时及周期任务的定时器
+    schedule_timers: Mutex<TimingWheel<u64>>,
+    //schedule_id -> 延时或周期任务
+    schedules: DashMap<u64, Schedule<'p>>,
+    //周期任务错过的执行次数
+    overruns: AtomicU64,
 }
 
 impl Drop for CoroutinePool<'_> {
@@ -127,27 +164,104 @@ impl<'p> CoroutinePool<'p> {
         max_size: usize,
         keep_alive_time: u64,
     ) -> Self {
-        let mut workers = Scheduler::new(name, stack_size);
+        Self::with_runtime(name, "", stack_size, min_size, max_size, keep_alive_time)
+    }
+
+    /// Create a new `CoroutinePool` instance shares the queues of the `runtime`, the pools of
+    /// different runtimes never steal tasks from each other.
+    #[must_use]
+    pub fn with_runtime(
+        name: String,
+        runtime: &str,
+        stack_size: usize,
+        min_size: usize,
+        max_size: usize,
+        keep_alive_time: u64,
+    ) -> Self {
+        let mut workers = Scheduler::with_runtime(name, runtime, stack_size);
         workers.add_listener(CoroutineCreator::default());
         CoroutinePool {
             state: Cell::new(PoolState::Running),
             workers,
-            running: AtomicUsize::new(0),
+            running: Arc::new(AtomicUsize::new(0)),
+            busy: AtomicUsize::new(0),
             pop_fail_times: AtomicUsize::new(0),
             min_size: AtomicUsize::new(min_size),
             max_size: AtomicUsize::new(max_size),
             task_queue: BeanFactory::get_or_default::<OrderedWorkStealQueue<Task<'p>>>(
-                crate::common::constants::TASK_GLOBAL_QUEUE_BEAN,
+                runtime_bean(TASK_GLOBAL_QUEUE_BEAN, runtime),
             )
             .local_queue(),
             keep_alive_time: AtomicU64::new(keep_alive_time),
             blocker: Arc::default(),
-            results: DashMap::new(),
-            waits: DashMap::default(),
-            no_waits: DashSet::default(),
+            registry: BeanFactory::get_or_default(runtime_bean(TASK_REGISTRY_BEAN, runtime)),
+            owner: Arc::default(),
+            task_listeners: RwLock::default(),
+            deadline_misses: AtomicU64::new(0),
+            task_capacity: AtomicUsize::new(0),
+            rejection_policy: RwLock::default(),
+            rejections: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            schedule_timers: Mutex::default(),
+            schedules: DashMap::new(),
+            overruns: AtomicU64::new(0),
         }
     }
 
+    /// Add a task listener to this pool.
+    pub fn add_task_listener(&self, listener: Arc<dyn TaskListener + 'p>) {
+        self.task_listeners
+            .write()
+            .expect("lock failed")
+            .push(listener);
+    }
+
+    /// Get the number of tasks missed their deadlines in this pool.
+    pub fn deadline_misses(&self) -> u64 {
+        self.deadline_misses.load(Ordering::Acquire)
+    }
+
+    /// Get the number of tasks submitted to a full task queue of this pool.
+    pub fn rejections(&self) -> u64 {
+        self.rejections.load(Ordering::Acquire)
+    }
+
+    /// Get the number of tasks cancelled by this pool due to timeout.
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Acquire)
+    }
+
+    /// Get the number of runs missed by the fixed-rate schedules of this pool.
+    pub fn overruns(&self) -> u64 {
+        self.overruns.load(Ordering::Acquire)
+    }
+
+    /// Set the max number of queued tasks submitted by this pool, 0 means unbounded.
+    pub fn set_task_capacity(&self, task_capacity: usize) {
+        self.task_capacity.store(task_capacity, Ordering::Release);
+    }
+
+    /// Get the max number of queued tasks submitted by this pool, 0 means unbounded.
+    pub fn get_task_capacity(&self) -> usize {
+        self.task_capacity.load(Ordering::Acquire)
+    }
+
+    /// Set what to do with a submitted task when the task queue of this pool is full.
+    pub fn set_rejection_policy(&self, rejection_policy: RejectionPolicy) {
+        *self.rejection_policy.write().expect("lock failed") = rejection_policy;
+    }
+
+    /// Get what to do with a submitted task when the task queue of this pool is full.
+    pub fn get_rejection_policy(&self) -> RejectionPolicy {
+        *self.rejection_policy.read().expect("lock failed")
+    }
+
+    /// Get the number of tasks submitted by this pool and still queued, the tasks may be stolen
+    /// by the sibling pools.
+    pub fn get_queued_size(&self) -> usize {
+        self.owner.queued.load(Ordering::Acquire)
+    }
+
     /// Set the minimum coroutine number in this pool.
     pub fn set_min_size(&self, min_size: usize) {
         self.min_size.store(min_size, Ordering::Release);
@@ -163,6 +277,12 @@ impl<'p> CoroutinePool<'p> {
         self.running.load(Ordering::Acquire)
     }
 
+    /// Gets the number of coroutines running tasks in this pool, including the ones parked in
+    /// syscalls, the idle coroutines waiting for tasks are excluded.
+    pub fn get_busy_size(&self) -> usize {
+        self.busy.load(Ordering::Acquire)
+    }
+
     /// Set the maximum coroutine number in this pool.
     pub fn set_max_size(&self, max_size: usize) {
         self.max_size.store(max_size, Ordering::Release);
@@ -231,13 +351,14 @@ impl<'p> CoroutinePool<'p> {
     }
 
     fn do_clean(&mut self) {
-        // clean up remaining wait tasks
-        for r in &self.waits {
-            let task_id = *r.key();
-            _ = self
-                .results
-                .insert(task_id, Err("The coroutine pool has stopped"));
-            self.notify(task_id);
+        // clean up remaining wait tasks, only the tasks submitted by this pool
+        let waits: Vec<u64> = self.owner.waits.iter().map(|r| *r.key()).collect();
+        for task_id in waits {
+            // the task is finished here, it's discarded if it still finishes later
+            _ = self.registry.remove(task_id);
+            _ = self.registry.timeouts.remove(&task_id);
+            self.owner
+                .finish(task_id, Err("The coroutine pool has stopped"));
         }
     }
 
@@ -252,23 +373,423 @@ impl<'p> CoroutinePool<'p> {
         param: Option<usize>,
         priority: Option<c_longlong>,
     ) -> std::io::Result<u64> {
-        match self.state() {
-            PoolState::Running => {}
-            PoolState::Stopping | PoolState::Stopped => {
-                return Err(Error::other("The coroutine pool is stopping or stopped !"))
-            }
-        }
+        self.check_running()?;
         let task = Task::new(
             name.unwrap_or(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
             func,
             param,
             priority,
         );
+        self.offer(task)
+    }
+
+    /// Submit a built task to this pool, see [`Task::with_deadline`], [`Task::with_timeout`]
+    /// and [`Task::with_stack_size`].
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit(&self, task: Task<'p>) -> std::io::Result<u64> {
+        self.check_running()?;
+        self.offer(task)
+    }
+
+    /// Submit a new task with the absolute `deadline` in ns to this pool, the timestamp is based
+    /// on [`crate::common::now`]. The tasks with deadlines run in earliest-deadline-first order
+    /// before the tasks with priorities.
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_task_with_deadline(
+        &self,
+        name: Option<String>,
+        func: impl FnOnce(Option<usize>) -> Option<usize> + 'p,
+        param: Option<usize>,
+        deadline: u64,
+        miss_policy: DeadlineMissPolicy,
+    ) -> std::io::Result<u64> {
+        self.check_running()?;
+        let task = Task::new(
+            name.unwrap_or(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
+            func,
+            param,
+            None,
+        )
+        .with_deadline(deadline, miss_policy);
+        self.offer(task)
+    }
+
+    /// Submit a new task with the max run time `timeout` in ns to this pool, the time is measured
+    /// from the start of the task or from now. Once it's exceeded, the task is cancelled and the
+    /// joiner gets the timeout error.
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_task_with_timeout(
+        &self,
+        name: Option<String>,
+        func: impl FnOnce(Option<usize>) -> Option<usize> + 'p,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        timeout: u64,
+        from: TimeoutFrom,
+    ) -> std::io::Result<u64> {
+        self.check_running()?;
+        let task = Task::new(
+            name.unwrap_or(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
+            func,
+            param,
+            priority,
+        )
+        .with_timeout(timeout, from);
+        self.offer(task)
+    }
+
+    /// Submit a new task retried by the `policy` to this pool, the `func` runs again if its
+    /// attempt fails. The backoff between attempts suspends the coroutine running the task
+    /// instead of blocking the thread, the joiner gets the result of the last attempt.
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_task_with_retry(
+        &self,
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'p,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        policy: RetryPolicy,
+    ) -> std::io::Result<u64> {
+        self.check_running()?;
+        let task = Task::retryable(
+            name.unwrap_or(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
+            func,
+            param,
+            priority,
+            policy,
+        );
+        self.offer(task)
+    }
+
+    /// Submit the task to this pool after `delay`, the task can be joined and cancelled by its
+    /// id as usual. The delayed task is not limited by the task capacity.
+    pub fn submit_delayed(&self, task: Task<'p>, delay: Duration) -> std::io::Result<u64> {
+        self.check_running()?;
         let task_id = task.id();
-        self.submit_raw_task(task);
+        self.register(&task);
+        self.schedule(task_id, get_timeout_time(delay), Schedule::Delayed(task));
         Ok(task_id)
     }
 
+    /// Run the `func` every `period` in this pool after `initial_delay`, returns the schedule
+    /// id for cancel. Each run is a new task, the results are discarded. If the schedule falls
+    /// behind, the missed runs are handled by the `overrun` policy.
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_at_fixed_rate(
+        &self,
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'p,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        initial_delay: Duration,
+        period: Duration,
+        overrun: OverrunPolicy,
+    ) -> std::io::Result<u64> {
+        let period = u64::try_from(period.as_nanos()).unwrap_or(u64::MAX);
+        self.submit_periodic(
+            name,
+            func,
+            param,
+            priority,
+            initial_delay,
+            Period::FixedRate(period, overrun),
+        )
+    }
+
+    /// Run the `func` in this pool after `initial_delay`, then `delay` after each run finished,
+    /// returns the schedule id for cancel. Each run is a new task, the results are discarded.
+    pub fn submit_with_fixed_delay(
+        &self,
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'p,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        initial_delay: Duration,
+        delay: Duration,
+    ) -> std::io::Result<u64> {
+        let delay = u64::try_from(delay.as_nanos()).unwrap_or(u64::MAX);
+        self.submit_periodic(
+            name,
+            func,
+            param,
+            priority,
+            initial_delay,
+            Period::FixedDelay(delay),
+        )
+    }
+
+    fn submit_periodic(
+        &self,
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'p,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        initial_delay: Duration,
+        period: Period,
+    ) -> std::io::Result<u64> {
+        self.check_running()?;
+        let first = get_timeout_time(initial_delay);
+        let schedule = Schedule::periodic(
+            name.unwrap_or(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
+            func,
+            param,
+            priority,
+            period,
+            first,
+        );
+        let schedule_id = Schedule::next_id();
+        self.schedule(schedule_id, first, schedule);
+        Ok(schedule_id)
+    }
+
+    fn schedule(&self, schedule_id: u64, timestamp: u64, schedule: Schedule<'p>) {
+        _ = self.schedules.insert(schedule_id, schedule);
+        _ = self
+            .schedule_timers
+            .lock()
+            .expect("lock failed")
+            .insert(timestamp, schedule_id);
+        self.blocker.notify();
+    }
+
+    /// Try to cancel the delayed task which is not submitted yet, or the periodic task by the
+    /// schedule id. The running task of the periodic schedule is not cancelled.
+    ///
+    /// Returns `false` if the schedule is not found.
+    pub fn try_cancel_schedule(&self, schedule_id: u64) -> bool {
+        let Some((_, schedule)) = self.schedules.remove(&schedule_id) else {
+            return false;
+        };
+        if let Schedule::Delayed(task) = schedule {
+            _ = self.registry.remove(task.id());
+            _ = self.registry.timeouts.remove(&task.id());
+        }
+        warn!("Cancel schedule:{} successfully !", schedule_id);
+        true
+    }
+
+    /// Submit the expired delayed and periodic tasks to the task queue.
+    fn check_schedules(&self) {
+        if self.schedules.is_empty() {
+            return;
+        }
+        if self.check_running().is_err() {
+            // the pool is stopping, the schedules will never fire
+            let ids: Vec<u64> = self.schedules.iter().map(|entry| *entry.key()).collect();
+            for schedule_id in ids {
+                let schedule = self.schedules.remove(&schedule_id);
+                if let Some((_, Schedule::Delayed(task))) = schedule {
+                    if self.untrack_timeout(&task) {
+                        self.finish(task.id(), Err("The coroutine pool has stopped"));
+                    }
+                }
+            }
+            return;
+        }
+        let now = now();
+        let mut expired = Vec::new();
+        {
+            let mut timers = self.schedule_timers.lock().expect("lock failed");
+            for entry in &self.schedules {
+                if let Some(timestamp) = entry.rearm() {
+                    _ = timers.insert(timestamp, *entry.key());
+                }
+            }
+            while let Some(schedule_id) = timers.poll(now) {
+                expired.push(schedule_id);
+            }
+        }
+        for schedule_id in expired {
+            self.fire(schedule_id, now);
+        }
+    }
+
+    fn fire(&self, schedule_id: u64, now: u64) {
+        let Some(mut schedule) = self.schedules.get_mut(&schedule_id) else {
+            // cancelled
+            return;
+        };
+        if let Schedule::Delayed(_) = *schedule {
+            drop(schedule);
+            if let Some((_, Schedule::Delayed(mut task))) = self.schedules.remove(&schedule_id) {
+                // the delayed task is not limited by the task capacity
+                _ = self.owner.try_reserve(0);
+                task.enqueued(self.owner.clone());
+                self.submit_raw_task(task);
+            }
+            return;
+        }
+        let (tasks, missed, next) = schedule.fire(now);
+        let name = schedule.name().to_string();
+        drop(schedule);
+        if let Some(next) = next {
+            _ = self
+                .schedule_timers
+                .lock()
+                .expect("lock failed")
+                .insert(next, schedule_id);
+        }
+        if missed > 0 {
+            _ = self.overruns.fetch_add(missed, Ordering::Release);
+            warn!("Schedule:{} missed {} runs !", schedule_id, missed);
+            for listener in self.task_listeners.read().expect("lock failed").iter() {
+                _ = crate::catch!(
+                    || listener.on_overrun(schedule_id, &name, missed),
+                    String::from("Listener on_overrun failed without message"),
+                    format!("{} invoke on_overrun", self.name())
+                );
+            }
+        }
+        for mut task in tasks {
+            self.register(&task);
+            // nobody joins the periodic tasks
+            _ = self.owner.no_waits.insert(task.id());
+            _ = self.owner.try_reserve(0);
+            task.enqueued(self.owner.clone());
+            self.submit_raw_task(task);
+        }
+    }
+
+    /// Submit the task if the task queue has room, otherwise apply the rejection policy.
+    ///
+    /// # Errors
+    /// if the task is rejected, the error kind is [`ErrorKind::WouldBlock`].
+    fn offer(&self, mut task: Task<'p>) -> std::io::Result<u64> {
+        let task_id = task.id();
+        self.register(&task);
+        let capacity = self.get_task_capacity();
+        if self.owner.try_reserve(capacity) {
+            task.enqueued(self.owner.clone());
+            self.submit_raw_task(task);
+            return Ok(task_id);
+        }
+        _ = self.rejections.fetch_add(1, Ordering::Release);
+        for listener in self.task_listeners.read().expect("lock failed").iter() {
+            _ = crate::catch!(
+                || listener.on_rejected(task_id, task.name()),
+                String::from("Listener on_rejected failed without message"),
+                format!("{} invoke on_rejected", self.name())
+            );
+        }
+        match self.get_rejection_policy() {
+            RejectionPolicy::Abort => {
+                warn!("Reject task:{} due to the task queue is full !", task_id);
+                _ = self.registry.remove(task_id);
+                _ = self.registry.timeouts.remove(&task_id);
+                Err(Error::new(
+                    ErrorKind::WouldBlock,
+                    "The task queue is full, the task is rejected !",
+                ))
+            }
+            RejectionPolicy::CallerRuns => {
+                self.run_task(task);
+                Ok(task_id)
+            }
+            RejectionPolicy::DiscardOldest => {
+                if self.discard_oldest() {
+                    // the discarded task still holds its slot until it's dequeued and skipped,
+                    // this one takes over the slot without reserving
+                    self.submit_raw_task(task);
+                } else {
+                    _ = self.owner.try_reserve(0);
+                    task.enqueued(self.owner.clone());
+                    self.submit_raw_task(task);
+                }
+                Ok(task_id)
+            }
+            RejectionPolicy::Block => {
+                while !self.owner.try_reserve(capacity) {
+                    if let Err(e) = self.check_running() {
+                        _ = self.registry.remove(task_id);
+                        _ = self.registry.timeouts.remove(&task_id);
+                        return Err(e);
+                    }
+                    // help to consume in coroutine, otherwise the worker may never get a chance
+                    if SchedulableCoroutine::current().is_none() || self.try_run().is_none() {
+                        self.owner.room.block(Duration::from_millis(10));
+                    }
+                }
+                task.enqueued(self.owner.clone());
+                self.submit_raw_task(task);
+                Ok(task_id)
+            }
+        }
+    }
+
+    /// Discard the earliest submitted task of this pool which is still queued, the queues are
+    /// exclusive to their threads, so the task is only marked as finished here and skipped when
+    /// it's dequeued.
+    ///
+    /// Returns `false` if there is no task to discard.
+    fn discard_oldest(&self) -> bool {
+        let mut queued: Vec<u64> = self
+            .registry
+            .tasks
+            .iter()
+            .filter(|entry| {
+                entry.0.state == TaskState::Queued
+                    && Arc::ptr_eq(&entry.1, &self.owner)
+                    && !self.schedules.contains_key(entry.key())
+            })
+            .map(|entry| *entry.key())
+            .collect();
+        queued.sort_unstable();
+        for task_id in queued {
+            // the task may be taken by a worker meanwhile, whoever changes its state first wins
+            if self
+                .registry
+                .tasks
+                .remove_if(&task_id, |_, entry| entry.0.state == TaskState::Queued)
+                .is_none()
+            {
+                continue;
+            }
+            warn!("Discard task:{} due to the task queue is full !", task_id);
+            _ = self.registry.timeouts.remove(&task_id);
+            self.owner.finish(task_id, Err("The task was discarded"));
+            return true;
+        }
+        false
+    }
+
+    /// Register the submitted task to the task registry and the timeouts.
+    fn register(&self, task: &Task<'p>) {
+        let task_id = task.id();
+        let info = TaskInfo {
+            id: task_id,
+            name: task.name().to_string(),
+            state: TaskState::Queued,
+            priority: task.priority(),
+        };
+        _ = self
+            .registry
+            .tasks
+            .insert(task_id, (info, self.owner.clone()));
+        if let Some((timeout, TimeoutFrom::Submit)) = task.timeout() {
+            _ = self
+                .registry
+                .timeouts
+                .insert(task_id, now().saturating_add(timeout));
+        }
+    }
+
+    fn check_running(&self) -> std::io::Result<()> {
+        match self.state() {
+            PoolState::Running => Ok(()),
+            PoolState::Stopping | PoolState::Stopped => {
+                Err(Error::other("The coroutine pool is stopping or stopped !"))
+            }
+        }
+    }
+
     /// Submit new task to this pool.
     ///
     /// Allow multiple threads to concurrently submit task to the pool,
@@ -280,7 +801,7 @@ impl<'p> CoroutinePool<'p> {
 
     /// Attempt to obtain task results with the given `task_id`.
     pub fn try_take_task_result(&self, task_id: u64) -> Option<Result<Option<usize>, &'p str>> {
-        self.results.remove(&task_id).map(|(_, r)| r)
+        self.owner.results.remove(&task_id).map(|(_, r)| r)
     }
 
     /// clean the task result data.
@@ -288,8 +809,14 @@ impl<'p> CoroutinePool<'p> {
         if self.try_take_task_result(task_id).is_some() {
             return;
         }
-        _ = self.no_waits.insert(task_id);
-        _ = CANCEL_TASKS.remove(&task_id);
+        // the task is still live, the result is discarded once it finishes
+        if self.registry.tasks.contains_key(&task_id) {
+            _ = self.owner.no_waits.insert(task_id);
+        } else {
+            // the cancel recorded after the task finished, the live task is still cancelled
+            // after its handle is dropped
+            _ = self.registry.cancels.remove(&task_id);
+        }
     }
 
     /// Use the given `task_id` to obtain task results, and if no results are found,
@@ -303,7 +830,7 @@ impl<'p> CoroutinePool<'p> {
         wait_time: Duration,
     ) -> std::io::Result<Result<Option<usize>, &str>> {
         if let Some(r) = self.try_take_task_result(task_id) {
-            self.notify(task_id);
+            self.owner.notify(task_id);
             return Ok(r);
         }
         if SchedulableCoroutine::current().is_some() {
@@ -318,13 +845,17 @@ impl<'p> CoroutinePool<'p> {
                 }
             }
         }
-        let arc = if let Some(arc) = self.waits.get(&task_id) {
-            arc.clone()
-        } else {
-            let arc = Arc::new((Mutex::new(true), Condvar::new()));
-            assert!(self.waits.insert(task_id, arc.clone()).is_none());
-            arc
-        };
+        let arc = self
+            .owner
+            .waits
+            .entry(task_id)
+            .or_insert_with(|| Arc::new((Mutex::new(true), Condvar::new())))
+            .clone();
+        // the task may be finished before the joiner is registered
+        if let Some(r) = self.try_take_task_result(task_id) {
+            self.owner.notify(task_id);
+            return Ok(r);
+        }
         let (lock, cvar) = &*arc;
         drop(
             cvar.wait_timeout_while(
@@ -335,7 +866,7 @@ impl<'p> CoroutinePool<'p> {
             .map_err(|e| Error::other(format!("{e}")))?,
         );
         if let Some(r) = self.try_take_task_result(task_id) {
-            self.notify(task_id);
+            self.owner.notify(task_id);
             return Ok(r);
         }
         Err(Error::new(ErrorKind::TimedOut, "wait timeout"))
@@ -410,8 +941,18 @@ impl<'p> CoroutinePool<'p> {
                 "The coroutine pool has reached its maximum size !",
             ));
         }
-        self.deref().submit_co(f, stack_size, priority).map(|_| {
-            _ = self.running.fetch_add(1, Ordering::Release);
+        let co = co!(
+            Some(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
+            f,
+            Some(stack_size.unwrap_or(self.stack_size())),
+            priority
+        )?;
+        // the coroutine may be migrated to and finished in other pools
+        _ = co.put(COROUTINE_OWNER, self.running.clone());
+        // count before submitting, the coroutine may finish before `submit_raw_co` returns
+        _ = self.running.fetch_add(1, Ordering::Release);
+        self.submit_raw_co(co).map(|_| ()).inspect_err(|_| {
+            _ = self.running.fetch_sub(1, Ordering::Release);
         })
     }
 
@@ -420,74 +961,309 @@ impl<'p> CoroutinePool<'p> {
     }
 
     fn try_run(&self) -> Option<()> {
-        self.task_queue.pop().map(|task| {
-            let task_id = task.id();
-            if CANCEL_TASKS.contains(&task_id) {
-                _ = CANCEL_TASKS.remove(&task_id);
-                warn!("Cancel task:{} successfully !", task_id);
+        self.task_queue.pop().map(|task| self.run_task(task))
+    }
+
+    fn run_task(&self, mut task: Task<'p>) {
+        task.dequeued();
+        let task_id = task.id();
+        if self.registry.cancels.remove(&task_id).is_some() {
+            if let Some((_, owner)) = self.registry.remove(task_id) {
+                _ = owner.no_waits.remove(&task_id);
+            }
+            _ = self.registry.timeouts.remove(&task_id);
+            warn!("Cancel task:{} successfully !", task_id);
+            return;
+        }
+        if let Some((_, TimeoutFrom::Submit)) = task.timeout() {
+            if !self.registry.timeouts.contains_key(&task_id) {
+                // timed out in the queue, already finished by `check_timeouts`
                 return;
             }
+        }
+        if !self.claim(task_id) {
+            // discarded or failed by the stopped pool
+            return;
+        }
+        let deadline = task.deadline();
+        if let Some(deadline) = deadline {
+            if now() > deadline {
+                self.deadline_missed(task_id, task.name(), deadline, false);
+                if task.deadline_miss_policy() == Some(DeadlineMissPolicy::Drop) {
+                    warn!("Drop task:{} due to deadline missed !", task_id);
+                    if self.untrack_timeout(&task) {
+                        self.finish(task_id, Err("The task missed its deadline"));
+                    }
+                    return;
+                }
+            }
+        }
+        let timeout = task.timeout();
+        if let Some((timeout, TimeoutFrom::Start)) = timeout {
+            _ = self
+                .registry
+                .timeouts
+                .insert(task_id, now().saturating_add(timeout));
+        }
+        // the priority of the coroutine before the task changes it
+        let previous = SchedulableCoroutine::current().and_then(|co| {
+            _ = self.registry.running.insert(task_id, co.id);
+            _ = co.put(COROUTINE_TASK, task_id);
+            co.priority()
+        });
+        _ = self.busy.fetch_add(1, Ordering::Release);
+        let start = now();
+        let result = self.attempt(&mut task);
+        _ = self.busy.fetch_sub(1, Ordering::Release);
+        _ = self.registry.running.remove(&task_id);
+        if let Some(co) = SchedulableCoroutine::current() {
+            _ = co.remove::<u64>(COROUTINE_TASK);
+        }
+        if self.registry.priorities.remove(&task_id).is_some() {
+            // the coroutine will run other tasks, restore its priority
             if let Some(co) = SchedulableCoroutine::current() {
-                _ = RUNNING_TASKS.insert(task_id, co.id);
+                _ = self.try_set_coroutine_priority(co.id, previous);
             }
-            let (_, result) = task.run();
-            _ = RUNNING_TASKS.remove(&task_id);
-            if self.no_waits.contains(&task_id) {
-                _ = self.no_waits.remove(&task_id);
-                return;
+        }
+        if timeout.is_some() && self.registry.timeouts.remove(&task_id).is_none() {
+            // timed out, already finished by `check_timeouts`
+            return;
+        }
+        if let Some(deadline) = deadline {
+            if start <= deadline && now() > deadline {
+                self.deadline_missed(task_id, task.name(), deadline, true);
             }
-            assert!(
-                self.results.insert(task_id, result).is_none(),
-                "The previous result was not retrieved in a timely manner"
+        }
+        self.finish(task_id, result);
+    }
+
+    /// Run the task until it succeeds or its retry policy gives up.
+    fn attempt(&self, task: &mut Task<'p>) -> Result<Option<usize>, &'p str> {
+        loop {
+            let result = task.attempt();
+            let retry_delay = task.retry_delay(&result);
+            for listener in self.task_listeners.read().expect("lock failed").iter() {
+                _ = crate::catch!(
+                    || listener.on_attempt(
+                        task.id(),
+                        task.name(),
+                        task.attempts(),
+                        &result,
+                        retry_delay
+                    ),
+                    String::from("Listener on_attempt failed without message"),
+                    format!("{} invoke on_attempt", self.name())
+                );
+            }
+            let Some(delay) = retry_delay else {
+                return result;
+            };
+            warn!(
+                "Retry task:{} in {:?} after {} attempts !",
+                task.id(),
+                delay,
+                task.attempts()
             );
-            self.notify(task_id);
-        })
+            if let Some(suspender) = SchedulableSuspender::current() {
+                // wait in the suspend queue, the worker thread keeps running other coroutines
+                suspender.delay(delay);
+            } else {
+                std::thread::sleep(delay);
+            }
+        }
+    }
+
+    /// Stop tracking the timeout of the task which is not run, returns `false` if the task has
+    /// timed out and been finished by `check_timeouts`.
+    fn untrack_timeout(&self, task: &Task<'p>) -> bool {
+        match task.timeout() {
+            Some((_, TimeoutFrom::Submit)) => self.registry.timeouts.remove(&task.id()).is_some(),
+            _ => true,
+        }
+    }
+
+    /// Cancel the tasks exceeded their max run time, the joiners get the timeout error.
+    ///
+    /// The running task is cancelled immediately by signal, the parked one is woken up and
+    /// cancelled by its scheduler. A CPU-bound task sharing the thread with this pool is
+    /// cancelled after it's preempted.
+    fn check_timeouts(&self) {
+        if self.registry.timeouts.is_empty() {
+            return;
+        }
+        let now = now();
+        let expired: Vec<u64> = self
+            .registry
+            .timeouts
+            .iter()
+            .filter(|entry| *entry.value() <= now)
+            .map(|entry| *entry.key())
+            .collect();
+        for task_id in expired {
+            // the task may be finished concurrently, whoever removes the entry finishes the task
+            if self
+                .registry
+                .timeouts
+                .remove_if(&task_id, |_, timeout_time| *timeout_time <= now)
+                .is_none()
+            {
+                continue;
+            }
+            _ = self.timeouts.fetch_add(1, Ordering::Release);
+            warn!("Cancel task:{} due to timeout !", task_id);
+            if let Some((_, co_id)) = self.registry.running.remove(&task_id) {
+                _ = self.registry.priorities.remove(&task_id);
+                Self::cancel_running_task(task_id, co_id);
+            }
+            self.finish(task_id, Err(TIMEOUT_ERROR));
+        }
+    }
+
+    fn finish(&self, task_id: u64, result: Result<Option<usize>, &'p str>) {
+        // the task may be stolen from the sibling pools, the result goes back to its owner
+        if let Some((_, owner)) = self.registry.remove(task_id) {
+            owner.finish(task_id, result);
+        }
+    }
+
+    fn deadline_missed(&self, task_id: u64, task_name: &str, deadline: u64, started: bool) {
+        _ = self.deadline_misses.fetch_add(1, Ordering::Release);
+        for listener in self.task_listeners.read().expect("lock failed").iter() {
+            _ = crate::catch!(
+                || listener.on_deadline_missed(task_id, task_name, deadline, started),
+                String::from("Listener on_deadline_missed failed without message"),
+                format!("{} invoke on_deadline_missed", self.name())
+            );
+        }
+    }
+
+    /// Try to change the priority of a task. The queued task is re-sorted immediately, the
+    /// running task changes the priority of its coroutine, which takes effect the next time the
+    /// coroutine is pushed to the ready queue.
+    ///
+    /// Returns `false` if the task is not found, it may be finished, not submitted yet or
+    /// waiting for its deadline.
+    pub fn try_set_task_priority(&self, task_id: u64, priority: Option<c_longlong>) -> bool {
+        if let Some(co_id) = self.registry.running.get(&task_id).map(|co_id| *co_id) {
+            _ = self.registry.priorities.insert(task_id);
+            _ = self.try_set_coroutine_priority(co_id, priority);
+            if let Some(mut entry) = self.registry.tasks.get_mut(&task_id) {
+                entry.0.priority = priority;
+            }
+            return true;
+        }
+        // only the band of the task is searched
+        let band = self
+            .registry
+            .tasks
+            .get(&task_id)
+            .map(|entry| entry.0.priority.unwrap_or(DEFAULT_PRECEDENCE));
+        let mut found = false;
+        _ = self.task_queue.reprioritize(band, |task| {
+            if task.id() != task_id {
+                return None;
+            }
+            found = true;
+            task.set_priority(priority);
+            Some(priority.unwrap_or(DEFAULT_PRECEDENCE))
+        });
+        if found {
+            if let Some(mut entry) = self.registry.tasks.get_mut(&task_id) {
+                entry.0.priority = priority;
+            }
+        }
+        found
+    }
+
+    /// Change the state of the queued task to running, fails if the task is finished already.
+    fn claim(&self, task_id: u64) -> bool {
+        let Some(mut entry) = self.registry.tasks.get_mut(&task_id) else {
+            return false;
+        };
+        entry.0.state = TaskState::Running;
+        true
+    }
+
+    pub(crate) fn set_task_state(&self, task_id: u64, state: TaskState) {
+        if let Some(mut entry) = self.registry.tasks.get_mut(&task_id) {
+            entry.0.state = state;
+        }
+    }
+
+    /// Get the live task of the runtime by id, the task is removed after it's finished.
+    pub fn get_task(&self, task_id: u64) -> Option<TaskInfo> {
+        self.registry
+            .tasks
+            .get(&task_id)
+            .map(|entry| entry.0.clone())
+    }
+
+    /// Find the live tasks of the runtime by name, ordered by id.
+    pub fn find_tasks(&self, name: &str) -> Vec<TaskInfo> {
+        let mut tasks: Vec<TaskInfo> = self
+            .registry
+            .tasks
+            .iter()
+            .filter(|entry| entry.0.name == name)
+            .map(|entry| entry.0.clone())
+            .collect();
+        tasks.sort_by_key(|info| info.id);
+        tasks
     }
 
-    fn notify(&self, task_id: u64) {
-        if let Some((_, arc)) = self.waits.remove(&task_id) {
-            let (lock, cvar) = &*arc;
-            let mut pending = lock.lock().expect("notify task failed");
-            *pending = false;
-            cvar.notify_one();
+    /// Try to cancel the live tasks of the runtime with the name, returns the number of
+    /// cancelling tasks.
+    #[must_use]
+    pub fn try_cancel_tasks(&self, name: &str) -> usize {
+        let tasks = self.find_tasks(name);
+        for info in &tasks {
+            self.try_cancel_task(info.id);
         }
+        tasks.len()
     }
 
-    /// Try to cancel a task.
-    pub fn try_cancel_task(task_id: u64) {
+    /// Try to cancel a task of the runtime.
+    pub fn try_cancel_task(&self, task_id: u64) {
         // 检查正在运行的任务是否是要取消的任务
-        if let Some(info) = RUNNING_TASKS.get(&task_id) {
-            let co_name = *info;
-            // todo windows support
-            #[allow(unused_variables)]
-            if let Some(pthread) = Scheduler::get_scheduling_thread(co_name) {
-                // 发送SIGVTALRM信号，在运行时取消任务
-                #[cfg(unix)]
-                if nix::sys::pthread::pthread_kill(pthread, nix::sys::signal::Signal::SIGVTALRM)
-                    .is_ok()
-                {
-                    warn!(
-                        "Attempt to cancel task:{} running on coroutine:{} by thread:{}, cancelling...",
-                        task_id, co_name, pthread
-                    );
-                } else {
-                    error!(
-                        "Attempt to cancel task:{} running on coroutine:{} by thread:{} failed !",
-                        task_id, co_name, pthread
-                    );
-                }
-            } else {
-                // 添加到待取消队列
-                Scheduler::try_cancel_coroutine(co_name);
+        if let Some(co_name) = self.registry.running.get(&task_id).map(|co_id| *co_id) {
+            Self::cancel_running_task(task_id, co_name);
+        } else if self.registry.tasks.contains_key(&task_id) {
+            // 添加到待取消队列
+            _ = self.registry.cancels.insert(task_id);
+            // the task may be finished before the cancel is recorded, nobody takes it then
+            if !self.registry.tasks.contains_key(&task_id) {
+                _ = self.registry.cancels.remove(&task_id);
+            }
+            warn!("Attempt to cancel task:{}, cancelling...", task_id);
+        }
+    }
+
+    // the task id is only used by the log macros, they may compile to nothing
+    #[allow(unused_variables)]
+    fn cancel_running_task(task_id: u64, co_name: u64) {
+        // todo windows support
+        if let Some(pthread) = Scheduler::get_scheduling_thread(co_name) {
+            // 发送SIGVTALRM信号，在运行时取消任务
+            #[cfg(unix)]
+            if nix::sys::pthread::pthread_kill(pthread, nix::sys::signal::Signal::SIGVTALRM).is_ok()
+            {
                 warn!(
-                    "Attempt to cancel task:{} running on coroutine:{}, cancelling...",
-                    task_id, co_name
+                    "Attempt to cancel task:{} running on coroutine:{} by thread:{}, cancelling...",
+                    task_id, co_name, pthread
+                );
+            } else {
+                error!(
+                    "Attempt to cancel task:{} running on coroutine:{} by thread:{} failed !",
+                    task_id, co_name, pthread
                 );
             }
         } else {
-            // 添加到待取消队列
-            _ = CANCEL_TASKS.insert(task_id);
-            warn!("Attempt to cancel task:{}, cancelling...", task_id);
+            // 添加到待取消队列，由调度器唤醒并取消
+            Scheduler::try_cancel_coroutine(co_name);
+            warn!(
+                "Attempt to cancel task:{} running on coroutine:{}, cancelling...",
+                task_id, co_name
+            );
         }
     }
 
@@ -529,6 +1305,8 @@ impl<'p> CoroutinePool<'p> {
             }
             PoolState::Stopped => return Err(Error::other("The coroutine pool is stopped !")),
         }
+        self.check_timeouts();
+        self.check_schedules();
         Self::init_current(self);
         let r = self.try_timeout_schedule(timeout_time);
         Self::clean_current();
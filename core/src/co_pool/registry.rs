@@ -0,0 +1,114 @@
+use crate::co_pool::task::TaskInfo;
+use crate::common::CondvarBlocker;
+use dashmap::{DashMap, DashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// The live tasks of a runtime. The pools of a runtime steal tasks from each other, so they
+/// share the registry, while the pools of different runtimes never see each other's tasks.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub(crate) struct TaskRegistry<'r> {
+    /// `task_id` -> the live task and the pool submitted it, removed after the task is finished
+    pub(crate) tasks: DashMap<u64, (TaskInfo, Arc<TaskOwner<'r>>)>,
+    /// `task_id` -> the timestamp in ns the task times out, removed by whoever finishes the task
+    /// first
+    pub(crate) timeouts: DashMap<u64, u64>,
+    /// the queued tasks to cancel
+    pub(crate) cancels: DashSet<u64>,
+    /// `task_id` -> `co_id`
+    pub(crate) running: DashMap<u64, u64>,
+    /// the running tasks which changed the priorities of their coroutines
+    pub(crate) priorities: DashSet<u64>,
+}
+
+impl<'r> TaskRegistry<'r> {
+    /// Remove the finished or discarded task with its pending cancel.
+    pub(crate) fn remove(&self, task_id: u64) -> Option<(TaskInfo, Arc<TaskOwner<'r>>)> {
+        _ = self.cancels.remove(&task_id);
+        self.tasks.remove(&task_id).map(|(_, task)| task)
+    }
+}
+
+/// The results of the tasks submitted by a pool, the tasks may be stolen and finished by the
+/// sibling pools, but their results always go back to the submitting pool.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub(crate) struct TaskOwner<'o> {
+    /// the number of slots reserved by the queued tasks of the pool
+    pub(crate) queued: AtomicUsize,
+    /// notified when a slot is released
+    pub(crate) room: CondvarBlocker,
+    /// the joiners waiting for the results
+    pub(crate) waits: DashMap<u64, Arc<(Mutex<bool>, Condvar)>>,
+    /// the results not taken yet
+    pub(crate) results: DashMap<u64, Result<Option<usize>, &'o str>>,
+    /// the tasks nobody joins, their results are discarded
+    pub(crate) no_waits: DashSet<u64>,
+}
+
+impl<'o> TaskOwner<'o> {
+    /// Reserve a slot for the task to queue, fails if `capacity` slots are reserved already,
+    /// 0 means unbounded.
+    pub(crate) fn try_reserve(&self, capacity: usize) -> bool {
+        self.queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (capacity == 0 || queued < capacity).then_some(queued + 1)
+            })
+            .is_ok()
+    }
+
+    /// Release the slot of the dequeued task, and wake up a blocked submitter.
+    pub(crate) fn release(&self) {
+        _ = self.queued.fetch_sub(1, Ordering::AcqRel);
+        self.room.notify();
+    }
+
+    /// Save the result of the task, and notify the joiner.
+    pub(crate) fn finish(&self, task_id: u64, result: Result<Option<usize>, &'o str>) {
+        if self.no_waits.remove(&task_id).is_some() {
+            return;
+        }
+        assert!(
+            self.results.insert(task_id, result).is_none(),
+            "The previous result was not retrieved in a timely manner"
+        );
+        self.notify(task_id);
+    }
+
+    /// Wake up the joiner of the task.
+    pub(crate) fn notify(&self, task_id: u64) {
+        if let Some((_, arc)) = self.waits.remove(&task_id) {
+            let (lock, cvar) = &*arc;
+            let mut pending = lock.lock().expect("notify task failed");
+            *pending = false;
+            cvar.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::co_pool::CoroutinePool;
+
+    #[test]
+    fn test_cancel_live_tasks_only() -> std::io::Result<()> {
+        let mut pool = CoroutinePool::default();
+        pool.try_cancel_task(u64::MAX);
+        assert!(pool.registry.cancels.is_empty());
+        let task_id = pool.submit_task(None, |_| Some(1), None, None)?;
+        pool.try_cancel_task(task_id);
+        assert!(pool.registry.cancels.contains(&task_id));
+        // dropping the handle doesn't revoke the cancel
+        pool.clean_task_result(task_id);
+        assert!(pool.registry.cancels.contains(&task_id));
+        pool.try_schedule_task()?;
+        assert!(pool.registry.cancels.is_empty());
+        assert!(pool.registry.tasks.is_empty());
+        assert!(pool.try_take_task_result(task_id).is_none());
+        // the finished task is never cancelled
+        pool.try_cancel_task(task_id);
+        assert!(pool.registry.cancels.is_empty());
+        Ok(())
+    }
+}
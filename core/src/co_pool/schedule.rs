@@ -0,0 +1,238 @@
+use crate::co_pool::task::Task;
+use crate::common::now;
+use std::ffi::c_longlong;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// What to do when a fixed-rate schedule falls behind, because the previous run took longer
+/// than the period or the pool was too busy.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum OverrunPolicy {
+    /// Skip the missed runs, and the run while the previous one is still running.
+    #[default]
+    Skip,
+    /// Run the missed runs back-to-back.
+    CatchUp,
+}
+
+/// How a periodic task is repeated.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Period {
+    /// Start a run every `period` ns.
+    FixedRate(u64, OverrunPolicy),
+    /// Start the next run `delay` ns after the previous run finished.
+    FixedDelay(u64),
+}
+
+// the state shared between a periodic schedule and its running task
+#[derive(Debug, Default)]
+pub(crate) struct RunState {
+    running: AtomicBool,
+    // the timestamp in ns the latest run finished, 0 if it's running or never ran
+    finished: AtomicU64,
+}
+
+// mark the run finished even if the task panics
+struct RunGuard(Arc<RunState>);
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.0.finished.store(now().max(1), Ordering::Release);
+        self.0.running.store(false, Ordering::Release);
+    }
+}
+
+/// A periodic task, each run is a new task.
+#[derive(educe::Educe)]
+#[educe(Debug)]
+pub(crate) struct Periodic<'s> {
+    name: String,
+    #[educe(Debug(ignore))]
+    func: Arc<dyn Fn(Option<usize>) -> Option<usize> + 's>,
+    param: Option<usize>,
+    priority: Option<c_longlong>,
+    period: Period,
+    // the timestamp in ns of the next run
+    next: u64,
+    state: Arc<RunState>,
+}
+
+/// A delayed or periodic task waiting in the timers of a pool.
+#[derive(Debug)]
+pub(crate) enum Schedule<'s> {
+    /// Run the task once when the timer expires.
+    Delayed(Task<'s>),
+    /// Create a new task for each run.
+    Periodic(Periodic<'s>),
+}
+
+impl<'s> Schedule<'s> {
+    /// Create a new periodic schedule, the first run starts at `first` in ns.
+    pub(crate) fn periodic(
+        name: String,
+        func: impl Fn(Option<usize>) -> Option<usize> + 's,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        period: Period,
+        first: u64,
+    ) -> Self {
+        Schedule::Periodic(Periodic {
+            name,
+            func: Arc::new(func),
+            param,
+            priority,
+            period,
+            next: first,
+            state: Arc::default(),
+        })
+    }
+
+    /// Allocate an id for the periodic schedule, the delayed task uses its task id.
+    pub(crate) fn next_id() -> u64 {
+        // shared with the task ids, so the delayed and periodic schedules never collide
+        Task::next_id()
+    }
+
+    /// get the task name.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Schedule::Delayed(task) => task.name(),
+            Schedule::Periodic(periodic) => &periodic.name,
+        }
+    }
+
+    /// Returns the timestamp in ns of the next run once the previous run of the fixed-delay
+    /// schedule finished, otherwise `None`.
+    pub(crate) fn rearm(&self) -> Option<u64> {
+        let Schedule::Periodic(Periodic {
+            period: Period::FixedDelay(delay),
+            state,
+            ..
+        }) = self
+        else {
+            return None;
+        };
+        let finished = state.finished.swap(0, Ordering::AcqRel);
+        (finished != 0).then(|| finished.saturating_add(*delay))
+    }
+
+    /// Fire the periodic schedule at `now`, returns the tasks to run, the number of missed runs
+    /// and the timestamp in ns of the next run of the fixed-rate schedule.
+    pub(crate) fn fire(&mut self, now: u64) -> (Vec<Task<'s>>, u64, Option<u64>) {
+        let Schedule::Periodic(Periodic {
+            name,
+            func,
+            param,
+            priority,
+            period,
+            next,
+            state,
+        }) = self
+        else {
+            return (Vec::new(), 0, None);
+        };
+        let (runs, missed) = match *period {
+            Period::FixedRate(period, policy) => {
+                let period = period.max(1);
+                let behind = now.saturating_sub(*next) / period;
+                *next = next.saturating_add(period.saturating_mul(behind.saturating_add(1)));
+                let running = state.running.load(Ordering::Acquire);
+                match policy {
+                    OverrunPolicy::Skip if running => (0, behind.saturating_add(1)),
+                    OverrunPolicy::Skip => (1, behind),
+                    OverrunPolicy::CatchUp => (behind.saturating_add(1), behind),
+                }
+            }
+            Period::FixedDelay(_) => (1, 0),
+        };
+        let tasks = (0..runs)
+            .map(|_| {
+                let func = func.clone();
+                let guard = RunGuard(state.clone());
+                state.running.store(true, Ordering::Release);
+                Task::new(
+                    name.clone(),
+                    move |param| {
+                        let guard = guard;
+                        let result = func(param);
+                        drop(guard);
+                        result
+                    },
+                    *param,
+                    *priority,
+                )
+            })
+            .collect();
+        let next = match period {
+            Period::FixedRate(_, _) => Some(*next),
+            Period::FixedDelay(_) => None,
+        };
+        (tasks, missed, next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_rate() {
+        let mut schedule = Schedule::periodic(
+            String::from("test"),
+            |p| p,
+            None,
+            None,
+            Period::FixedRate(10, OverrunPolicy::Skip),
+            100,
+        );
+        let (tasks, missed, next) = schedule.fire(100);
+        assert_eq!((1, 0, Some(110)), (tasks.len(), missed, next));
+        // the previous run is still running
+        let (skipped, missed, next) = schedule.fire(135);
+        assert_eq!((0, 3, Some(140)), (skipped.len(), missed, next));
+        for task in tasks {
+            _ = task.run();
+        }
+        let mut schedule = Schedule::periodic(
+            String::from("test"),
+            |p| p,
+            None,
+            None,
+            Period::FixedRate(10, OverrunPolicy::CatchUp),
+            100,
+        );
+        let (tasks, missed, next) = schedule.fire(125);
+        assert_eq!((3, 2, Some(130)), (tasks.len(), missed, next));
+    }
+
+    #[test]
+    fn test_next_id() {
+        let task = Task::new(String::from("test"), |p| p, None, None);
+        let schedule_id = Schedule::next_id();
+        assert_ne!(task.id(), schedule_id);
+        assert!(Task::new(String::from("test"), |p| p, None, None).id() > schedule_id);
+    }
+
+    #[test]
+    fn test_fixed_delay() {
+        let mut schedule = Schedule::periodic(
+            String::from("test"),
+            |p| p,
+            None,
+            None,
+            Period::FixedDelay(10),
+            0,
+        );
+        let (tasks, _, next) = schedule.fire(100);
+        assert_eq!(None, next);
+        assert_eq!(None, schedule.rearm());
+        for task in tasks {
+            _ = task.run();
+        }
+        let next = schedule.rearm().expect("the run should be finished");
+        assert!(next >= 10);
+        assert_eq!(None, schedule.rearm());
+    }
+}
@@ -1,11 +1,245 @@
-use crate::catch;
+use crate::co_pool::registry::TaskOwner;
+use crate::common::constants::SyscallName;
 use crate::common::ordered_work_steal::Ordered;
-use std::ffi::c_longlong;
-use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::scheduler::SchedulableCoroutine;
+use crate::{catch, error};
+use rand::RngExt;
+use std::ffi::{c_char, c_int, c_longlong};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// 做C兼容时会用到
 pub type UserTaskFunc = extern "C" fn(usize) -> usize;
 
+/// Free the param of a [`UserTaskFunc`] which is dropped without running, such as the
+/// cancelled tasks or the tasks missed their deadlines.
+pub type UserTaskFree = extern "C" fn(usize);
+
+/// What to do with a task whose deadline has passed before it starts.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DeadlineMissPolicy {
+    /// Drop the task without running it, the joiner gets an error.
+    #[default]
+    Drop,
+    /// Run the task anyway.
+    Run,
+}
+
+/// What to do with a submitted task when the task queue of the pool is full.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RejectionPolicy {
+    /// Reject the task, the submitter gets an error.
+    #[default]
+    Abort,
+    /// Run the task in the submitter immediately.
+    CallerRuns,
+    /// Discard the task at the head of the queue to make room, its joiner gets an error.
+    DiscardOldest,
+    /// Block the submitter until the queue has room or the pool is stopping.
+    Block,
+}
+
+/// The error the joiner gets if the task is cancelled due to timeout.
+pub const TIMEOUT_ERROR: &str = "The task timed out";
+
+/// Where the max run time of a task is measured from.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum TimeoutFrom {
+    /// Measured from the time the task starts running.
+    #[default]
+    Start,
+    /// Measured from the time the task is submitted, the time waiting in the queue counts.
+    Submit,
+}
+
+/// How long to wait before retrying a failed task.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Backoff {
+    /// Wait the same time in ns before each retry.
+    Fixed(u64),
+    /// Wait `initial` ns before the first retry and double it each time, up to `max` ns.
+    Exponential {
+        /// The wait time in ns before the first retry.
+        initial: u64,
+        /// The max wait time in ns.
+        max: u64,
+    },
+}
+
+/// Decide whether and when a failed attempt of a task is retried.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    jitter: bool,
+    retry_if: fn(&Result<Option<usize>, &str>) -> bool,
+}
+
+impl RetryPolicy {
+    /// Retry the panicked task until it has run `max_attempts` times.
+    #[must_use]
+    pub fn new(max_attempts: u32, backoff: Backoff) -> Self {
+        fn failed(result: &Result<Option<usize>, &str>) -> bool {
+            result.is_err()
+        }
+        RetryPolicy {
+            max_attempts,
+            backoff,
+            jitter: false,
+            retry_if: failed,
+        }
+    }
+
+    /// Randomize the wait time between the half and the full backoff, so the failed tasks
+    /// don't retry at the same time.
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Retry the attempt only if the `retry_if` returns `true` for its result, for example,
+    /// to retry the tasks returning an error marker.
+    #[must_use]
+    pub fn with_retry_if(mut self, retry_if: fn(&Result<Option<usize>, &str>) -> bool) -> Self {
+        self.retry_if = retry_if;
+        self
+    }
+
+    /// get the max number of attempts, including the first run.
+    #[must_use]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// get the backoff between attempts.
+    #[must_use]
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// get the wait time before the retry after the `attempt`th attempt, starts from 1.
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => 2u64
+                .checked_pow(attempt.saturating_sub(1))
+                .map_or(u64::MAX, |factor| initial.saturating_mul(factor))
+                .min(max),
+        };
+        if self.jitter && delay > 1 {
+            let half = delay / 2;
+            return Duration::from_nanos(half + rand::rng().random_range(0..=delay - half));
+        }
+        Duration::from_nanos(delay)
+    }
+}
+
+/// The state of a submitted task.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TaskState {
+    /// The task is waiting in the task queue.
+    Queued,
+    /// The task is running.
+    Running,
+    /// The coroutine running the task is suspended.
+    Suspended,
+    /// The coroutine running the task is in the syscall.
+    Syscall(SyscallName),
+}
+
+/// The information of a live task.
+#[repr(C)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TaskInfo {
+    /// The runtime-unique id of the task.
+    pub id: u64,
+    /// The name of the task, it may be shared by multiple tasks.
+    pub name: String,
+    /// The state of the task.
+    pub state: TaskState,
+    /// The priority of the task.
+    pub priority: Option<c_longlong>,
+}
+
+impl TaskInfo {
+    /// Copy this info to the C compatible `raw` info, the name is truncated if the buffer is
+    /// too small, but `name_len` is always the byte length of the whole name.
+    ///
+    /// # Safety
+    /// The `name` buffer of `raw` must be null or valid for `name_capacity` bytes.
+    pub unsafe fn write_raw(&self, raw: &mut RawTaskInfo) {
+        raw.id = self.id;
+        raw.state = self.state;
+        raw.has_priority = self.priority.is_some();
+        raw.priority = self.priority.unwrap_or_default();
+        raw.name_len = self.name.len();
+        if !raw.name.is_null() {
+            let len = self.name.len().min(raw.name_capacity);
+            std::ptr::copy_nonoverlapping(self.name.as_ptr(), raw.name.cast::<u8>(), len);
+        }
+    }
+
+    /// Read the info filled by `f`, `f` is called again with a larger name buffer if the name
+    /// is truncated. Returns `None` if `f` fails.
+    pub fn read_raw(mut f: impl FnMut(&mut RawTaskInfo) -> c_int) -> Option<Self> {
+        let mut name: Vec<u8> = Vec::with_capacity(64);
+        loop {
+            let mut raw = RawTaskInfo {
+                id: 0,
+                state: TaskState::Queued,
+                has_priority: false,
+                priority: 0,
+                name: name.as_mut_ptr().cast::<c_char>(),
+                name_capacity: name.capacity(),
+                name_len: 0,
+            };
+            if 0 != f(&mut raw) {
+                return None;
+            }
+            if raw.name_len > name.capacity() {
+                name.reserve(raw.name_len);
+                continue;
+            }
+            unsafe { name.set_len(raw.name_len) };
+            return Some(TaskInfo {
+                id: raw.id,
+                name: String::from_utf8_lossy(&name).into_owned(),
+                state: raw.state,
+                priority: raw.has_priority.then_some(raw.priority),
+            });
+        }
+    }
+}
+
+/// The C compatible [`TaskInfo`], the name is copied to the buffer provided by the caller.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawTaskInfo {
+    /// The runtime-unique id of the task.
+    pub id: u64,
+    /// The state of the task.
+    pub state: TaskState,
+    /// Whether the task has a priority.
+    pub has_priority: bool,
+    /// The priority of the task, ignored if `has_priority` is `false`.
+    pub priority: c_longlong,
+    /// The buffer of the name, the name is not copied if it's null.
+    pub name: *mut c_char,
+    /// The capacity of the name buffer in bytes.
+    pub name_capacity: usize,
+    /// The byte length of the whole name, without the trailing nul.
+    pub name_len: usize,
+}
+
 /// The task impls.
 #[repr(C)]
 #[derive(educe::Educe)]
@@ -14,9 +248,16 @@ pub struct Task<'t> {
     id: u64,
     name: String,
     #[educe(Debug(ignore))]
-    func: Box<dyn FnOnce(Option<usize>) -> Option<usize> + 't>,
+    func: Box<dyn FnMut(Option<usize>) -> Option<usize> + 't>,
     param: Option<usize>,
     priority: Option<c_longlong>,
+    deadline: Option<(u64, DeadlineMissPolicy)>,
+    timeout: Option<(u64, TimeoutFrom)>,
+    retry: Option<RetryPolicy>,
+    attempts: u32,
+    stack_size: Option<usize>,
+    // the submitting pool, set while the task is queued
+    owner: Option<Arc<TaskOwner<'t>>>,
 }
 
 impl<'t> Task<'t> {
@@ -27,18 +268,136 @@ impl<'t> Task<'t> {
         param: Option<usize>,
         priority: Option<c_longlong>,
     ) -> Self {
-        let mut hasher = DefaultHasher::new();
-        name.hash(&mut hasher);
-        let id = hasher.finish();
+        let mut func = Some(func);
+        Self::build(
+            name,
+            Box::new(move |param| (func.take().expect("the task can only run once"))(param)),
+            param,
+            priority,
+        )
+    }
+
+    /// Create a new `Task` instance which is retried by the `policy` if its attempt fails.
+    pub fn retryable(
+        name: String,
+        func: impl Fn(Option<usize>) -> Option<usize> + 't,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        policy: RetryPolicy,
+    ) -> Self {
+        let mut task = Self::build(name, Box::new(func), param, priority);
+        task.retry = Some(policy);
+        task
+    }
+
+    /// Allocate an id, the schedules share the id space with the tasks.
+    pub(crate) fn next_id() -> u64 {
+        // 0 is reserved for the invalid task
+        static TASK_ID: AtomicU64 = AtomicU64::new(1);
+        TASK_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn build(
+        name: String,
+        func: Box<dyn FnMut(Option<usize>) -> Option<usize> + 't>,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+    ) -> Self {
         Task {
-            id,
+            id: Self::next_id(),
             name,
-            func: Box::new(func),
+            func,
             param,
             priority,
+            deadline: None,
+            timeout: None,
+            retry: None,
+            attempts: 0,
+            stack_size: None,
+            owner: None,
         }
     }
 
+    /// Set the absolute deadline in ns of this task, the timestamp is based on
+    /// [`crate::common::now`].
+    #[must_use]
+    pub fn with_deadline(mut self, deadline: u64, miss_policy: DeadlineMissPolicy) -> Self {
+        self.deadline = Some((deadline, miss_policy));
+        self
+    }
+
+    /// get the task deadline miss policy, returns `None` if the task has no deadline.
+    #[must_use]
+    pub fn deadline_miss_policy(&self) -> Option<DeadlineMissPolicy> {
+        self.deadline.map(|(_, miss_policy)| miss_policy)
+    }
+
+    /// Set the max run time in ns of this task, the task is cancelled by the runtime once it's
+    /// exceeded.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: u64, from: TimeoutFrom) -> Self {
+        self.timeout = Some((timeout, from));
+        self
+    }
+
+    /// get the max run time in ns of this task, returns `None` if the task has no timeout.
+    #[must_use]
+    pub fn timeout(&self) -> Option<(u64, TimeoutFrom)> {
+        self.timeout
+    }
+
+    /// Set the min stack size of this task, the task runs on a new stack with `stack_size` bytes
+    /// if the stack of the running coroutine doesn't have enough room, see
+    /// [`crate::coroutine::Coroutine::maybe_grow_with`].
+    #[must_use]
+    pub fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// get the min stack size of this task, returns `None` if the task uses the stack of the
+    /// running coroutine.
+    #[must_use]
+    pub fn stack_size(&self) -> Option<usize> {
+        self.stack_size
+    }
+
+    /// Change the priority of this task, it takes effect the next time this task is pushed to
+    /// an ordered queue.
+    pub fn set_priority(&mut self, priority: Option<c_longlong>) {
+        self.priority = priority;
+    }
+
+    /// Hold the slot reserved from the submitting pool until this task is dequeued.
+    pub(crate) fn enqueued(&mut self, owner: Arc<TaskOwner<'t>>) {
+        self.owner = Some(owner);
+    }
+
+    /// This task is taken from the queue, release its slot.
+    pub(crate) fn dequeued(&mut self) {
+        if let Some(owner) = self.owner.take() {
+            owner.release();
+        }
+    }
+
+    /// get the number of attempts the task has run.
+    #[must_use]
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// get the wait time before retrying the task with the `result` of the latest attempt,
+    /// returns `None` if the task should not be retried.
+    #[must_use]
+    pub fn retry_delay(&self, result: &Result<Option<usize>, &str>) -> Option<Duration> {
+        let policy = self.retry?;
+        // the predicate is only called if the task has attempts left
+        if self.attempts >= policy.max_attempts() || !(policy.retry_if)(result) {
+            return None;
+        }
+        Some(policy.delay(self.attempts))
+    }
+
     /// get the task name.
     #[must_use]
     pub fn name(&self) -> &str {
@@ -55,15 +414,34 @@ impl<'t> Task<'t> {
     ///
     /// # Errors
     /// if an exception occurred while executing this task.
-    pub fn run<'e>(self) -> (String, Result<Option<usize>, &'e str>) {
-        (
-            self.name.clone(),
-            catch!(
-                || (self.func)(self.param),
-                format!("task {} failed without message", self.name),
-                format!("task {}", self.name)
-            ),
-        )
+    pub fn run<'e>(mut self) -> (String, Result<Option<usize>, &'e str>) {
+        let result = self.attempt();
+        (self.name, result)
+    }
+
+    /// execute the task once, only the retryable task can be executed again.
+    ///
+    /// # Errors
+    /// if an exception occurred while executing this task.
+    pub fn attempt<'e>(&mut self) -> Result<Option<usize>, &'e str> {
+        self.attempts += 1;
+        let param = self.param;
+        let func = &mut self.func;
+        catch!(
+            || match self.stack_size {
+                Some(stack_size) => {
+                    SchedulableCoroutine::maybe_grow_with(stack_size, stack_size, || func(param))
+                }
+                None => Ok(func(param)),
+            },
+            format!("task {} failed without message", self.name),
+            format!("task {}", self.name)
+        )?
+        .map_err(|e| {
+            let message: &'e str = format!("task {} grow stack failed: {e}", self.name).leak();
+            error!("{}", message);
+            message
+        })
     }
 }
 
@@ -71,11 +449,16 @@ impl Ordered for Task<'_> {
     fn priority(&self) -> Option<c_longlong> {
         self.priority
     }
+
+    fn deadline(&self) -> Option<u64> {
+        self.deadline.map(|(deadline, _)| deadline)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::co_pool::task::Task;
+    use crate::co_pool::task::{Backoff, RetryPolicy, Task};
+    use std::time::Duration;
 
     #[test]
     fn test() {
@@ -91,6 +474,88 @@ mod tests {
         assert_eq!((String::from("test"), Ok(None)), task.run());
     }
 
+    #[test]
+    fn test_stack_size() {
+        let task = Task::new(
+            String::from("test"),
+            |p| {
+                let stack = psm::stack_pointer() as usize;
+                // the task already runs on a stack big enough, no more stack is allocated
+                let inner = crate::scheduler::SchedulableCoroutine::maybe_grow_with(
+                    1024 * 1024,
+                    1024 * 1024,
+                    || psm::stack_pointer() as usize,
+                )
+                .expect("grow stack failed");
+                assert!(stack.abs_diff(inner) < 64 * 1024);
+                p
+            },
+            Some(1),
+            None,
+        )
+        .with_stack_size(2 * 1024 * 1024);
+        assert_eq!(Some(2 * 1024 * 1024), task.stack_size());
+        assert_eq!((String::from("test"), Ok(Some(1))), task.run());
+    }
+
+    #[test]
+    fn test_unique_id() {
+        let first = Task::new(String::from("same"), |p| p, None, None);
+        let second = Task::new(String::from("same"), |p| p, None, None);
+        assert_ne!(0, first.id());
+        assert_ne!(first.id(), second.id());
+        assert_eq!(first.name(), second.name());
+    }
+
+    #[test]
+    fn test_retry() {
+        let policy = RetryPolicy::new(
+            3,
+            Backoff::Exponential {
+                initial: 10,
+                max: 30,
+            },
+        );
+        assert_eq!(Duration::from_nanos(10), policy.delay(1));
+        assert_eq!(Duration::from_nanos(20), policy.delay(2));
+        assert_eq!(Duration::from_nanos(30), policy.delay(3));
+        assert_eq!(Duration::from_nanos(30), policy.delay(u32::MAX));
+        let jittered = policy.with_jitter(true).delay(2);
+        assert!(Duration::from_nanos(10) <= jittered && jittered <= Duration::from_nanos(20));
+        let mut task = Task::retryable(
+            String::from("test"),
+            |_| panic!("test panic, just ignore it"),
+            None,
+            None,
+            policy,
+        );
+        let mut delays = Vec::new();
+        loop {
+            let result = task.attempt();
+            match task.retry_delay(&result) {
+                Some(delay) => delays.push(delay),
+                None => break,
+            }
+        }
+        assert_eq!(3, task.attempts());
+        assert_eq!(
+            vec![Duration::from_nanos(10), Duration::from_nanos(20)],
+            delays
+        );
+        let task = Task::retryable(
+            String::from("test"),
+            |_| Some(0),
+            None,
+            None,
+            policy.with_retry_if(|result| *result == Ok(Some(0))),
+        );
+        assert_eq!(
+            Some(Duration::from_nanos(10)),
+            task.retry_delay(&Ok(Some(0)))
+        );
+        assert_eq!(None, task.retry_delay(&Err("other")));
+    }
+
     #[test]
     fn test_panic() {
         let task = Task::new(
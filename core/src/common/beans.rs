@@ -43,6 +43,12 @@ impl BeanFactory<'_> {
             .map(|(_, ptr)| unsafe { *Box::from_raw((ptr as *mut c_void).cast::<B>()) })
     }
 
+    /// Remove bean if exists without dropping it, the references to the bean keep valid.
+    #[must_use]
+    pub fn detach_bean(bean_name: &str) -> bool {
+        Self::get_instance().0.remove(bean_name).is_some()
+    }
+
     /// Get the bean by name.
     #[must_use]
     pub fn get_bean<B>(bean_name: &str) -> Option<&B> {
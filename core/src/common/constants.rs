@@ -1,4 +1,5 @@
 use crate::impl_display_by_debug;
+use dashmap::DashMap;
 use once_cell::sync::Lazy;
 use std::time::Duration;
 
@@ -9,18 +10,54 @@ pub const DEFAULT_STACK_SIZE: usize = 128 * 1024;
 #[cfg(all(target_os = "linux", feature = "io_uring"))]
 pub const IO_URING_TIMEOUT_USERDATA: u64 = u64::MAX - 1;
 
+/// A user data used to indicate the completion of `IORING_OP_ASYNC_CANCEL`.
+#[cfg(all(target_os = "linux", feature = "io_uring"))]
+pub const IO_URING_CANCEL_USERDATA: u64 = u64::MAX - 2;
+
 /// Coroutine global queue bean name.
 pub const COROUTINE_GLOBAL_QUEUE_BEAN: &str = "coroutineGlobalQueueBean";
 
 /// Task global queue bean name.
 pub const TASK_GLOBAL_QUEUE_BEAN: &str = "taskGlobalQueueBean";
 
+/// Task registry bean name, the pools share the live tasks of their runtime through it.
+pub const TASK_REGISTRY_BEAN: &str = "taskRegistryBean";
+
+/// Coroutine suspend queues bean name, the schedulers enabled migration share their
+/// suspend queues through it.
+pub const COROUTINE_SUSPEND_QUEUES_BEAN: &str = "coroutineSuspendQueuesBean";
+
 /// Monitor bean name.
 pub const MONITOR_BEAN: &str = "monitorBean";
 
 /// Default time slice.
 pub const SLICE: Duration = Duration::from_millis(10);
 
+/// The coroutine local key of the time slice decided by the scheduling policy.
+pub const COROUTINE_TIME_SLICE: &str = "COROUTINE_TIME_SLICE";
+
+/// The coroutine local key of the running counter of the pool owns the coroutine.
+pub const COROUTINE_OWNER: &str = "COROUTINE_OWNER";
+
+/// The coroutine local key of the task running in the coroutine.
+pub const COROUTINE_TASK: &str = "COROUTINE_TASK";
+
+/// Default tick resolution of the timing wheels.
+pub const DEFAULT_TIMER_TICK: Duration = Duration::from_millis(1);
+
+/// Get the name of the `bean` owned by the `runtime`, the default runtime is named "".
+#[must_use]
+pub fn runtime_bean(bean: &'static str, runtime: &str) -> &'static str {
+    static NAMES: Lazy<DashMap<(&str, String), &str>> = Lazy::new(DashMap::new);
+    if runtime.is_empty() {
+        return bean;
+    }
+    NAMES
+        .entry((bean, runtime.to_string()))
+        .or_insert_with(|| format!("{bean}-{runtime}").leak())
+        .value()
+}
+
 /// Get the cpu count
 #[must_use]
 pub fn cpu_count() -> usize {
@@ -52,8 +89,15 @@ pub enum SyscallName {
     sleep,
     usleep,
     nanosleep,
+    #[cfg(target_os = "linux")]
+    clock_nanosleep,
+    sched_yield,
+    pause,
     poll,
+    #[cfg(target_os = "linux")]
+    ppoll,
     select,
+    pselect,
     #[cfg(target_os = "linux")]
     accept4,
     #[cfg(target_os = "linux")]
@@ -121,6 +165,11 @@ pub enum SyscallName {
     pthread_mutex_trylock,
     pthread_mutex_lock,
     pthread_mutex_unlock,
+    waitpid,
+    #[cfg(target_os = "linux")]
+    wait4,
+    #[cfg(target_os = "linux")]
+    waitid,
     #[cfg(windows)]
     CreateFileW,
     #[cfg(windows)]
@@ -134,6 +183,23 @@ pub enum SyscallName {
 }
 
 impl SyscallName {
+    /// Returns `true` if the syscall only waits for a timer, such as `sleep`.
+    #[must_use]
+    pub fn is_sleep(&self) -> bool {
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                matches!(self, Self::Sleep | Self::sleep | Self::usleep | Self::nanosleep)
+            } else if #[cfg(target_os = "linux")] {
+                matches!(
+                    self,
+                    Self::sleep | Self::usleep | Self::nanosleep | Self::clock_nanosleep
+                )
+            } else {
+                matches!(self, Self::sleep | Self::usleep | Self::nanosleep)
+            }
+        }
+    }
+
     /// Get the `NIO` syscall.
     #[must_use]
     pub fn nio() -> Self {
@@ -181,6 +247,8 @@ pub enum SyscallState {
     Timeout,
     ///系统调用回调成功
     Callback,
+    ///协程被取消，系统调用应返回ECANCELED
+    Cancelled,
 }
 
 impl_display_by_debug!(SyscallState);
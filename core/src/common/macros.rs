@@ -94,6 +94,9 @@ macro_rules! impl_current_for {
 
         impl$(<$($generic $( : $trait_tt1 $( + $trait_tt2)*)?),+>)? $struct_name$(<$($generic),+>)? {
             /// Init the current.
+            // never inline the thread local accesses, the coroutines may be resumed in
+            // other threads after suspended, so the cached thread local address is invalid
+            #[inline(never)]
             pub(crate) fn init_current(current: &Self) {
                 $name.with(|s| unsafe {
                     s.as_ptr()
@@ -111,6 +114,7 @@ macro_rules! impl_current_for {
 
             /// Get the current if has.
             #[must_use]
+            #[inline(never)]
             #[allow(unreachable_pub)]
             pub fn current<'current>() -> Option<&'current Self> {
                 $name.try_with(|s| unsafe {
@@ -131,6 +135,7 @@ macro_rules! impl_current_for {
             }
 
             /// Clean the current.
+            #[inline(never)]
             pub(crate) fn clean_current() {
                 _ = $name.try_with(|s| unsafe {
                     _ = s.as_ptr()
@@ -91,6 +91,39 @@ pub mod work_steal;
 #[doc = include_str!("../../docs/en/ordered-work-steal.md")]
 pub mod ordered_work_steal;
 
+/// A hierarchical timing wheel with O(1) insert and cancel.
+///
+/// # Examples
+///
+/// ```
+/// use open_coroutine_core::common::timing_wheel::TimingWheel;
+/// use std::time::Duration;
+///
+/// let mut wheel = TimingWheel::new(Duration::from_millis(1));
+/// let id = wheel.insert(2_000_000, 2);
+/// _ = wheel.insert(1_000_000, 1);
+/// assert_eq!(Some(2), wheel.cancel(id));
+/// assert_eq!(None, wheel.poll(999_999));
+/// assert_eq!(Some(1), wheel.poll(1_000_000));
+/// assert!(wheel.is_empty());
+/// ```
+pub mod timing_wheel;
+
+/// The cpu, cache and numa topology, used to pin the event-loops and to choose the nearer victims
+/// when stealing.
+///
+/// # Examples
+///
+/// ```
+/// use open_coroutine_core::common::topology::{CpuDistance, CpuTopology};
+///
+/// let topology = CpuTopology::uniform(2);
+/// assert_eq!(CpuDistance::Local, topology.distance(0, 0));
+/// assert_eq!(CpuDistance::Cache, topology.distance(0, 1));
+/// assert_eq!(CpuDistance::Remote, topology.distance(0, 2));
+/// ```
+pub mod topology;
+
 #[cfg(target_os = "linux")]
 extern "C" {
     fn linux_version_code() -> c_int;
@@ -110,12 +143,52 @@ pub fn current_kernel_version() -> c_int {
     unsafe { linux_version_code() }
 }
 
-/// get the current wall clock in ns
+/// get the current monotonic clock in ns, all the timestamps of the runtime are based on it.
+///
+/// Unlike the wall clock, it's not affected by NTP steps or manual clock changes.
+#[must_use]
+pub fn now() -> u64 {
+    cfg_if::cfg_if! {
+        if #[cfg(windows)] {
+            use windows_sys::Win32::System::Performance::{
+                QueryPerformanceCounter, QueryPerformanceFrequency,
+            };
+            let mut counter = 0;
+            let mut frequency = 0;
+            unsafe {
+                _ = QueryPerformanceCounter(&raw mut counter);
+                _ = QueryPerformanceFrequency(&raw mut frequency);
+            }
+            let counter = u128::try_from(counter).unwrap_or_default();
+            let frequency = u128::try_from(frequency).unwrap_or(1).max(1);
+            u64::try_from(counter * 1_000_000_000 / frequency).unwrap_or(u64::MAX)
+        } else {
+            let mut time = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            };
+            assert_eq!(
+                0,
+                unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &raw mut time) },
+                "get monotonic clock failed"
+            );
+            u64::try_from(time.tv_sec)
+                .unwrap_or_default()
+                .saturating_mul(1_000_000_000)
+                .saturating_add(u64::try_from(time.tv_nsec).unwrap_or_default())
+        }
+    }
+}
+
+/// get the current wall clock in ns since `UNIX_EPOCH`.
+///
+/// Only use it where the API demands absolute realtime, see [`to_realtime`] and
+/// [`from_realtime`].
 ///
 /// # Panics
 /// if the time is before `UNIX_EPOCH`
 #[must_use]
-pub fn now() -> u64 {
+pub fn realtime() -> u64 {
     u64::try_from(
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -125,6 +198,28 @@ pub fn now() -> u64 {
     .unwrap_or(u64::MAX)
 }
 
+/// Convert the `timestamp` of [`now`] to the wall clock in ns since `UNIX_EPOCH`.
+#[must_use]
+pub fn to_realtime(timestamp: u64) -> u64 {
+    let (now, realtime) = (now(), realtime());
+    if timestamp >= now {
+        realtime.saturating_add(timestamp - now)
+    } else {
+        realtime.saturating_sub(now - timestamp)
+    }
+}
+
+/// Convert the wall clock `realtime` in ns since `UNIX_EPOCH` to the timestamp of [`now`].
+#[must_use]
+pub fn from_realtime(realtime: u64) -> u64 {
+    let (now, current) = (now(), self::realtime());
+    if realtime >= current {
+        now.saturating_add(realtime - current)
+    } else {
+        now.saturating_sub(current - realtime)
+    }
+}
+
 /// current ns time add `dur`.
 #[must_use]
 pub fn get_timeout_time(dur: Duration) -> u64 {
@@ -233,9 +328,21 @@ mod tests {
         assert!(now() - start < 1_000_000_000);
     }
 
+    #[test]
+    fn realtime_conversion() {
+        let start = now();
+        assert!(now() >= start);
+        let deadline = get_timeout_time(Duration::from_secs(1));
+        let converted = from_realtime(to_realtime(deadline));
+        // the clocks move between the calls
+        assert!(converted.abs_diff(deadline) < 10_000_000);
+        assert!(to_realtime(start) <= realtime());
+        assert!(from_realtime(0) <= start);
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn test() {
-        assert!(current_kernel_version() > kernel_version(2, 7, 0))
+        assert!(current_kernel_version() > kernel_version(2, 7, 0));
     }
 }
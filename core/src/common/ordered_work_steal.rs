@@ -1,11 +1,18 @@
+use crate::common::now;
+use crate::common::topology::CpuDistance;
 use crossbeam_deque::{Injector, Steal};
+use crossbeam_skiplist::map::Entry;
 use crossbeam_skiplist::SkipMap;
 use rand::RngExt;
 use st3::fifo::Worker;
-use std::collections::VecDeque;
+use st3::StealError;
+use std::collections::{BTreeMap, VecDeque};
 use std::ffi::c_longlong;
 use std::fmt::Debug;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
 /// The highest precedence.
 pub const HIGHEST_PRECEDENCE: c_longlong = c_longlong::MIN;
@@ -16,32 +23,214 @@ pub const LOWEST_PRECEDENCE: c_longlong = c_longlong::MAX;
 /// The default precedence.
 pub const DEFAULT_PRECEDENCE: c_longlong = 0;
 
+/// The default number of pops between two pops from the global queue.
+pub const DEFAULT_GLOBAL_INTERVAL: u32 = 61;
+
+/// The default capacity of each local queue.
+pub const DEFAULT_LOCAL_CAPACITY: usize = 256;
+
+/// The window in ns of [`BandStats::max_wait`].
+pub const STATS_WINDOW: u64 = 10_000_000_000;
+
 /// Ordered trait for user's datastructures.
 pub trait Ordered {
     /// Get the priority of the element.
     fn priority(&self) -> Option<c_longlong>;
+
+    /// Get the absolute deadline in ns of the element, the elements with deadlines are served
+    /// in earliest-deadline-first order before all priorities.
+    fn deadline(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// The statistics of a priority band.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct BandStats {
+    /// The priority of this band.
+    pub priority: c_longlong,
+    /// The number of pending elements in this band.
+    pub pending: usize,
+    /// The max time in ns an element of this band waited before it's served in the last one
+    /// or two [`STATS_WINDOW`]s, including the current wait.
+    pub max_wait: u64,
+    /// The time in ns the oldest pending element of this band has waited, 0 if it's empty.
+    pub wait: u64,
+}
+
+// the queued element with the time it was pushed, 0 if it's not recorded
+#[derive(Debug)]
+struct Stamped<T> {
+    pushed: u64,
+    sequence: u64,
+    item: T,
+}
+
+impl<T> Stamped<T> {
+    fn key(&self) -> (u64, u64) {
+        (self.pushed, self.sequence)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Band {
+    // (push time, sequence) of the pending elements, the oldest first. Never lock here, the
+    // coroutines pop the queue and may be preempted anywhere
+    pending: SkipMap<(u64, u64), ()>,
+    // the start time of the current window
+    window: AtomicU64,
+    // the max wait of the current window
+    max_wait: AtomicU64,
+    // the max wait of the previous window
+    last_max_wait: AtomicU64,
+}
+
+impl Band {
+    // the push time of the oldest pending element
+    fn oldest(&self) -> Option<u64> {
+        self.pending.front().map(|entry| entry.key().0)
+    }
+
+    fn record_wait(&self, now: u64, waited: u64) {
+        let window = self.window.load(Ordering::Acquire);
+        let elapsed = now.saturating_sub(window);
+        if elapsed >= STATS_WINDOW
+            && self
+                .window
+                .compare_exchange(window, now, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            let last = self.max_wait.swap(0, Ordering::AcqRel);
+            // nothing was served in the previous window
+            let last = if elapsed >= STATS_WINDOW.saturating_mul(2) {
+                0
+            } else {
+                last
+            };
+            self.last_max_wait.store(last, Ordering::Release);
+        }
+        _ = self.max_wait.fetch_max(waited, Ordering::AcqRel);
+    }
+
+    fn max_wait(&self, now: u64) -> u64 {
+        let elapsed = now.saturating_sub(self.window.load(Ordering::Acquire));
+        if elapsed >= STATS_WINDOW.saturating_mul(2) {
+            0
+        } else if elapsed >= STATS_WINDOW {
+            self.max_wait.load(Ordering::Acquire)
+        } else {
+            self.max_wait
+                .load(Ordering::Acquire)
+                .max(self.last_max_wait.load(Ordering::Acquire))
+        }
+    }
+}
+
+/// The bands of a local queue.
+#[derive(Debug)]
+struct LocalBands<T: Debug> {
+    queue: SkipMap<c_longlong, Worker<Stamped<T>>>,
+    // the re-prioritized elements taken from `queue`, only the owner can push to `queue`
+    inbox: SkipMap<c_longlong, Injector<Stamped<T>>>,
+    inbox_len: AtomicUsize,
+}
+
+impl<T: Debug> Default for LocalBands<T> {
+    fn default() -> Self {
+        LocalBands {
+            queue: SkipMap::new(),
+            inbox: SkipMap::new(),
+            inbox_len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T: Debug> LocalBands<T> {
+    fn push_inbox(&self, priority: c_longlong, item: Stamped<T>) {
+        self.inbox
+            .get_or_insert_with(priority, Injector::new)
+            .value()
+            .push(item);
+        _ = self.inbox_len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Pop a re-prioritized element whose priority is in `range`.
+    fn pop_inbox(&self, range: impl RangeBounds<c_longlong>) -> Option<(c_longlong, Stamped<T>)> {
+        // Fast path, nothing is re-prioritized
+        if self.inbox_len.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        for entry in self.inbox.range(range) {
+            if let Some(item) = steal(entry.value()) {
+                _ = self.inbox_len.fetch_sub(1, Ordering::AcqRel);
+                return Some((*entry.key(), item));
+            }
+        }
+        None
+    }
+
+    fn len(&self) -> usize {
+        self.queue
+            .iter()
+            .map(|entry| {
+                let worker = entry.value();
+                worker.capacity() - worker.spare_capacity()
+            })
+            .sum::<usize>()
+            + self.inbox_len.load(Ordering::Acquire)
+    }
+}
+
+fn steal<T>(injector: &Injector<T>) -> Option<T> {
+    loop {
+        match injector.steal() {
+            Steal::Success(item) => return Some(item),
+            Steal::Retry => {}
+            Steal::Empty => return None,
+        }
+    }
 }
 
 /// Work stealing global queue, shared by multiple threads.
 #[repr(C)]
 #[derive(Debug)]
 pub struct OrderedWorkStealQueue<T: Debug> {
-    shared_queue: SkipMap<c_longlong, Injector<T>>,
+    shared_queue: SkipMap<c_longlong, Injector<Stamped<T>>>,
     /// Number of pending tasks in the queue. This helps prevent unnecessary
     /// locking in the hot path.
     len: AtomicUsize,
     local_capacity: usize,
-    local_queues: VecDeque<SkipMap<c_longlong, Worker<T>>>,
+    local_queues: VecDeque<LocalBands<T>>,
     index: AtomicUsize,
+    // the bands of all local queues and the global queue, used by aging and statistics
+    bands: SkipMap<c_longlong, Band>,
+    // the wait time in ns to improve the priority by one, 0 means no aging
+    aging: AtomicU64,
+    // whether the statistics of the bands are recorded without aging
+    stats: AtomicBool,
+    // (deadline, sequence) -> element
+    deadlines: Mutex<BTreeMap<(u64, u64), T>>,
+    deadline_len: AtomicUsize,
+    sequence: AtomicU64,
+    // the max number of elements to steal from a sibling at once, 0 means half of the capacity
+    steal_batch: AtomicUsize,
+    // whether the local queues steal from their siblings
+    work_stealing: AtomicBool,
+    // the local queues pop from the global queue every `global_interval` pops
+    global_interval: AtomicU32,
+    // the distances between the local queues, the nearer siblings are stolen first
+    distances: RwLock<Vec<Vec<CpuDistance>>>,
 }
 
 impl<T: Debug> Drop for OrderedWorkStealQueue<T> {
     fn drop(&mut self) {
         if !std::thread::panicking() {
             for local_queue in &self.local_queues {
-                for entry in local_queue {
+                for entry in &local_queue.queue {
                     assert!(entry.value().pop().is_none(), "local queue not empty");
                 }
+                assert!(local_queue.pop_inbox(..).is_none(), "local queue not empty");
             }
             assert!(self.pop().is_none(), "global queue not empty");
         }
@@ -50,7 +239,38 @@ impl<T: Debug> Drop for OrderedWorkStealQueue<T> {
 
 impl<T: Debug + Ordered> OrderedWorkStealQueue<T> {
     /// Push an element to the global queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::ordered_work_steal::{Ordered, OrderedWorkStealQueue};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Job(Option<u64>);
+    ///
+    /// impl Ordered for Job {
+    ///     fn priority(&self) -> Option<std::ffi::c_longlong> {
+    ///         None
+    ///     }
+    ///
+    ///     fn deadline(&self) -> Option<u64> {
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// let queue = OrderedWorkStealQueue::new(1, 32);
+    /// queue.push(Job(None));
+    /// queue.push(Job(Some(2)));
+    /// queue.push(Job(Some(1)));
+    /// assert_eq!(queue.pop(), Some(Job(Some(1))));
+    /// assert_eq!(queue.pop(), Some(Job(Some(2))));
+    /// assert_eq!(queue.pop(), Some(Job(None)));
+    /// ```
     pub fn push(&self, item: T) {
+        if let Some(deadline) = item.deadline() {
+            self.push_with_deadline(deadline, item);
+            return;
+        }
         self.push_with_priority(item.priority().unwrap_or(DEFAULT_PRECEDENCE), item);
     }
 }
@@ -63,11 +283,343 @@ impl<T: Debug> OrderedWorkStealQueue<T> {
             shared_queue: SkipMap::new(),
             len: AtomicUsize::new(0),
             local_capacity,
-            local_queues: (0..local_queues_size).map(|_| SkipMap::new()).collect(),
+            local_queues: (0..local_queues_size)
+                .map(|_| LocalBands::default())
+                .collect(),
             index: AtomicUsize::new(0),
+            bands: SkipMap::new(),
+            aging: AtomicU64::new(0),
+            stats: AtomicBool::new(false),
+            deadlines: Mutex::new(BTreeMap::new()),
+            deadline_len: AtomicUsize::new(0),
+            sequence: AtomicU64::new(0),
+            steal_batch: AtomicUsize::new(0),
+            work_stealing: AtomicBool::new(true),
+            global_interval: AtomicU32::new(DEFAULT_GLOBAL_INTERVAL),
+            distances: RwLock::new(Vec::new()),
         }
     }
 
+    /// Returns the number of local queues.
+    pub fn local_queue_size(&self) -> usize {
+        self.local_queues.len()
+    }
+
+    /// Returns the capacity of each local queue.
+    pub fn local_capacity(&self) -> usize {
+        self.local_capacity
+    }
+
+    /// Get the max number of elements a local queue steals from a sibling at once.
+    pub fn steal_batch(&self) -> usize {
+        match self.steal_batch.load(Ordering::Acquire) {
+            0 => self.local_capacity.saturating_add(1).saturating_div(2),
+            batch => batch,
+        }
+    }
+
+    /// Set the max number of elements a local queue steals from a sibling at once,
+    /// 0 means half of the local capacity.
+    pub fn set_steal_batch(&self, steal_batch: usize) {
+        self.steal_batch.store(steal_batch, Ordering::Release);
+    }
+
+    /// Returns `true` if the local queues steal from their siblings.
+    pub fn work_stealing(&self) -> bool {
+        self.work_stealing.load(Ordering::Acquire)
+    }
+
+    /// Enable or disable the stealing between local queues. Without stealing, a local queue
+    /// only serves its own elements and the global queue, which keeps the elements on the
+    /// cores they were pushed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::ordered_work_steal::OrderedWorkStealQueue;
+    ///
+    /// let queue = OrderedWorkStealQueue::new(2, 64);
+    /// queue.set_work_stealing(false);
+    /// let local0 = queue.local_queue();
+    /// let local1 = queue.local_queue();
+    /// for i in 0..4 {
+    ///     local0.push_with_priority(i, i);
+    /// }
+    /// assert_eq!(local1.pop(), None);
+    /// for i in 0..4 {
+    ///     assert_eq!(local0.pop(), Some(i));
+    /// }
+    /// assert_eq!(local0.pop(), None);
+    /// ```
+    pub fn set_work_stealing(&self, work_stealing: bool) {
+        self.work_stealing.store(work_stealing, Ordering::Release);
+    }
+
+    /// Get the number of pops between two pops from the global queue.
+    pub fn global_interval(&self) -> u32 {
+        self.global_interval.load(Ordering::Acquire)
+    }
+
+    /// Set the number of pops between two pops from the global queue, a local queue pops
+    /// from the global queue first every `global_interval` pops, so the global queue won't
+    /// starve.
+    ///
+    /// # Panics
+    /// if the `global_interval` is zero.
+    pub fn set_global_interval(&self, global_interval: u32) {
+        assert!(
+            global_interval > 0,
+            "global_interval must be greater than 0"
+        );
+        self.global_interval
+            .store(global_interval, Ordering::Release);
+    }
+
+    /// Set the distances between the local queues, `distances[i][j]` is the distance from the
+    /// local queue `i` to the local queue `j`. A local queue steals from the nearer siblings
+    /// first, the siblings with the same distance are stolen in random order. Without
+    /// distances, all the siblings are stolen in random order.
+    ///
+    /// The victims of a local queue are computed once when it's got by
+    /// [`OrderedWorkStealQueue::local_queue`], so the distances should be set before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::ordered_work_steal::OrderedWorkStealQueue;
+    /// use open_coroutine_core::common::topology::CpuDistance;
+    ///
+    /// let queue = OrderedWorkStealQueue::new(3, 64);
+    /// let (local, near) = (CpuDistance::Local, CpuDistance::Cache);
+    /// let far = CpuDistance::Remote;
+    /// queue.set_distances(vec![
+    ///     vec![local, far, near],
+    ///     vec![far, local, far],
+    ///     vec![near, far, local],
+    /// ]);
+    /// let local0 = queue.local_queue();
+    /// let local1 = queue.local_queue();
+    /// let local2 = queue.local_queue();
+    /// local1.push_with_priority(1, 1);
+    /// local2.push_with_priority(2, 2);
+    /// assert_eq!(local0.pop(), Some(2));
+    /// assert_eq!(local0.pop(), Some(1));
+    /// assert_eq!(local0.pop(), None);
+    /// ```
+    pub fn set_distances(&self, distances: Vec<Vec<CpuDistance>>) {
+        *self.distances.write().expect("lock failed") = distances;
+    }
+
+    /// Get the indexes of the siblings to steal by the local queue `index`, grouped by
+    /// distance, the nearer groups first.
+    fn victims(&self, index: usize) -> Box<[Box<[usize]>]> {
+        let distances = self.distances.read().expect("lock failed");
+        let distance = |i: usize| {
+            distances
+                .get(index)
+                .and_then(|distances| distances.get(i).copied())
+                .unwrap_or(CpuDistance::Remote)
+        };
+        let mut groups: BTreeMap<CpuDistance, Vec<usize>> = BTreeMap::new();
+        for i in (0..self.local_queues.len()).filter(|i| *i != index) {
+            groups.entry(distance(i)).or_default().push(i);
+        }
+        groups.into_values().map(Vec::into_boxed_slice).collect()
+    }
+
+    /// Push an element with the absolute `deadline` in ns to the global queue, the elements
+    /// with deadlines are served in earliest-deadline-first order before all priorities.
+    pub fn push_with_deadline(&self, deadline: u64, item: T) {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        _ = self
+            .deadlines
+            .lock()
+            .expect("lock failed")
+            .insert((deadline, sequence), item);
+        _ = self.deadline_len.fetch_add(1, Ordering::Release);
+        //add count
+        _ = self.len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns the number of elements with deadlines.
+    pub fn deadline_len(&self) -> usize {
+        self.deadline_len.load(Ordering::Acquire)
+    }
+
+    fn pop_deadline(&self) -> Option<T> {
+        // Fast path, no lock if there are no elements with deadlines
+        if self.deadline_len() == 0 {
+            return None;
+        }
+        let (_, item) = self.deadlines.lock().expect("lock failed").pop_first()?;
+        _ = self.deadline_len.fetch_sub(1, Ordering::AcqRel);
+        // Decrement the count.
+        self.dec_len();
+        Some(item)
+    }
+
+    /// Get the wait time to improve the effective priority of a band by one,
+    /// returns `None` if aging is disabled.
+    pub fn aging(&self) -> Option<Duration> {
+        match self.aging.load(Ordering::Acquire) {
+            0 => None,
+            aging => Some(Duration::from_nanos(aging)),
+        }
+    }
+
+    /// Set the wait time to improve the effective priority of a band by one,
+    /// `None` disables aging.
+    ///
+    /// Each element is aged by its own wait since it was pushed, a band whose oldest element
+    /// waits long enough is served before the bands with better priorities, so the worse
+    /// priorities won't starve.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::ordered_work_steal::OrderedWorkStealQueue;
+    /// use std::time::Duration;
+    ///
+    /// let queue = OrderedWorkStealQueue::new(1, 32);
+    /// queue.set_aging(Some(Duration::from_millis(1)));
+    /// queue.push_with_priority(100, 100);
+    /// std::thread::sleep(Duration::from_millis(20));
+    /// queue.push_with_priority(0, 0);
+    /// assert_eq!(queue.pop(), Some(0));
+    /// std::thread::sleep(Duration::from_millis(20));
+    /// // waited about 40ms, the effective priority is about 60
+    /// queue.push_with_priority(90, 90);
+    /// assert_eq!(queue.pop(), Some(100));
+    /// assert_eq!(queue.pop(), Some(90));
+    /// ```
+    pub fn set_aging(&self, aging: Option<Duration>) {
+        let aging = aging.map_or(0, |aging| {
+            u64::try_from(aging.as_nanos()).unwrap_or(u64::MAX).max(1)
+        });
+        self.aging.store(aging, Ordering::Release);
+    }
+
+    /// Returns `true` if the statistics of the bands are recorded without aging.
+    pub fn stats_enabled(&self) -> bool {
+        self.stats.load(Ordering::Acquire)
+    }
+
+    /// Enable or disable recording the statistics of the bands. The statistics are always
+    /// recorded with aging, without both, the queue never reads the clock. It should be set
+    /// before the elements are pushed, the elements pushed before are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::ordered_work_steal::OrderedWorkStealQueue;
+    ///
+    /// let queue = OrderedWorkStealQueue::new(1, 32);
+    /// queue.push_with_priority(0, 0);
+    /// assert!(queue.stats().is_empty());
+    /// assert_eq!(queue.pop(), Some(0));
+    /// queue.set_stats(true);
+    /// queue.push_with_priority(0, 0);
+    /// assert_eq!(queue.stats()[0].pending, 1);
+    /// assert_eq!(queue.pop(), Some(0));
+    /// assert_eq!(queue.stats()[0].pending, 0);
+    /// ```
+    pub fn set_stats(&self, stats: bool) {
+        self.stats.store(stats, Ordering::Release);
+    }
+
+    fn recording(&self) -> bool {
+        self.aging.load(Ordering::Acquire) != 0 || self.stats_enabled()
+    }
+
+    /// Get the statistics of the priority bands, ordered by priority, empty if neither aging
+    /// nor statistics is enabled.
+    pub fn stats(&self) -> Vec<BandStats> {
+        let now = now();
+        self.bands
+            .iter()
+            .map(|entry| {
+                let band = entry.value();
+                let wait = band.oldest().map_or(0, |pushed| now.saturating_sub(pushed));
+                BandStats {
+                    priority: *entry.key(),
+                    pending: band.pending.len(),
+                    max_wait: band.max_wait(now).max(wait),
+                    wait,
+                }
+            })
+            .collect()
+    }
+
+    fn on_push(&self, priority: c_longlong, item: T) -> Stamped<T> {
+        if !self.recording() {
+            return Stamped {
+                pushed: 0,
+                sequence: 0,
+                item,
+            };
+        }
+        let item = Stamped {
+            pushed: now().max(1),
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
+            item,
+        };
+        _ = self
+            .bands
+            .get_or_insert_with(priority, Band::default)
+            .value()
+            .pending
+            .insert(item.key(), ());
+        item
+    }
+
+    fn on_pop(&self, priority: c_longlong, item: Stamped<T>) -> T {
+        // the elements pushed before recording are not counted
+        if item.pushed == 0 {
+            return item.item;
+        }
+        if let Some(entry) = self.bands.get(&priority) {
+            let band = entry.value();
+            let now = now();
+            _ = band.pending.remove(&item.key());
+            band.record_wait(now, now.saturating_sub(item.pushed));
+        }
+        item.item
+    }
+
+    // the moved element keeps its push time, so it's still aged by its own wait
+    fn on_move(&self, priority: c_longlong, new_priority: c_longlong, item: &Stamped<T>) {
+        if item.pushed == 0 {
+            return;
+        }
+        if let Some(entry) = self.bands.get(&priority) {
+            _ = entry.value().pending.remove(&item.key());
+        }
+        _ = self
+            .bands
+            .get_or_insert_with(new_priority, Band::default)
+            .value()
+            .pending
+            .insert(item.key(), ());
+    }
+
+    /// Returns the priority of the band should be served first, or `None` if aging is disabled.
+    /// A band is aged by the wait of its oldest element.
+    fn aged(&self, priorities: impl Iterator<Item = c_longlong>) -> Option<c_longlong> {
+        let aging = self.aging.load(Ordering::Acquire);
+        if aging == 0 {
+            return None;
+        }
+        let now = now();
+        priorities.min_by_key(|priority| {
+            let waited = self
+                .bands
+                .get(priority)
+                .and_then(|entry| entry.value().oldest())
+                .map_or(0, |pushed| now.saturating_sub(pushed));
+            priority.saturating_sub(c_longlong::try_from(waited / aging).unwrap_or(c_longlong::MAX))
+        })
+    }
+
     /// Returns `true` if the global queue is empty.
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -78,15 +630,28 @@ impl<T: Debug> OrderedWorkStealQueue<T> {
         self.len.load(Ordering::Acquire)
     }
 
+    // the global queue is shared by all threads, never lose the concurrent updates
+    fn dec_len(&self) {
+        _ = self
+            .len
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |len| {
+                Some(len.saturating_sub(1))
+            });
+    }
+
     /// Push an element to the global queue.
     pub fn push_with_priority(&self, priority: c_longlong, item: T) {
+        let item = self.on_push(priority, item);
+        self.push_raw(priority, item);
+    }
+
+    fn push_raw(&self, priority: c_longlong, item: Stamped<T>) {
         self.shared_queue
             .get_or_insert_with(priority, Injector::new)
             .value()
             .push(item);
         //add count
-        self.len
-            .store(self.len().saturating_add(1), Ordering::Release);
+        _ = self.len.fetch_add(1, Ordering::Release);
     }
 
     /// Pop an element from the global queue.
@@ -95,21 +660,129 @@ impl<T: Debug> OrderedWorkStealQueue<T> {
         if self.is_empty() {
             return None;
         }
+        if let Some(item) = self.pop_deadline() {
+            return Some(item);
+        }
+        let non_empty = self
+            .shared_queue
+            .iter()
+            .filter(|entry| !entry.value().is_empty())
+            .map(|entry| *entry.key());
+        if let Some(entry) = self
+            .aged(non_empty)
+            .and_then(|priority| self.shared_queue.get(&priority))
+        {
+            if let Some(item) = self.steal(&entry) {
+                return Some(item);
+            }
+        }
         for entry in &self.shared_queue {
-            loop {
-                match entry.value().steal() {
-                    Steal::Success(item) => {
-                        // Decrement the count.
-                        self.len
-                            .store(self.len().saturating_sub(1), Ordering::Release);
-                        return Some(item);
+            if let Some(item) = self.steal(&entry) {
+                return Some(item);
+            }
+        }
+        None
+    }
+
+    /// Change the priorities of the queued elements in the band `band`, including the elements
+    /// in the local queues, `None` searches all the bands.
+    ///
+    /// `f` returns the new priority of an element or `None` to keep it. The changed elements
+    /// are moved to the tail of their new bands. The elements of a local queue stay in it, the
+    /// elements of the global queue stay in the global queue. The elements with deadlines are
+    /// not affected. Returns the number of changed elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::ordered_work_steal::OrderedWorkStealQueue;
+    ///
+    /// let queue = OrderedWorkStealQueue::new(1, 32);
+    /// let local = queue.local_queue();
+    /// for i in 0..3 {
+    ///     local.push_with_priority(i, i);
+    /// }
+    /// queue.push_with_priority(2, 3);
+    /// let changed = queue.reprioritize(Some(2), |item| Some(-*item));
+    /// assert_eq!(changed, 2);
+    /// assert_eq!(local.len(), 4);
+    /// assert_eq!(local.local_len(), 3);
+    /// // the local queue is served before the global queue
+    /// assert_eq!(local.pop(), Some(2));
+    /// assert_eq!(local.pop(), Some(0));
+    /// assert_eq!(local.pop(), Some(1));
+    /// assert_eq!(local.pop(), Some(3));
+    /// assert_eq!(local.pop(), None);
+    /// ```
+    pub fn reprioritize(
+        &self,
+        band: Option<c_longlong>,
+        mut f: impl FnMut(&mut T) -> Option<c_longlong>,
+    ) -> usize {
+        let range = band.map_or((Bound::Unbounded, Bound::Unbounded), |band| {
+            (Bound::Included(band), Bound::Included(band))
+        });
+        let mut changed = 0;
+        let mut sort = |priority: c_longlong, mut item: Stamped<T>| match f(&mut item.item) {
+            Some(new_priority) if new_priority != priority => {
+                changed += 1;
+                self.on_move(priority, new_priority, &item);
+                (new_priority, item)
+            }
+            _ => (priority, item),
+        };
+        let mut drained = Vec::new();
+        for entry in self.shared_queue.range(range) {
+            while let Some(item) = steal(entry.value()) {
+                self.dec_len();
+                drained.push((*entry.key(), item));
+            }
+        }
+        for (priority, item) in drained {
+            let (priority, item) = sort(priority, item);
+            self.push_raw(priority, item);
+        }
+        for local_queue in &self.local_queues {
+            // the re-prioritized ones are older than the ones in the same band of the worker
+            let mut drained = Vec::new();
+            while let Some(item) = local_queue.pop_inbox(range) {
+                drained.push(item);
+            }
+            for entry in local_queue.queue.range(range) {
+                self.drain_worker(entry.value(), |item| drained.push((*entry.key(), item)));
+            }
+            for (priority, item) in drained {
+                let (priority, item) = sort(priority, item);
+                local_queue.push_inbox(priority, item);
+            }
+        }
+        changed
+    }
+
+    /// Take all the elements out of the worker owned by another thread.
+    fn drain_worker(&self, worker: &Worker<Stamped<T>>, mut f: impl FnMut(Stamped<T>)) {
+        if worker.is_empty() {
+            return;
+        }
+        let into_queue = Worker::new(self.local_capacity.max(1));
+        loop {
+            match worker.stealer_ref().steal(&into_queue, |n| n) {
+                Ok(_) => {
+                    while let Some(item) = into_queue.pop() {
+                        f(item);
                     }
-                    Steal::Retry => {}
-                    Steal::Empty => break,
                 }
+                Err(StealError::Busy) => {}
+                Err(StealError::Empty) => break,
             }
         }
-        None
+    }
+
+    fn steal(&self, entry: &Entry<'_, c_longlong, Injector<Stamped<T>>>) -> Option<T> {
+        let item = steal(entry.value())?;
+        // Decrement the count.
+        self.dec_len();
+        Some(self.on_pop(*entry.key(), item))
     }
 
     /// Get a local queue, this method should be called up to `local_queue_size` times.
@@ -126,13 +799,13 @@ impl<T: Debug> OrderedWorkStealQueue<T> {
             .local_queues
             .get(index)
             .unwrap_or_else(|| panic!("local queue {index} init failed!"));
-        OrderedLocalQueue::new(self, local)
+        OrderedLocalQueue::new(self, index, local)
     }
 }
 
 impl<T: Debug> Default for OrderedWorkStealQueue<T> {
     fn default() -> Self {
-        Self::new(num_cpus::get(), 256)
+        Self::new(num_cpus::get(), DEFAULT_LOCAL_CAPACITY)
     }
 }
 
@@ -143,17 +816,20 @@ pub struct OrderedLocalQueue<'l, T: Debug> {
     /// Used to schedule bookkeeping tasks every so often.
     tick: AtomicU32,
     shared: &'l OrderedWorkStealQueue<T>,
+    index: usize,
     stealing: AtomicBool,
-    queue: &'l SkipMap<c_longlong, Worker<T>>,
-    len: AtomicUsize,
+    local: &'l LocalBands<T>,
+    // the siblings to steal grouped by distance, the nearer groups first
+    victims: Box<[Box<[usize]>]>,
 }
 
 impl<T: Debug> Drop for OrderedLocalQueue<'_, T> {
     fn drop(&mut self) {
         if !std::thread::panicking() {
-            for entry in self.queue {
+            for entry in &self.local.queue {
                 assert!(entry.value().pop().is_none(), "local queue not empty");
             }
+            assert!(self.local.pop_inbox(..).is_none(), "local queue not empty");
         }
     }
 }
@@ -161,22 +837,26 @@ impl<T: Debug> Drop for OrderedLocalQueue<'_, T> {
 impl<T: Debug + Ordered> OrderedLocalQueue<'_, T> {
     /// If the queue is full, first push half to global,
     /// then push the item to global.
+    ///
+    /// The elements with deadlines are always pushed to global.
     pub fn push(&self, item: T) {
+        if let Some(deadline) = item.deadline() {
+            self.shared.push_with_deadline(deadline, item);
+            return;
+        }
         self.push_with_priority(item.priority().unwrap_or(DEFAULT_PRECEDENCE), item);
     }
 }
 
 impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
-    fn new(
-        shared: &'l OrderedWorkStealQueue<T>,
-        queue: &'l SkipMap<c_longlong, Worker<T>>,
-    ) -> Self {
+    fn new(shared: &'l OrderedWorkStealQueue<T>, index: usize, local: &'l LocalBands<T>) -> Self {
         OrderedLocalQueue {
             tick: AtomicU32::new(0),
             shared,
+            index,
             stealing: AtomicBool::new(false),
-            queue,
-            len: AtomicUsize::new(0),
+            local,
+            victims: shared.victims(index),
         }
     }
 
@@ -195,6 +875,16 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
         self.len() == 0
     }
 
+    /// Change the priorities of the queued elements in all the queues,
+    /// see [`OrderedWorkStealQueue::reprioritize`].
+    pub fn reprioritize(
+        &self,
+        band: Option<c_longlong>,
+        f: impl FnMut(&mut T) -> Option<c_longlong>,
+    ) -> usize {
+        self.shared.reprioritize(band, f)
+    }
+
     /// Returns `true` if the local queue is full.
     ///
     /// # Examples
@@ -226,6 +916,7 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
             .saturating_add(1)
             .saturating_div(2)
             .saturating_sub(self.local_len())
+            .min(self.shared.steal_batch())
     }
 
     fn can_steal(&self) -> bool {
@@ -239,19 +930,18 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
 
     /// Returns the number of elements in the queue.
     pub fn local_len(&self) -> usize {
-        self.len.load(Ordering::Acquire)
+        self.local.len()
     }
 
     /// Returns the number of elements in the all queues.
     pub fn len(&self) -> usize {
-        let mut full_len = self.shared.len();
-        for local_queue in &self.shared.local_queues {
-            for entry in local_queue {
-                let worker = entry.value();
-                full_len += worker.capacity() - worker.spare_capacity();
-            }
-        }
-        full_len
+        self.shared.len()
+            + self
+                .shared
+                .local_queues
+                .iter()
+                .map(LocalBands::len)
+                .sum::<usize>()
     }
 
     fn try_lock(&self) -> bool {
@@ -284,44 +974,44 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
     /// assert_eq!(local.pop(), None);
     /// ```
     pub fn push_with_priority(&self, priority: c_longlong, item: T) {
+        let item = self.shared.on_push(priority, item);
         if self.is_local_full() {
             self.push_to_global(priority, item);
             return;
         }
         if let Err(item) = self
+            .local
             .queue
             .get_or_insert_with(priority, || Worker::new(self.shared.local_capacity))
             .value()
             .push(item)
         {
             self.push_to_global(priority, item);
-        } else {
-            //add count
-            self.len
-                .store(self.local_len().saturating_add(1), Ordering::Release);
         }
     }
 
-    fn push_to_global(&self, priority: c_longlong, item: T) {
+    fn push_to_global(&self, priority: c_longlong, item: Stamped<T>) {
         //把本地队列的一半放到全局队列
         let count = self.local_len() / 2;
         let mut done = 0;
         while done < count {
-            for entry in self.queue.iter().rev() {
+            let before = done;
+            for entry in self.local.queue.iter().rev() {
                 if done >= count {
                     break;
                 }
                 if let Some(item) = entry.value().pop() {
-                    self.shared.push_with_priority(*entry.key(), item);
+                    self.shared.push_raw(*entry.key(), item);
                     done += 1;
                 }
             }
+            if done == before {
+                // the elements were stolen by others
+                break;
+            }
         }
-        // refresh count
-        self.len
-            .store(self.local_len().saturating_sub(count), Ordering::Release);
         //直接放到全局队列
-        self.shared.push_with_priority(priority, item);
+        self.shared.push_raw(priority, item);
     }
 
     /// Increment the tick
@@ -377,8 +1067,12 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
     /// assert_eq!(queue.pop(), None);
     /// ```
     pub fn pop(&self) -> Option<T> {
-        //每从本地弹出61次，就从全局队列弹出
-        if self.tick().is_multiple_of(61) {
+        //有截止时间的元素最优先
+        if let Some(val) = self.shared.pop_deadline() {
+            return Some(val);
+        }
+        //每从本地弹出global_interval次，就从全局队列弹出
+        if self.tick().is_multiple_of(self.shared.global_interval()) {
             if let Some(val) = self.shared.pop() {
                 return Some(val);
             }
@@ -386,29 +1080,37 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
         if let Some(val) = self.pop_local() {
             return Some(val);
         }
-        if self.try_lock() {
-            //尝试从其他本地队列steal
+        if self.shared.work_stealing() && self.try_lock() {
+            //尝试从其他本地队列steal，近的优先
             let local_queues = &self.shared.local_queues;
-            let num = local_queues.len();
-            let start = rand::rng().random_range(0..num);
-            for i in 0..num {
-                let i = (start + i) % num;
+            let mut rng = rand::rng();
+            // the siblings with the same distance are stolen in random order
+            let victims = self.victims.iter().flat_map(|group| {
+                let start = rng.random_range(0..group.len());
+                (0..group.len()).map(move |i| group[(start + i) % group.len()])
+            });
+            for i in victims {
                 if let Some(another) = local_queues.get(i) {
                     if !self.can_steal() {
                         //本地队列超过一半，不再steal
                         break;
                     }
-                    if std::ptr::eq(&raw const another, &raw const self.queue) {
+                    if std::ptr::eq(another, self.local) {
                         //不能偷自己
                         continue;
                     }
-                    for entry in another {
+                    // the idle sibling can't serve its re-prioritized ones
+                    if let Some((priority, val)) = another.pop_inbox(..) {
+                        self.release_lock();
+                        return Some(self.shared.on_pop(priority, val));
+                    }
+                    for entry in &another.queue {
                         let worker = entry.value();
                         if worker.is_empty() {
                             //其他队列为空
                             continue;
                         }
-                        let into_entry = self.queue.get_or_insert_with(*entry.key(), || {
+                        let into_entry = self.local.queue.get_or_insert_with(*entry.key(), || {
                             Worker::new(self.shared.local_capacity)
                         });
                         let into_queue = into_entry.value();
@@ -428,15 +1130,10 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
                             })
                             .is_ok()
                         {
-                            // refresh local len
-                            self.len.store(
-                                self.local_len().saturating_add(
-                                    into_queue.capacity() - into_queue.spare_capacity(),
-                                ),
-                                Ordering::Release,
-                            );
-                            self.release_lock();
-                            return self.pop_local();
+                            if let Some(val) = self.pop_local() {
+                                self.release_lock();
+                                return Some(val);
+                            }
                         }
                     }
                 }
@@ -448,16 +1145,53 @@ impl<'l, T: Debug> OrderedLocalQueue<'l, T> {
     }
 
     fn pop_local(&self) -> Option<T> {
+        let non_empty = self
+            .local
+            .queue
+            .iter()
+            .filter(|entry| !entry.value().is_empty())
+            .map(|entry| *entry.key())
+            .chain(
+                self.local
+                    .inbox
+                    .iter()
+                    .filter(|entry| !entry.value().is_empty())
+                    .map(|entry| *entry.key()),
+            );
+        if let Some(priority) = self.shared.aged(non_empty) {
+            if let Some(val) = self.pop_inbox(priority..=priority) {
+                return Some(val);
+            }
+            if let Some(val) = self
+                .local
+                .queue
+                .get(&priority)
+                .and_then(|entry| self.pop_worker(&entry))
+            {
+                return Some(val);
+            }
+        }
         //从本地队列弹出元素
-        for entry in self.queue {
-            if let Some(val) = entry.value().pop() {
-                // Decrement the count.
-                self.len
-                    .store(self.local_len().saturating_sub(1), Ordering::Release);
+        for entry in &self.local.queue {
+            // the re-prioritized ones are served before the worse or the newer ones
+            if let Some(val) = self.pop_inbox(..=*entry.key()) {
+                return Some(val);
+            }
+            if let Some(val) = self.pop_worker(&entry) {
                 return Some(val);
             }
         }
-        None
+        self.pop_inbox(..)
+    }
+
+    fn pop_inbox(&self, range: impl RangeBounds<c_longlong>) -> Option<T> {
+        let (priority, val) = self.local.pop_inbox(range)?;
+        Some(self.shared.on_pop(priority, val))
+    }
+
+    fn pop_worker(&self, entry: &Entry<'_, c_longlong, Worker<Stamped<T>>>) -> Option<T> {
+        let val = entry.value().pop()?;
+        Some(self.shared.on_pop(*entry.key(), val))
     }
 }
 
@@ -486,4 +1220,123 @@ mod tests {
         assert_eq!(local1.pop(), None);
         assert_eq!(queue.pop(), None);
     }
+
+    #[test]
+    fn test_band_stats() {
+        let queue = OrderedWorkStealQueue::new(1, 2);
+        queue.set_stats(true);
+        let local = queue.local_queue();
+        // overflow to the global queue is not counted twice
+        for i in 0..4 {
+            local.push_with_priority(i % 2, i);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        let stats = queue.stats();
+        assert_eq!(2, stats.len());
+        for (priority, band) in stats.iter().enumerate() {
+            assert_eq!(c_longlong::try_from(priority).unwrap(), band.priority);
+            assert_eq!(2, band.pending);
+            assert!(band.max_wait >= 10_000_000);
+        }
+        while local.pop().is_some() {}
+        for band in queue.stats() {
+            assert_eq!(0, band.pending);
+            assert!(band.max_wait >= 10_000_000);
+        }
+    }
+
+    #[test]
+    fn test_element_wait() {
+        let queue = OrderedWorkStealQueue::new(1, 32);
+        queue.set_stats(true);
+        queue.push_with_priority(0, 0);
+        queue.push_with_priority(0, 1);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(Some(0), queue.pop());
+        // the remaining element keeps its own wait, it's not reset by the pop
+        let band = queue.stats()[0];
+        assert_eq!(1, band.pending);
+        assert!(band.wait >= 10_000_000);
+        assert_eq!(Some(1), queue.pop());
+        assert!(queue.stats()[0].max_wait >= 10_000_000);
+        assert_eq!(0, queue.stats()[0].wait);
+    }
+
+    #[test]
+    fn test_band_window() {
+        let band = Band::default();
+        let start = STATS_WINDOW * 10;
+        band.record_wait(start, 100);
+        band.record_wait(start + 1, 10);
+        assert_eq!(100, band.max_wait(start + 1));
+        // the previous window is still reported
+        band.record_wait(start + STATS_WINDOW, 10);
+        assert_eq!(100, band.max_wait(start + STATS_WINDOW));
+        assert_eq!(10, band.max_wait(start + STATS_WINDOW * 2));
+        assert_eq!(0, band.max_wait(start + STATS_WINDOW * 3));
+        // nothing was served in the previous window
+        band.record_wait(start + STATS_WINDOW * 4, 1);
+        assert_eq!(1, band.max_wait(start + STATS_WINDOW * 4));
+    }
+
+    #[test]
+    fn test_reprioritize() {
+        let queue = OrderedWorkStealQueue::new(2, 64);
+        queue.set_stats(true);
+        let local0 = queue.local_queue();
+        let local1 = queue.local_queue();
+        for i in 0..4 {
+            local0.push_with_priority(i, i);
+            local1.push_with_priority(i, i + 4);
+        }
+        queue.push_with_priority(1, 8);
+        // move the odd elements to the band 10
+        let changed = queue.reprioritize(None, |item| (*item % 2 == 1).then_some(10));
+        assert_eq!(4, changed);
+        assert_eq!(9, local0.len());
+        // the elements stay in their queues
+        assert_eq!(1, queue.len());
+        assert_eq!(4, local0.local_len());
+        assert_eq!(4, local1.local_len());
+        let pending: Vec<(c_longlong, usize)> = queue
+            .stats()
+            .iter()
+            .map(|band| (band.priority, band.pending))
+            .collect();
+        assert_eq!(vec![(0, 2), (1, 1), (2, 2), (3, 0), (10, 4)], pending);
+        // only the band 1 is searched
+        assert_eq!(
+            0,
+            queue.reprioritize(Some(1), |item| (*item == 0).then_some(10))
+        );
+        let mut popped = Vec::new();
+        while let Some(item) = local0.pop() {
+            popped.push(item);
+        }
+        // the moved ones keep their order, the idle sibling is stolen
+        assert_eq!(vec![0, 2, 1, 3, 4, 6, 5, 7, 8], popped);
+        assert_eq!(local1.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_steal_batch() {
+        let queue = OrderedWorkStealQueue::new(2, 64);
+        assert_eq!(32, queue.steal_batch());
+        queue.set_steal_batch(2);
+        let local0 = queue.local_queue();
+        let local1 = queue.local_queue();
+        for i in 0..8 {
+            local0.push_with_priority(0, i);
+        }
+        assert_eq!(Some(0), local1.pop());
+        assert_eq!(1, local1.local_len());
+        queue.set_work_stealing(false);
+        assert_eq!(Some(1), local1.pop());
+        assert_eq!(None, local1.pop());
+        for i in 2..8 {
+            assert_eq!(Some(i), local0.pop());
+        }
+        assert!(queue.is_empty());
+    }
 }
@@ -0,0 +1,400 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// The number of bits used by the slots of each level.
+const SLOT_BITS: u32 = 6;
+
+/// The number of slots in each level.
+const SLOTS: usize = 1 << SLOT_BITS;
+
+/// The number of levels.
+const LEVELS: usize = 6;
+
+/// The max ticks the levels can represent, later deadlines are placed in the top level
+/// and cascaded again when their slot is reached.
+#[allow(clippy::cast_possible_truncation)]
+const MAX_TICKS: u64 = (1 << (SLOT_BITS * LEVELS as u32)) - 1;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Position {
+    Slot(usize, usize),
+    Ready,
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    // the deadline in ns
+    deadline: u64,
+    position: Position,
+    value: T,
+}
+
+/// A hierarchical timing wheel.
+///
+/// The values expire exactly at their deadlines, the tick only decides the width of the slots.
+/// Insert and cancel are O(1), the values in higher levels are cascaded to lower
+/// levels lazily when their slot is reached.
+#[derive(Debug)]
+pub struct TimingWheel<T> {
+    tick: u64,
+    // the latest polled time in ns
+    now: u64,
+    // the slots before this tick have been processed
+    elapsed: u64,
+    next_id: u64,
+    entries: HashMap<u64, Entry<T>>,
+    // the ids in each slot of each level
+    levels: Vec<Vec<HashSet<u64>>>,
+    // the bitmap of non-empty slots of each level
+    occupied: [u64; LEVELS],
+    // the expired ids, cancelled ids are skipped lazily
+    ready: VecDeque<u64>,
+}
+
+impl<T> Default for TimingWheel<T> {
+    fn default() -> Self {
+        Self::new(crate::common::constants::DEFAULT_TIMER_TICK)
+    }
+}
+
+// the levels and slots are always small enough to cast
+#[allow(clippy::cast_possible_truncation)]
+impl<T> TimingWheel<T> {
+    /// Create a timing wheel with the `tick` resolution.
+    ///
+    /// # Panics
+    /// if the `tick` is zero.
+    #[must_use]
+    pub fn new(tick: Duration) -> Self {
+        TimingWheel {
+            tick: Self::tick_nanos(tick),
+            now: 0,
+            elapsed: 0,
+            next_id: 0,
+            entries: HashMap::new(),
+            levels: (0..LEVELS)
+                .map(|_| (0..SLOTS).map(|_| HashSet::new()).collect())
+                .collect(),
+            occupied: [0; LEVELS],
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn tick_nanos(tick: Duration) -> u64 {
+        let tick = u64::try_from(tick.as_nanos()).unwrap_or(u64::MAX);
+        assert!(tick > 0, "tick must be greater than 0");
+        tick
+    }
+
+    /// Get the tick resolution.
+    #[must_use]
+    pub fn tick(&self) -> Duration {
+        Duration::from_nanos(self.tick)
+    }
+
+    /// Change the tick resolution, the inserted values are kept.
+    ///
+    /// # Panics
+    /// if the `tick` is zero.
+    pub fn set_tick(&mut self, tick: Duration) {
+        let tick = Self::tick_nanos(tick);
+        if tick == self.tick {
+            return;
+        }
+        self.tick = tick;
+        self.elapsed = self.now / tick;
+        self.occupied = [0; LEVELS];
+        for slots in &mut self.levels {
+            for slot in slots {
+                slot.clear();
+            }
+        }
+        let ids: Vec<u64> = self.entries.keys().copied().collect();
+        for id in ids {
+            if self
+                .entries
+                .get(&id)
+                .is_some_and(|entry| entry.position != Position::Ready)
+            {
+                self.place(id);
+            }
+        }
+    }
+
+    /// Returns the number of values in this wheel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if this wheel is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Insert a value expired at the `deadline` in ns, returns the id for cancel.
+    pub fn insert(&mut self, deadline: u64, value: T) -> u64 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        _ = self.entries.insert(
+            id,
+            Entry {
+                deadline,
+                position: Position::Ready,
+                value,
+            },
+        );
+        self.place(id);
+        id
+    }
+
+    /// Cancel the value by id, returns `None` if it's expired and polled or cancelled.
+    pub fn cancel(&mut self, id: u64) -> Option<T> {
+        let entry = self.entries.remove(&id)?;
+        if let Position::Slot(level, slot) = entry.position {
+            let ids = &mut self.levels[level][slot];
+            _ = ids.remove(&id);
+            if ids.is_empty() {
+                self.occupied[level] &= !(1 << slot);
+            }
+        }
+        Some(entry.value)
+    }
+
+    /// Returns a mutable reference to a value matching the `predicate`.
+    pub fn find_mut(&mut self, mut predicate: impl FnMut(&T) -> bool) -> Option<&mut T> {
+        self.entries
+            .values_mut()
+            .map(|entry| &mut entry.value)
+            .find(|value| predicate(value))
+    }
+
+    /// Cancel the values matching the `predicate`, returns them in no particular order.
+    pub fn cancel_if(&mut self, mut predicate: impl FnMut(&T) -> bool) -> Vec<T> {
+        let ids: Vec<u64> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| predicate(&entry.value))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter().filter_map(|id| self.cancel(id)).collect()
+    }
+
+    /// Take at most `count` values with the earliest deadlines out, returns the deadlines in ns
+    /// and the values in deadline order.
+    pub fn take(&mut self, count: usize) -> Vec<(u64, T)> {
+        let mut ids: Vec<(u64, u64)> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| (entry.deadline, *id))
+            .collect();
+        if count < ids.len() {
+            _ = ids.select_nth_unstable(count);
+            ids.truncate(count);
+        }
+        ids.sort_unstable();
+        ids.into_iter()
+            .filter_map(|(deadline, id)| self.cancel(id).map(|value| (deadline, value)))
+            .collect()
+    }
+
+    /// Poll an expired value before `now` in ns.
+    pub fn poll(&mut self, now: u64) -> Option<T> {
+        self.now = self.now.max(now);
+        self.advance();
+        while let Some(id) = self.ready.pop_front() {
+            if let Some(entry) = self.entries.remove(&id) {
+                return Some(entry.value);
+            }
+        }
+        None
+    }
+
+    /// Returns the deadline in ns of the earliest value, the returned deadline may be earlier
+    /// than the actual deadline of the values in higher levels.
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<u64> {
+        if self.ready.iter().any(|id| self.entries.contains_key(id)) {
+            return Some(self.now);
+        }
+        self.next_expiration()
+            .map(|(_, _, start)| start.saturating_mul(self.tick).max(self.now))
+    }
+
+    fn place(&mut self, id: u64) {
+        let Some(entry) = self.entries.get_mut(&id) else {
+            return;
+        };
+        if entry.deadline <= self.now {
+            entry.position = Position::Ready;
+            self.ready.push_back(id);
+            return;
+        }
+        // not earlier than `elapsed`, the slot of `elapsed` may be partially expired
+        let when = (entry.deadline / self.tick).max(self.elapsed);
+        let level = Self::level_for(self.elapsed, when);
+        let slot = Self::slot_for(when, level);
+        entry.position = Position::Slot(level, slot);
+        _ = self.levels[level][slot].insert(id);
+        self.occupied[level] |= 1 << slot;
+    }
+
+    /// The level is decided by the highest different bit between `elapsed` and `when`.
+    fn level_for(elapsed: u64, when: u64) -> usize {
+        let masked = ((elapsed ^ when) | (SLOTS as u64 - 1)).min(MAX_TICKS);
+        let significant = u64::BITS - 1 - masked.leading_zeros();
+        (significant / SLOT_BITS) as usize
+    }
+
+    fn slot_for(when: u64, level: usize) -> usize {
+        ((when >> (level as u32 * SLOT_BITS)) & (SLOTS as u64 - 1)) as usize
+    }
+
+    /// Returns the level, slot and the start tick of the earliest non-empty slot.
+    fn next_expiration(&self) -> Option<(usize, usize, u64)> {
+        for level in 0..LEVELS {
+            let occupied = self.occupied[level];
+            if occupied == 0 {
+                continue;
+            }
+            let slot_range = 1u64 << (level as u32 * SLOT_BITS);
+            let level_range = slot_range << SLOT_BITS;
+            let now_slot = (self.elapsed / slot_range) % SLOTS as u64;
+            let zeros = u64::from(occupied.rotate_right(now_slot as u32).trailing_zeros());
+            let slot = (zeros + now_slot) % SLOTS as u64;
+            let level_start = self.elapsed & !(level_range - 1);
+            let mut start = level_start.saturating_add(slot * slot_range);
+            if start <= self.elapsed && level > 0 {
+                // the deadline is too far away and placed in the top level
+                start = start.saturating_add(level_range);
+            }
+            return Some((level, slot as usize, start));
+        }
+        None
+    }
+
+    fn advance(&mut self) {
+        let now = self.now / self.tick;
+        while let Some((level, slot, start)) = self.next_expiration() {
+            if start > now {
+                break;
+            }
+            self.elapsed = self.elapsed.max(start);
+            self.occupied[level] &= !(1 << slot);
+            let ids = std::mem::take(&mut self.levels[level][slot]);
+            for id in ids {
+                // cascade to the lower levels or ready
+                self.place(id);
+            }
+            if level == 0 && start == now {
+                // the rest values of the current slot are not expired yet
+                break;
+            }
+        }
+        self.elapsed = self.elapsed.max(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    #[test]
+    fn test_expire_in_order() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        for i in (0..100u64).rev() {
+            _ = wheel.insert(i * 7 * MS + 1, i);
+        }
+        assert_eq!(100, wheel.len());
+        assert_eq!(None, wheel.poll(0));
+        for i in 0..100u64 {
+            // never expire earlier even in the same tick
+            assert_eq!(None, wheel.poll(i * 7 * MS));
+            assert_eq!(Some(i), wheel.poll(i * 7 * MS + 1));
+        }
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_cascade() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        // in different levels
+        let deadlines = [3, 70, 5_000, 300_000, 20_000_000, 2_000_000_000];
+        for deadline in deadlines {
+            _ = wheel.insert(deadline * MS, deadline);
+        }
+        for deadline in deadlines {
+            assert_eq!(None, wheel.poll(deadline * MS - MS));
+            assert_eq!(Some(deadline), wheel.poll(deadline * MS));
+        }
+        // beyond the max ticks
+        let far = u64::MAX / 4 / MS * MS;
+        _ = wheel.insert(far, 0);
+        assert_eq!(None, wheel.poll(far / 2));
+        assert_eq!(None, wheel.poll(far - MS));
+        assert_eq!(Some(0), wheel.poll(far));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        let first = wheel.insert(10 * MS, 1);
+        let second = wheel.insert(10 * MS, 2);
+        let third = wheel.insert(10_000 * MS, 3);
+        assert_eq!(Some(1), wheel.cancel(first));
+        assert_eq!(None, wheel.cancel(first));
+        assert_eq!(Some(3), wheel.cancel(third));
+        assert_eq!(Some(10 * MS), wheel.next_deadline());
+        assert_eq!(Some(2), wheel.poll(20_000 * MS));
+        assert_eq!(None, wheel.cancel(second));
+        assert_eq!(None, wheel.poll(u64::MAX));
+        assert_eq!(None, wheel.next_deadline());
+    }
+
+    #[test]
+    fn test_find_mut() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        _ = wheel.insert(10 * MS, 1);
+        _ = wheel.insert(10_000 * MS, 2);
+        *wheel.find_mut(|value| *value == 2).expect("not found") = 3;
+        assert!(wheel.find_mut(|value| *value == 2).is_none());
+        assert_eq!(Some(1), wheel.poll(10 * MS));
+        assert_eq!(Some(3), wheel.poll(10_000 * MS));
+    }
+
+    #[test]
+    fn test_take() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        for i in (1..=10u64).rev() {
+            _ = wheel.insert(i * 100 * MS, i);
+        }
+        // expired but not polled yet
+        assert_eq!(None, wheel.poll(0));
+        _ = wheel.insert(0, 0);
+        assert_eq!(vec![(0, 0), (100 * MS, 1), (200 * MS, 2)], wheel.take(3));
+        assert_eq!(8, wheel.len());
+        assert_eq!(None, wheel.poll(250 * MS));
+        assert_eq!(Some(3), wheel.poll(300 * MS));
+        assert_eq!(7, wheel.take(usize::MAX).len());
+        assert!(wheel.is_empty());
+        assert_eq!(None, wheel.poll(u64::MAX));
+    }
+
+    #[test]
+    fn test_set_tick() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        _ = wheel.insert(100 * MS, 1);
+        _ = wheel.insert(5_000 * MS, 2);
+        assert_eq!(None, wheel.poll(50 * MS));
+        wheel.set_tick(Duration::from_millis(10));
+        assert_eq!(None, wheel.poll(99 * MS));
+        assert_eq!(Some(1), wheel.poll(100 * MS));
+        assert_eq!(None, wheel.poll(4_999 * MS));
+        assert_eq!(Some(2), wheel.poll(5_000 * MS));
+        assert!(wheel.is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The sysfs directory of the cpu and numa topology on linux.
+pub const SYSFS_ROOT: &str = "/sys/devices/system";
+
+/// The topology of a logical cpu.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CpuInfo {
+    /// The id of the logical cpu.
+    pub id: usize,
+    /// The id of the physical package(socket).
+    pub package: usize,
+    /// The id of the numa node.
+    pub node: usize,
+    /// The id of the last level cache, it's the smallest cpu id sharing the cache.
+    pub llc: usize,
+}
+
+/// The distance between two logical cpus, a nearer sibling shares more caches.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum CpuDistance {
+    /// The same logical cpu.
+    Local,
+    /// The cpus share the last level cache.
+    Cache,
+    /// The cpus are in the same numa node.
+    Node,
+    /// The cpus are in different numa nodes.
+    Remote,
+}
+
+/// How to pin the event-loops to the cpus.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum PinPolicy {
+    /// Don't pin the event-loops, let the OS schedule them.
+    None,
+    /// Pin the event-loops to the cpus sharing caches first, fill a node before the next one.
+    #[default]
+    Compact,
+    /// Spread the event-loops across the numa nodes.
+    Scatter,
+}
+
+/// The cpu and numa topology.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CpuTopology {
+    cpus: Vec<CpuInfo>,
+}
+
+impl CpuTopology {
+    /// Get the topology of this machine, it's detected only once.
+    ///
+    /// Read from the sysfs on linux, fallback to a uniform topology if it's unavailable.
+    #[must_use]
+    pub fn current() -> &'static Self {
+        static TOPOLOGY: Lazy<CpuTopology> = Lazy::new(|| {
+            CpuTopology::from_sysfs(Path::new(SYSFS_ROOT))
+                .unwrap_or_else(|| CpuTopology::uniform(num_cpus::get()))
+        });
+        &TOPOLOGY
+    }
+
+    /// Create a topology with `count` cpus in one node and one cache.
+    #[must_use]
+    pub fn uniform(count: usize) -> Self {
+        CpuTopology {
+            cpus: (0..count)
+                .map(|id| CpuInfo {
+                    id,
+                    package: 0,
+                    node: 0,
+                    llc: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Read the topology from the sysfs directory like `/sys/devices/system`,
+    /// returns `None` if the online cpus are unknown.
+    #[must_use]
+    pub fn from_sysfs(root: &Path) -> Option<Self> {
+        let online = parse_cpu_list(&std::fs::read_to_string(root.join("cpu/online")).ok()?);
+        if online.is_empty() {
+            return None;
+        }
+        let mut nodes = BTreeMap::new();
+        if let Ok(entries) = std::fs::read_dir(root.join("node")) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some(node) = name
+                    .strip_prefix("node")
+                    .and_then(|id| id.parse::<usize>().ok())
+                else {
+                    continue;
+                };
+                if let Ok(list) = std::fs::read_to_string(entry.path().join("cpulist")) {
+                    for cpu in parse_cpu_list(&list) {
+                        _ = nodes.insert(cpu, node);
+                    }
+                }
+            }
+        }
+        let cpus = online
+            .into_iter()
+            .map(|id| {
+                let dir = root.join(format!("cpu/cpu{id}"));
+                CpuInfo {
+                    id,
+                    package: read_usize(&dir.join("topology/physical_package_id")).unwrap_or(0),
+                    node: nodes.get(&id).copied().unwrap_or(0),
+                    llc: last_level_cache(&dir).unwrap_or(id),
+                }
+            })
+            .collect();
+        Some(CpuTopology { cpus })
+    }
+
+    /// Get the logical cpus, ordered by id.
+    #[must_use]
+    pub fn cpus(&self) -> &[CpuInfo] {
+        &self.cpus
+    }
+
+    /// Get the logical cpu by id.
+    #[must_use]
+    pub fn cpu(&self, id: usize) -> Option<&CpuInfo> {
+        self.cpus.iter().find(|cpu| cpu.id == id)
+    }
+
+    /// Get the distance between two logical cpus, the unknown cpus are remote.
+    #[must_use]
+    pub fn distance(&self, a: usize, b: usize) -> CpuDistance {
+        if a == b {
+            return CpuDistance::Local;
+        }
+        match (self.cpu(a), self.cpu(b)) {
+            (Some(a), Some(b)) if a.llc == b.llc && a.node == b.node => CpuDistance::Cache,
+            (Some(a), Some(b)) if a.node == b.node => CpuDistance::Node,
+            _ => CpuDistance::Remote,
+        }
+    }
+
+    /// Get the distances between the pinned cpus of a `layout`,
+    /// the unpinned ones are remote to all others.
+    #[must_use]
+    pub fn distances(&self, layout: &[Option<usize>]) -> Vec<Vec<CpuDistance>> {
+        layout
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                layout
+                    .iter()
+                    .enumerate()
+                    .map(|(j, b)| match (a, b) {
+                        _ if i == j => CpuDistance::Local,
+                        (Some(a), Some(b)) => self.distance(*a, *b),
+                        _ => CpuDistance::Remote,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Get the cpus to pin `count` event-loops, `None` means not pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use open_coroutine_core::common::topology::{CpuTopology, PinPolicy};
+    ///
+    /// let topology = CpuTopology::uniform(2);
+    /// assert_eq!(vec![None, None], topology.layout(PinPolicy::None, 2));
+    /// assert_eq!(
+    ///     vec![Some(0), Some(1), Some(0)],
+    ///     topology.layout(PinPolicy::Compact, 3)
+    /// );
+    /// ```
+    #[must_use]
+    pub fn layout(&self, policy: PinPolicy, count: usize) -> Vec<Option<usize>> {
+        let mut ordered: Vec<&CpuInfo> = self.cpus.iter().collect();
+        match policy {
+            PinPolicy::None => return vec![None; count],
+            PinPolicy::Compact => ordered.sort_by_key(|cpu| (cpu.node, cpu.llc, cpu.id)),
+            PinPolicy::Scatter => {
+                // the rank of the cpu in its node, then interleave the nodes
+                let mut ranks = BTreeMap::new();
+                ordered.sort_by_key(|cpu| (cpu.node, cpu.llc, cpu.id));
+                let mut keys = BTreeMap::new();
+                for cpu in &ordered {
+                    let rank: &mut usize = ranks.entry(cpu.node).or_default();
+                    _ = keys.insert(cpu.id, (*rank, cpu.node));
+                    *rank += 1;
+                }
+                ordered.sort_by_key(|cpu| keys.get(&cpu.id).copied());
+            }
+        }
+        if ordered.is_empty() {
+            return vec![None; count];
+        }
+        (0..count)
+            .map(|i| ordered.get(i % ordered.len()).map(|cpu| cpu.id))
+            .collect()
+    }
+}
+
+/// Prefer allocating the memory of the current thread from the numa node of `cpu`,
+/// so the coroutine stacks created by the pinned event-loop are node-local.
+///
+/// # Errors
+/// if the memory policy can't be set.
+pub fn prefer_local_memory(cpu: usize) -> std::io::Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "linux")] {
+            const MPOL_PREFERRED: libc::c_long = 1;
+            let Some(node) = CpuTopology::current().cpu(cpu).map(|cpu| cpu.node) else {
+                return Ok(());
+            };
+            let bits = libc::c_ulong::BITS as usize;
+            // the kernel reads `maxnode - 1` bits, so a spare bit is kept after the node
+            let max_node = node + 2;
+            let mut mask: Vec<libc::c_ulong> = vec![0; max_node.div_ceil(bits)];
+            if let Some(word) = mask.get_mut(node / bits) {
+                *word |= 1 << (node % bits);
+            }
+            if unsafe {
+                libc::syscall(libc::SYS_set_mempolicy, MPOL_PREFERRED, mask.as_ptr(), max_node)
+            } == -1
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        } else {
+            _ = cpu;
+            Ok(())
+        }
+    }
+}
+
+/// Parse the cpu list like `0-3,8,10-11`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let mut bounds = range.splitn(2, '-').map(|id| id.trim().parse::<usize>());
+        match (bounds.next(), bounds.next()) {
+            (Some(Ok(start)), Some(Ok(end))) => cpus.extend(start..=end),
+            (Some(Ok(id)), None) => cpus.push(id),
+            _ => {}
+        }
+    }
+    cpus
+}
+
+fn read_usize(path: &Path) -> Option<usize> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Get the smallest cpu id sharing the last level cache with the cpu.
+fn last_level_cache(cpu_dir: &Path) -> Option<usize> {
+    let mut llc = None;
+    for entry in std::fs::read_dir(cpu_dir.join("cache")).ok()?.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with("index") {
+            continue;
+        }
+        let Some(level) = read_usize(&entry.path().join("level")) else {
+            continue;
+        };
+        let Some(first) = std::fs::read_to_string(entry.path().join("shared_cpu_list"))
+            .ok()
+            .and_then(|list| parse_cpu_list(&list).into_iter().min())
+        else {
+            continue;
+        };
+        if llc.is_none_or(|(max, _)| level > max) {
+            llc = Some((level, first));
+        }
+    }
+    llc.map(|(_, first)| first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().expect("no parent")).expect("create dir failed");
+        std::fs::write(path, content).expect("write failed");
+    }
+
+    // 2 nodes, each node has 2 caches, each cache is shared by 2 cpus
+    fn fake_sysfs() -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "topology-{}-{}",
+            std::process::id(),
+            crate::common::now()
+        ));
+        write(&root, "cpu/online", "0-7\n");
+        write(&root, "node/node0/cpulist", "0-3\n");
+        write(&root, "node/node1/cpulist", "4-7\n");
+        for cpu in 0..8 {
+            let first = cpu / 2 * 2;
+            write(
+                &root,
+                &format!("cpu/cpu{cpu}/topology/physical_package_id"),
+                &format!("{}\n", cpu / 4),
+            );
+            write(&root, &format!("cpu/cpu{cpu}/cache/index0/level"), "1\n");
+            write(
+                &root,
+                &format!("cpu/cpu{cpu}/cache/index0/shared_cpu_list"),
+                &format!("{cpu}\n"),
+            );
+            write(&root, &format!("cpu/cpu{cpu}/cache/index3/level"), "3\n");
+            write(
+                &root,
+                &format!("cpu/cpu{cpu}/cache/index3/shared_cpu_list"),
+                &format!("{first}-{}\n", first + 1),
+            );
+        }
+        root
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(vec![0, 1, 2, 3, 8, 10, 11], parse_cpu_list("0-3,8,10-11\n"));
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    #[test]
+    fn test_from_sysfs() {
+        let root = fake_sysfs();
+        let topology = CpuTopology::from_sysfs(&root).expect("read topology failed");
+        std::fs::remove_dir_all(root).expect("remove dir failed");
+        assert_eq!(8, topology.cpus().len());
+        assert_eq!(
+            Some(&CpuInfo {
+                id: 5,
+                package: 1,
+                node: 1,
+                llc: 4,
+            }),
+            topology.cpu(5)
+        );
+        assert_eq!(CpuDistance::Local, topology.distance(1, 1));
+        assert_eq!(CpuDistance::Cache, topology.distance(0, 1));
+        assert_eq!(CpuDistance::Node, topology.distance(0, 2));
+        assert_eq!(CpuDistance::Remote, topology.distance(0, 4));
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2)],
+            topology.layout(PinPolicy::Compact, 3)
+        );
+        assert_eq!(
+            vec![Some(0), Some(4), Some(1), Some(5)],
+            topology.layout(PinPolicy::Scatter, 4)
+        );
+        assert_eq!(
+            vec![
+                vec![CpuDistance::Local, CpuDistance::Remote, CpuDistance::Cache],
+                vec![CpuDistance::Remote, CpuDistance::Local, CpuDistance::Remote],
+                vec![CpuDistance::Cache, CpuDistance::Remote, CpuDistance::Local],
+            ],
+            topology.distances(&[Some(0), None, Some(1)])
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use crate::common::constants::{cpu_count, DEFAULT_STACK_SIZE};
+use crate::co_pool::adaptive::AdaptiveConfig;
+use crate::co_pool::task::RejectionPolicy;
+use crate::common::constants::{cpu_count, DEFAULT_STACK_SIZE, DEFAULT_TIMER_TICK};
+use crate::common::ordered_work_steal::{DEFAULT_GLOBAL_INTERVAL, DEFAULT_LOCAL_CAPACITY};
+use crate::common::topology::PinPolicy;
+use crate::scheduler::policy::SchedulingPolicyKind;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
@@ -11,6 +16,21 @@ pub struct Config {
     min_memory_count: usize,
     memory_keep_alive_time: u64,
     hook: bool,
+    timer_tick: u64,
+    scheduling_policy: SchedulingPolicyKind,
+    coroutine_queue_aging: u64,
+    task_queue_aging: u64,
+    queue_stats: bool,
+    coroutine_migration: bool,
+    local_queue_size: usize,
+    local_queue_capacity: usize,
+    steal_batch: usize,
+    global_queue_interval: u32,
+    work_stealing: bool,
+    pin_policy: PinPolicy,
+    task_queue_capacity: usize,
+    rejection_policy: RejectionPolicy,
+    adaptive: AdaptiveConfig,
 }
 
 impl Config {
@@ -40,6 +60,21 @@ impl Config {
             min_memory_count,
             memory_keep_alive_time,
             hook,
+            timer_tick: u64::try_from(DEFAULT_TIMER_TICK.as_nanos()).expect("overflow"),
+            scheduling_policy: SchedulingPolicyKind::default(),
+            coroutine_queue_aging: 0,
+            task_queue_aging: 0,
+            queue_stats: false,
+            coroutine_migration: false,
+            local_queue_size: 0,
+            local_queue_capacity: DEFAULT_LOCAL_CAPACITY,
+            steal_batch: 0,
+            global_queue_interval: DEFAULT_GLOBAL_INTERVAL,
+            work_stealing: true,
+            pin_policy: PinPolicy::default(),
+            task_queue_capacity: 0,
+            rejection_policy: RejectionPolicy::default(),
+            adaptive: AdaptiveConfig::default(),
         }
     }
 
@@ -83,6 +118,84 @@ impl Config {
         self.hook
     }
 
+    #[must_use]
+    pub fn timer_tick(&self) -> u64 {
+        self.timer_tick
+    }
+
+    #[must_use]
+    pub fn scheduling_policy(&self) -> SchedulingPolicyKind {
+        self.scheduling_policy
+    }
+
+    #[must_use]
+    pub fn coroutine_queue_aging(&self) -> u64 {
+        self.coroutine_queue_aging
+    }
+
+    #[must_use]
+    pub fn task_queue_aging(&self) -> u64 {
+        self.task_queue_aging
+    }
+
+    #[must_use]
+    pub fn queue_stats(&self) -> bool {
+        self.queue_stats
+    }
+
+    #[must_use]
+    pub fn coroutine_migration(&self) -> bool {
+        self.coroutine_migration
+    }
+
+    #[must_use]
+    pub fn local_queue_size(&self) -> usize {
+        if self.local_queue_size == 0 {
+            return self.event_loop_size;
+        }
+        self.local_queue_size
+    }
+
+    #[must_use]
+    pub fn local_queue_capacity(&self) -> usize {
+        self.local_queue_capacity
+    }
+
+    #[must_use]
+    pub fn steal_batch(&self) -> usize {
+        self.steal_batch
+    }
+
+    #[must_use]
+    pub fn global_queue_interval(&self) -> u32 {
+        self.global_queue_interval
+    }
+
+    #[must_use]
+    pub fn work_stealing(&self) -> bool {
+        self.work_stealing
+    }
+
+    #[must_use]
+    pub fn pin_policy(&self) -> PinPolicy {
+        self.pin_policy
+    }
+
+    #[must_use]
+    pub fn task_queue_capacity(&self) -> usize {
+        self.task_queue_capacity
+    }
+
+    #[must_use]
+    pub fn rejection_policy(&self) -> RejectionPolicy {
+        self.rejection_policy
+    }
+
+    #[must_use]
+    pub fn adaptive(&self) -> AdaptiveConfig {
+        self.adaptive
+    }
+
     pub fn set_event_loop_size(&mut self, event_loop_size: usize) -> &mut Self {
         assert!(
             event_loop_size > 0,
@@ -132,6 +245,90 @@ impl Config {
         self.hook = hook;
         self
     }
+
+    pub fn set_timer_tick(&mut self, timer_tick: u64) -> &mut Self {
+        assert!(timer_tick > 0, "timer_tick must be greater than 0");
+        self.timer_tick = timer_tick;
+        self
+    }
+
+    pub fn set_scheduling_policy(&mut self, scheduling_policy: SchedulingPolicyKind) -> &mut Self {
+        self.scheduling_policy = scheduling_policy;
+        self
+    }
+
+    pub fn set_coroutine_queue_aging(&mut self, coroutine_queue_aging: u64) -> &mut Self {
+        self.coroutine_queue_aging = coroutine_queue_aging;
+        self
+    }
+
+    pub fn set_task_queue_aging(&mut self, task_queue_aging: u64) -> &mut Self {
+        self.task_queue_aging = task_queue_aging;
+        self
+    }
+
+    pub fn set_queue_stats(&mut self, queue_stats: bool) -> &mut Self {
+        self.queue_stats = queue_stats;
+        self
+    }
+
+    pub fn set_coroutine_migration(&mut self, coroutine_migration: bool) -> &mut Self {
+        self.coroutine_migration = coroutine_migration;
+        self
+    }
+
+    pub fn set_local_queue_size(&mut self, local_queue_size: usize) -> &mut Self {
+        self.local_queue_size = local_queue_size;
+        self
+    }
+
+    pub fn set_local_queue_capacity(&mut self, local_queue_capacity: usize) -> &mut Self {
+        assert!(
+            local_queue_capacity > 0,
+            "local_queue_capacity must be greater than 0"
+        );
+        self.local_queue_capacity = local_queue_capacity;
+        self
+    }
+
+    pub fn set_steal_batch(&mut self, steal_batch: usize) -> &mut Self {
+        self.steal_batch = steal_batch;
+        self
+    }
+
+    pub fn set_global_queue_interval(&mut self, global_queue_interval: u32) -> &mut Self {
+        assert!(
+            global_queue_interval > 0,
+            "global_queue_interval must be greater than 0"
+        );
+        self.global_queue_interval = global_queue_interval;
+        self
+    }
+
+    pub fn set_work_stealing(&mut self, work_stealing: bool) -> &mut Self {
+        self.work_stealing = work_stealing;
+        self
+    }
+
+    pub fn set_pin_policy(&mut self, pin_policy: PinPolicy) -> &mut Self {
+        self.pin_policy = pin_policy;
+        self
+    }
+
+    pub fn set_task_queue_capacity(&mut self, task_queue_capacity: usize) -> &mut Self {
+        self.task_queue_capacity = task_queue_capacity;
+        self
+    }
+
+    pub fn set_rejection_policy(&mut self, rejection_policy: RejectionPolicy) -> &mut Self {
+        self.rejection_policy = rejection_policy;
+        self
+    }
+
+    pub fn set_adaptive(&mut self, adaptive: AdaptiveConfig) -> &mut Self {
+        self.adaptive = adaptive;
+        self
+    }
 }
 
 impl Default for Config {
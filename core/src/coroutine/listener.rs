@@ -47,6 +47,10 @@ pub trait Listener<Yield, Return>: Debug {
         message: &str,
     ) {
     }
+
+    /// Callback when the coroutine is migrated from the scheduler named `from`,
+    /// it's called in the thread of the new scheduler.
+    fn on_migrate(&self, local: &CoroutineLocal, from: &str) {}
 }
 
 macro_rules! broadcast {
@@ -110,4 +114,6 @@ where
         old_state: CoroutineState<Yield, Return>,
         message: &str
     ), "on_error");
+
+    broadcast!(on_migrate(local: &CoroutineLocal, from: &str), "on_migrate");
 }
@@ -72,6 +72,12 @@ impl<'c, Param, Yield, Return> Coroutine<'c, Param, Yield, Return> {
         self.id
     }
 
+    /// Change the priority of this coroutine, it takes effect the next time this coroutine
+    /// is pushed to an ordered queue.
+    pub fn set_priority(&mut self, priority: Option<c_longlong>) {
+        self.priority = priority;
+    }
+
     /// Returns the current state of this `StateCoroutine`.
     pub fn state(&self) -> CoroutineState<Yield, Return>
     where
@@ -75,7 +75,11 @@ where
                 self.on_running(self, old_state);
                 return Ok(());
             }
-            CoroutineState::Syscall(_, _, SyscallState::Callback | SyscallState::Timeout) => {
+            CoroutineState::Syscall(
+                _,
+                _,
+                SyscallState::Callback | SyscallState::Timeout | SyscallState::Cancelled,
+            ) => {
                 return Ok(());
             }
             _ => {}
@@ -140,13 +144,19 @@ where
         )))
     }
 
-    /// running -> cancel
+    /// ready/running/suspend -> cancel
+    ///
+    /// The coroutine in a syscall can't be cancelled, its fds and `io_uring` requests refer to its
+    /// stack, the syscall should fail with `ECANCELED` instead.
     ///
     /// # Errors
     /// if change state fails.
-    pub(super) fn cancel(&self) -> std::io::Result<()> {
+    pub(crate) fn cancel(&self) -> std::io::Result<()> {
         let current = self.state();
-        if CoroutineState::Running == current {
+        if matches!(
+            current,
+            CoroutineState::Ready | CoroutineState::Running | CoroutineState::Suspend(_, _)
+        ) {
             let new_state = CoroutineState::Cancelled;
             let old_state = self.change_state(new_state);
             self.on_cancel(self, old_state);
@@ -18,7 +18,8 @@ impl<Param, Yield> Suspender<'_, Param, Yield> {
         self.until_with(arg, get_timeout_time(delay))
     }
 
-    /// Delay the execution of the coroutine with an arg until `timestamp`.
+    /// Delay the execution of the coroutine with an arg until `timestamp`,
+    /// the `timestamp` is based on [`crate::common::now`].
     pub fn until_with(&self, arg: Yield, timestamp: u64) -> Param {
         TIMESTAMP.with(|s| unsafe {
             s.as_ptr()
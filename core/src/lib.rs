@@ -50,6 +50,7 @@
     clippy::indexing_slicing,
     clippy::separated_literal_suffix, // conflicts with clippy::unseparated_literal_suffix
     clippy::single_char_lifetime_names, // TODO: change lifetime names
+    clippy::non_std_lazy_statics, // TODO: migrate to std::sync::LazyLock
     unknown_lints, // for windows nightly
     linker_messages, // for windows nightly
     unused_attributes, // for windows nightly
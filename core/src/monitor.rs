@@ -1,5 +1,5 @@
 use crate::common::beans::BeanFactory;
-use crate::common::constants::{CoroutineState, MONITOR_BEAN};
+use crate::common::constants::{CoroutineState, COROUTINE_TIME_SLICE, MONITOR_BEAN, SLICE};
 use crate::common::{get_timeout_time, now, CondvarBlocker};
 use crate::coroutine::listener::Listener;
 use crate::coroutine::local::CoroutineLocal;
@@ -8,13 +8,13 @@ use crate::{catch, error, impl_current_for, impl_display_by_debug, info};
 #[cfg(unix)]
 use nix::sys::pthread::{pthread_kill, pthread_self, Pthread};
 #[cfg(unix)]
-use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, SigmaskHow, Signal};
 use std::cell::{Cell, UnsafeCell};
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::io::{Error, ErrorKind};
-use std::mem::MaybeUninit;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 use std::time::Duration;
 
@@ -44,14 +44,33 @@ enum MonitorState {
 
 impl_display_by_debug!(MonitorState);
 
+// the number of SIGURG handled, the selectors interrupted by them are not interrupted by users
+#[cfg(unix)]
+static PREEMPTIONS: AtomicUsize = AtomicUsize::new(0);
+
 /// The monitor impls.
 #[repr(C)]
 #[derive(Debug)]
 pub(crate) struct Monitor {
     notify_queue: UnsafeCell<HashSet<NotifyNode>>,
     state: Cell<MonitorState>,
-    thread: UnsafeCell<MaybeUninit<JoinHandle<()>>>,
+    thread: UnsafeCell<Option<JoinHandle<()>>>,
     blocker: Arc<CondvarBlocker>,
+    // the SIGURG handler before the monitor started, restored after the monitor stopped
+    #[cfg(unix)]
+    sigurg: Cell<Option<SigAction>>,
+    // the panic hook before the monitor started, restored after the monitor stopped
+    panic_hook: Mutex<Option<PanicHook>>,
+}
+
+type Hook = Box<dyn Fn(&std::panic::PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+struct PanicHook(Hook);
+
+impl Debug for PanicHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PanicHook")
+    }
 }
 
 impl Default for Monitor {
@@ -59,8 +78,11 @@ impl Default for Monitor {
         Monitor {
             notify_queue: UnsafeCell::default(),
             state: Cell::new(MonitorState::Created),
-            thread: UnsafeCell::new(MaybeUninit::uninit()),
+            thread: UnsafeCell::new(None),
             blocker: Arc::default(),
+            #[cfg(unix)]
+            sigurg: Cell::new(None),
+            panic_hook: Mutex::new(None),
         }
     }
 }
@@ -73,6 +95,7 @@ impl Monitor {
     fn start(&self) -> std::io::Result<()> {
         #[cfg(unix)]
         extern "C" fn sigurg_handler(_: libc::c_int) {
+            _ = PREEMPTIONS.fetch_add(1, Ordering::Release);
             if let Ok(mut set) = SigSet::thread_get_mask() {
                 //只抢占处于Running状态的协程。
                 //MonitorListener的设计理念是不对Syscall状态的协程发送信号。
@@ -102,10 +125,21 @@ impl Monitor {
             }
         }
         match self.state.get() {
-            MonitorState::Created => {
+            MonitorState::Created | MonitorState::Stopped => {
                 self.state.set(MonitorState::Running);
                 // install panic hook
+                *self.panic_hook.lock().expect("lock failed") =
+                    Some(PanicHook(std::panic::take_hook()));
                 std::panic::set_hook(Box::new(|panic_hook_info| {
+                    // the hooked syscalls issued while capturing the backtrace change the
+                    // coroutine back to running, block the preemption until the hook returns,
+                    // or the coroutine may be suspended with the allocator locked
+                    #[cfg(unix)]
+                    let mask = {
+                        let mut set = SigSet::empty();
+                        set.add(Signal::SIGURG);
+                        set.thread_swap_mask(SigmaskHow::SIG_BLOCK).ok()
+                    };
                     let syscall = crate::common::constants::SyscallName::panicking;
                     if let Some(co) = SchedulableCoroutine::current() {
                         let new_state = crate::common::constants::SyscallState::Executing;
@@ -132,6 +166,10 @@ impl Monitor {
                             error!("{} change to running state failed !", co.name());
                         }
                     }
+                    #[cfg(unix)]
+                    if let Some(mask) = mask {
+                        _ = mask.thread_set_mask();
+                    }
                 }));
                 #[cfg(unix)]
                 {
@@ -143,11 +181,12 @@ impl Monitor {
                         SaFlags::SA_RESTART,
                         set,
                     );
-                    unsafe { _ = sigaction(Signal::SIGURG, &sa)? };
+                    self.sigurg
+                        .set(Some(unsafe { sigaction(Signal::SIGURG, &sa)? }));
                 }
                 // start the monitor thread
                 let monitor = unsafe { &mut *self.thread.get() };
-                *monitor = MaybeUninit::new(
+                *monitor = Some(
                     std::thread::Builder::new()
                         .name("open-coroutine-monitor".to_string())
                         .spawn(|| {
@@ -166,9 +205,9 @@ impl Monitor {
                 Ok(())
             }
             MonitorState::Running => Ok(()),
-            MonitorState::Stopping | MonitorState::Stopped => Err(Error::new(
+            MonitorState::Stopping => Err(Error::new(
                 ErrorKind::Unsupported,
-                "Restart operation is unsupported !",
+                "The monitor is stopping !",
             )),
         }
     }
@@ -321,9 +360,38 @@ impl Monitor {
         }
     }
 
+    /// Returns the number of SIGURG handled.
+    #[cfg(unix)]
+    pub(crate) fn preemptions() -> usize {
+        PREEMPTIONS.load(Ordering::Acquire)
+    }
+
+    /// Stop the monitor and wait for the monitor thread to exit, the monitor can be started
+    /// again by the next preemptive coroutine.
     #[allow(dead_code)]
     pub(crate) fn stop() {
-        Self::get_instance().state.set(MonitorState::Stopping);
+        let monitor = Self::get_instance();
+        if MonitorState::Running != monitor.state.get() {
+            return;
+        }
+        monitor.state.set(MonitorState::Stopping);
+        monitor.blocker.notify();
+        if let Some(thread) = unsafe { &mut *monitor.thread.get() }.take() {
+            if thread.join().is_err() {
+                error!("monitor thread join failed !");
+            }
+        }
+        if let Some(PanicHook(hook)) = monitor.panic_hook.lock().expect("lock failed").take() {
+            // restore the panic hook
+            std::panic::set_hook(hook);
+        }
+        #[cfg(unix)]
+        if let Some(sa) = monitor.sigurg.take() {
+            // restore the SIGURG handler
+            if unsafe { sigaction(Signal::SIGURG, &sa) }.is_err() {
+                error!("restore SIGURG handler failed !");
+            }
+        }
     }
 
     fn submit(timestamp: u64) -> std::io::Result<NotifyNode> {
@@ -592,7 +660,11 @@ impl<Yield, Return> Listener<Yield, Return> for MonitorListener {
         match new_state {
             CoroutineState::Ready => {}
             CoroutineState::Running => {
-                let timestamp = get_timeout_time(Duration::from_millis(10));
+                let slice = local
+                    .get::<Duration>(COROUTINE_TIME_SLICE)
+                    .copied()
+                    .unwrap_or(SLICE);
+                let timestamp = get_timeout_time(slice);
                 if let Ok(node) = Monitor::submit(timestamp) {
                     _ = local.put(NOTIFY_NODE, node);
                 }
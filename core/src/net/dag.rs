@@ -0,0 +1,602 @@
+use crate::common::{get_timeout_time, now};
+use crate::net::join::JoinHandle;
+use crate::net::EventLoops;
+use crate::scheduler::SchedulableSuspender;
+use crate::warn;
+use std::collections::HashSet;
+use std::ffi::c_int;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// The error of the node whose task was rejected by the event-loop.
+pub const REJECTED_ERROR: &str = "The node was rejected";
+
+type NodeFunc = Box<dyn FnOnce(Vec<Option<usize>>) -> Result<Option<usize>, &'static str> + Send>;
+
+// the node ready to run, with the results of its dependencies
+type Ready = (usize, NodeFunc, Vec<Option<usize>>);
+
+/// What to do with the other nodes when a node fails.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum FailurePolicy {
+    /// Cancel all nodes which have not started, the running nodes are not interrupted.
+    #[default]
+    FailFast,
+    /// Cancel the nodes which depend on the failed node, the independent branches keep running.
+    ContinueIndependent,
+}
+
+/// The status of a node in the graph.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum NodeStatus {
+    /// The node is waiting for its dependencies or the event-loop.
+    Pending,
+    /// The node is running.
+    Running,
+    /// The node completed successfully.
+    Completed,
+    /// The node returned an error, panicked or was rejected.
+    Failed,
+    /// The node never ran because another node failed.
+    Cancelled,
+}
+
+impl NodeStatus {
+    /// Returns `true` if the node will not change anymore.
+    #[must_use]
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            NodeStatus::Completed | NodeStatus::Failed | NodeStatus::Cancelled
+        )
+    }
+}
+
+/// The status and timings of a node.
+#[repr(C)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeReport {
+    /// The name of the node.
+    pub name: String,
+    /// The status of the node.
+    pub status: NodeStatus,
+    /// The result of the node, `None` if it's not finished or cancelled.
+    pub result: Option<Result<Option<usize>, &'static str>>,
+    /// The timestamp in ns the node started, 0 if it never started.
+    pub started: u64,
+    /// The timestamp in ns the node finished, 0 if it's not finished.
+    pub finished: u64,
+}
+
+impl NodeReport {
+    /// The time the node ran, `None` if it never started or is still running.
+    #[must_use]
+    pub fn elapsed(&self) -> Option<Duration> {
+        (self.started != 0 && self.finished != 0)
+            .then(|| Duration::from_nanos(self.finished.saturating_sub(self.started)))
+    }
+}
+
+impl NodeReport {
+    /// Copy this report to the C compatible `raw` report, the name is not copied.
+    pub fn write_raw(&self, raw: &mut RawNodeReport) {
+        raw.status = self.status;
+        raw.has_result = self.result.is_some();
+        raw.result = self.result.unwrap_or(Ok(None)).into();
+        raw.started = self.started;
+        raw.finished = self.finished;
+    }
+
+    /// Read the report filled by `f`, the name is kept by the caller. Returns `None` if `f`
+    /// fails.
+    ///
+    /// # Safety
+    /// The error filled by `f` must be a static utf-8 string.
+    pub unsafe fn read_raw(
+        name: String,
+        mut f: impl FnMut(&mut RawNodeReport) -> c_int,
+    ) -> Option<Self> {
+        let mut raw = RawNodeReport {
+            status: NodeStatus::Pending,
+            has_result: false,
+            result: Ok(None).into(),
+            started: 0,
+            finished: 0,
+        };
+        if 0 != f(&mut raw) {
+            return None;
+        }
+        Some(NodeReport {
+            name,
+            status: raw.status,
+            result: raw.has_result.then(|| raw.result.into_result()),
+            started: raw.started,
+            finished: raw.finished,
+        })
+    }
+}
+
+/// The C compatible result of a node, see [`RawNodeFunc`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RawNodeResult {
+    /// Whether the node completed successfully.
+    pub ok: bool,
+    /// Whether the node returned a value, ignored if `ok` is `false`.
+    pub has_value: bool,
+    /// The value of the node, ignored if `has_value` is `false`.
+    pub value: usize,
+    /// The static utf-8 error of the node, ignored if `ok` is `true`.
+    pub error: *const u8,
+    /// The byte length of the error.
+    pub error_len: usize,
+}
+
+impl From<Result<Option<usize>, &'static str>> for RawNodeResult {
+    fn from(result: Result<Option<usize>, &'static str>) -> Self {
+        let error = result.err().unwrap_or_default();
+        let value = result.ok().flatten();
+        RawNodeResult {
+            ok: result.is_ok(),
+            has_value: value.is_some(),
+            value: value.unwrap_or_default(),
+            error: error.as_ptr(),
+            error_len: error.len(),
+        }
+    }
+}
+
+impl RawNodeResult {
+    /// Convert to the result of the node.
+    ///
+    /// # Safety
+    /// The error must be a static utf-8 string.
+    pub unsafe fn into_result(self) -> Result<Option<usize>, &'static str> {
+        if self.ok {
+            return Ok(self.has_value.then_some(self.value));
+        }
+        if self.error.is_null() {
+            return Err("Graph node failed without message");
+        }
+        Err(std::str::from_utf8_unchecked(std::slice::from_raw_parts(
+            self.error,
+            self.error_len,
+        )))
+    }
+}
+
+/// The function of a node created by C, it's called with `param` and the `len` results of
+/// the dependencies, then writes the result of the node to `output`, which is `Ok(None)`
+/// unless it's written.
+pub type RawNodeFunc = extern "C" fn(
+    param: usize,
+    inputs: *const RawNodeResult,
+    len: usize,
+    output: *mut RawNodeResult,
+);
+
+/// The C compatible [`NodeReport`] without the name.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RawNodeReport {
+    /// The status of the node.
+    pub status: NodeStatus,
+    /// Whether the node has a result.
+    pub has_result: bool,
+    /// The result of the node, ignored if `has_result` is `false`.
+    pub result: RawNodeResult,
+    /// The timestamp in ns the node started, 0 if it never started.
+    pub started: u64,
+    /// The timestamp in ns the node finished, 0 if it's not finished.
+    pub finished: u64,
+}
+
+/// The report of all nodes in the graph, in the order they were added.
+#[repr(C)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GraphReport {
+    /// The reports of the nodes, indexed by the node index.
+    pub nodes: Vec<NodeReport>,
+}
+
+impl GraphReport {
+    /// Returns `true` if all nodes completed successfully.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.nodes
+            .iter()
+            .all(|node| NodeStatus::Completed == node.status)
+    }
+}
+
+#[derive(educe::Educe)]
+#[educe(Debug)]
+struct Node {
+    deps: Vec<usize>,
+    dependents: Vec<usize>,
+    // the number of dependencies not completed yet
+    remaining: usize,
+    #[educe(Debug(ignore))]
+    func: Option<NodeFunc>,
+    report: NodeReport,
+}
+
+/// A graph of tasks, a node runs once all its dependencies completed, with their results as
+/// inputs. The ready nodes run in parallel across the event-loops.
+#[derive(Debug, Default)]
+pub struct TaskGraph {
+    policy: FailurePolicy,
+    nodes: Vec<Node>,
+}
+
+impl TaskGraph {
+    /// Create a new empty graph.
+    #[must_use]
+    pub fn new(policy: FailurePolicy) -> Self {
+        TaskGraph {
+            policy,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Add a node which depends on the `deps` nodes, returns the index of the node.
+    ///
+    /// The node receives the results of `deps` in the same order. The dependencies must be
+    /// added before, so the graph can never contain a cycle.
+    ///
+    /// # Errors
+    /// if any of the `deps` does not exist.
+    pub fn add_node(
+        &mut self,
+        name: &str,
+        deps: &[usize],
+        func: impl FnOnce(Vec<Option<usize>>) -> Result<Option<usize>, &'static str> + Send + 'static,
+    ) -> std::io::Result<usize> {
+        let index = self.nodes.len();
+        if let Some(dep) = deps.iter().find(|dep| **dep >= index) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("The dependency {dep} of node {name} does not exist"),
+            ));
+        }
+        for dep in deps {
+            self.nodes[*dep].dependents.push(index);
+        }
+        self.nodes.push(Node {
+            deps: deps.to_vec(),
+            dependents: Vec::new(),
+            remaining: deps.len(),
+            func: Some(Box::new(func)),
+            report: NodeReport {
+                name: name.to_string(),
+                status: NodeStatus::Pending,
+                result: None,
+                started: 0,
+                finished: 0,
+            },
+        });
+        Ok(index)
+    }
+
+    /// Returns the number of nodes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the graph has no node.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Submit the nodes without dependencies to the event-loops, the others are submitted once
+    /// their dependencies completed.
+    #[must_use]
+    pub fn submit(mut self) -> TaskGraphHandle {
+        let ready = self
+            .nodes
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, node)| 0 == node.remaining)
+            .filter_map(|(index, node)| node.func.take().map(|func| (index, func, Vec::new())))
+            .collect();
+        let state = Arc::new(GraphState {
+            inner: Mutex::new(GraphInner {
+                policy: self.policy,
+                unfinished: self.nodes.len(),
+                nodes: self.nodes,
+            }),
+            cvar: Condvar::new(),
+            handles: Mutex::new(Vec::new()),
+        });
+        GraphState::spawn(&state, ready);
+        TaskGraphHandle(state)
+    }
+}
+
+#[derive(Debug)]
+struct GraphInner {
+    policy: FailurePolicy,
+    // the number of nodes not in terminal status
+    unfinished: usize,
+    nodes: Vec<Node>,
+}
+
+impl GraphInner {
+    fn finish(&mut self, index: usize, status: NodeStatus) {
+        let node = &mut self.nodes[index];
+        node.report.status = status;
+        node.report.finished = now().max(1);
+        self.unfinished -= 1;
+    }
+
+    // cancel the pending nodes affected by the failed node
+    fn cancel(&mut self, failed: usize) {
+        let affected: Vec<usize> = match self.policy {
+            FailurePolicy::FailFast => (0..self.nodes.len()).collect(),
+            FailurePolicy::ContinueIndependent => {
+                // the dependents are always added after their dependencies
+                let mut affected = HashSet::from([failed]);
+                for index in failed + 1..self.nodes.len() {
+                    if self.nodes[index]
+                        .deps
+                        .iter()
+                        .any(|dep| affected.contains(dep))
+                    {
+                        _ = affected.insert(index);
+                    }
+                }
+                affected.into_iter().collect()
+            }
+        };
+        for index in affected {
+            let node = &mut self.nodes[index];
+            if NodeStatus::Pending == node.report.status {
+                // the submitted node will see the status and skip running
+                drop(node.func.take());
+                self.finish(index, NodeStatus::Cancelled);
+            }
+        }
+    }
+
+    // record the result, returns the nodes which become ready
+    fn complete(
+        &mut self,
+        index: usize,
+        result: Result<Option<usize>, &'static str>,
+    ) -> Vec<Ready> {
+        if self.nodes[index].report.status.is_terminal() {
+            return Vec::new();
+        }
+        let succeed = result.is_ok();
+        self.nodes[index].report.result = Some(result);
+        if !succeed {
+            self.finish(index, NodeStatus::Failed);
+            self.cancel(index);
+            return Vec::new();
+        }
+        self.finish(index, NodeStatus::Completed);
+        let mut ready = Vec::new();
+        for dependent in self.nodes[index].dependents.clone() {
+            let node = &mut self.nodes[dependent];
+            node.remaining -= 1;
+            if node.remaining > 0 || NodeStatus::Pending != node.report.status {
+                continue;
+            }
+            let Some(func) = node.func.take() else {
+                continue;
+            };
+            let inputs = self.nodes[dependent]
+                .deps
+                .iter()
+                .map(|dep| {
+                    self.nodes[*dep]
+                        .report
+                        .result
+                        .and_then(Result::ok)
+                        .flatten()
+                })
+                .collect();
+            ready.push((dependent, func, inputs));
+        }
+        ready
+    }
+}
+
+#[derive(Debug)]
+struct GraphState {
+    inner: Mutex<GraphInner>,
+    cvar: Condvar,
+    // the handles of the submitted nodes not reaped yet
+    handles: Mutex<Vec<NodeHandle>>,
+}
+
+// the node index and the handle of its task
+#[derive(Debug)]
+struct NodeHandle(usize, JoinHandle);
+
+// the event-loops are shared by all threads, just like `EventLoops`
+unsafe impl Send for NodeHandle {}
+
+impl GraphState {
+    fn lock(&self) -> MutexGuard<'_, GraphInner> {
+        self.inner.lock().expect("lock graph failed")
+    }
+
+    // the lock must not be held, the task may run in the caller thread
+    fn spawn(state: &Arc<Self>, mut ready: Vec<Ready>) {
+        while let Some((index, func, inputs)) = ready.pop() {
+            let name = state.lock().nodes[index].report.name.clone();
+            let arc = state.clone();
+            let handle = EventLoops::submit_task(
+                Some(name.clone()),
+                move |_| {
+                    Self::run(&arc, index, func, inputs);
+                    None
+                },
+                None,
+                None,
+            );
+            if handle.id().is_err() {
+                warn!("Graph node {name} was rejected !");
+                ready.extend(state.complete(index, Err(REJECTED_ERROR)));
+                continue;
+            }
+            state
+                .handles
+                .lock()
+                .expect("lock graph handles failed")
+                .push(NodeHandle(index, handle));
+        }
+    }
+
+    // fail the nodes whose tasks finished without running them, such as the timed out or
+    // cancelled tasks, otherwise they would stay pending forever
+    fn reap(state: &Arc<Self>) {
+        let mut failed = Vec::new();
+        state
+            .handles
+            .lock()
+            .expect("lock graph handles failed")
+            .retain(|NodeHandle(index, handle)| match handle.try_join() {
+                Some(Err(e)) => {
+                    failed.push((*index, e));
+                    false
+                }
+                Some(Ok(_)) => false,
+                None => true,
+            });
+        for (index, e) in failed {
+            let ready = state.complete(index, Err(e));
+            Self::spawn(state, ready);
+        }
+    }
+
+    fn run(state: &Arc<Self>, index: usize, func: NodeFunc, inputs: Vec<Option<usize>>) {
+        // never bind inside the log macros, they may compile to nothing
+        #[allow(unused_variables)]
+        let name = {
+            let mut inner = state.lock();
+            let report = &mut inner.nodes[index].report;
+            if NodeStatus::Pending != report.status {
+                // cancelled before running
+                return;
+            }
+            report.status = NodeStatus::Running;
+            report.started = now().max(1);
+            report.name.clone()
+        };
+        let result = crate::catch!(
+            move || func(inputs),
+            String::from("Graph node failed without message"),
+            format!("graph node {name}")
+        )
+        .and_then(|result| result);
+        let ready = state.complete(index, result);
+        Self::spawn(state, ready);
+    }
+
+    fn complete(&self, index: usize, result: Result<Option<usize>, &'static str>) -> Vec<Ready> {
+        let mut inner = self.lock();
+        let ready = inner.complete(index, result);
+        if 0 == inner.unfinished {
+            self.cvar.notify_all();
+        }
+        ready
+    }
+}
+
+/// The handle of a submitted [`TaskGraph`].
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct TaskGraphHandle(Arc<GraphState>);
+
+impl TaskGraphHandle {
+    /// Returns `true` if all nodes finished.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        GraphState::reap(&self.0);
+        0 == self.0.lock().unfinished
+    }
+
+    /// Get the current status and timings of the node, `None` if it does not exist.
+    #[must_use]
+    pub fn node_report(&self, index: usize) -> Option<NodeReport> {
+        GraphState::reap(&self.0);
+        self.0
+            .lock()
+            .nodes
+            .get(index)
+            .map(|node| node.report.clone())
+    }
+
+    /// Get the current status and timings of all nodes.
+    #[must_use]
+    pub fn report(&self) -> GraphReport {
+        GraphState::reap(&self.0);
+        GraphReport {
+            nodes: self
+                .0
+                .lock()
+                .nodes
+                .iter()
+                .map(|node| node.report.clone())
+                .collect(),
+        }
+    }
+
+    /// join with `Duration`.
+    ///
+    /// # Errors
+    /// see `timeout_at_join`.
+    pub fn timeout_join(&self, dur: Duration) -> std::io::Result<GraphReport> {
+        self.timeout_at_join(get_timeout_time(dur))
+    }
+
+    /// join.
+    ///
+    /// # Errors
+    /// see `timeout_at_join`.
+    pub fn join(&self) -> std::io::Result<GraphReport> {
+        self.timeout_at_join(u64::MAX)
+    }
+
+    /// Wait until all nodes finished, returns the report of the graph.
+    ///
+    /// # Errors
+    /// if join timeout.
+    pub fn timeout_at_join(&self, timeout_time: u64) -> std::io::Result<GraphReport> {
+        if let Some(suspender) = SchedulableSuspender::current() {
+            // the nodes may run on the current event-loop, never block it
+            while !self.is_finished() {
+                if timeout_time.saturating_sub(now()) == 0 {
+                    return Err(Error::new(ErrorKind::TimedOut, "wait timeout"));
+                }
+                suspender.delay(Duration::from_millis(1));
+            }
+            return Ok(self.report());
+        }
+        while !self.is_finished() {
+            let left = timeout_time.saturating_sub(now());
+            if left == 0 {
+                return Err(Error::new(ErrorKind::TimedOut, "wait timeout"));
+            }
+            // wake up in slices to reap the tasks finished without running their nodes
+            drop(
+                self.0
+                    .cvar
+                    .wait_timeout_while(
+                        self.0.lock(),
+                        Duration::from_nanos(left).min(Duration::from_millis(10)),
+                        |inner| inner.unfinished > 0,
+                    )
+                    .map_err(|e| Error::other(format!("{e}")))?,
+            );
+        }
+        Ok(self.report())
+    }
+}
@@ -42,7 +42,8 @@ cfg_if::cfg_if! {
 pub(crate) struct EventLoop<'e> {
     stop: Arc<(Mutex<bool>, Condvar)>,
     shared_stop: Arc<(Mutex<AtomicUsize>, Condvar)>,
-    cpu: usize,
+    // the cpu to pin, `None` means not pinned
+    cpu: Option<usize>,
     #[cfg(any(
         all(target_os = "linux", feature = "io_uring"),
         all(windows, feature = "iocp")
@@ -94,7 +95,8 @@ impl Default for EventLoop<'_> {
         let random_cpu_index = rand::rng().random_range(0..max_cpu_index);
         Self::new(
             format!("open-coroutine-event-loop-{random_cpu_index}"),
-            random_cpu_index,
+            "",
+            Some(random_cpu_index),
             crate::common::constants::DEFAULT_STACK_SIZE,
             0,
             65536,
@@ -107,10 +109,15 @@ impl Default for EventLoop<'_> {
 
 static COROUTINE_TOKENS: Lazy<DashSet<u64>> = Lazy::new(DashSet::new);
 
+// the number of signals handled while the selectors were waiting, see `EventLoop::interrupts`
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
 impl<'e> EventLoop<'e> {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         name: String,
-        cpu: usize,
+        runtime: &str,
+        cpu: Option<usize>,
         stack_size: usize,
         min_size: usize,
         max_size: usize,
@@ -125,23 +132,25 @@ impl<'e> EventLoop<'e> {
                 all(target_os = "linux", feature = "io_uring"),
                 all(windows, feature = "iocp")
             ))]
-            operator: crate::net::operator::Operator::new(cpu)?,
+            operator: crate::net::operator::Operator::new(cpu.unwrap_or_default())?,
             #[cfg(any(
                 all(target_os = "linux", feature = "io_uring"),
                 all(windows, feature = "iocp")
             ))]
             syscall_wait_table: DashMap::new(),
             selector: Poller::new()?,
-            pool: CoroutinePool::new(name, stack_size, min_size, max_size, keep_alive_time),
+            pool: CoroutinePool::with_runtime(
+                name,
+                runtime,
+                stack_size,
+                min_size,
+                max_size,
+                keep_alive_time,
+            ),
             phantom_data: PhantomData,
         })
     }
 
-    /// Try to cancel a task from `CoroutinePool`.
-    pub(super) fn try_cancel_task(task_id: u64) {
-        CoroutinePool::try_cancel_task(task_id);
-    }
-
     #[allow(trivial_numeric_casts, clippy::cast_possible_truncation)]
     fn token(syscall: SyscallName) -> u64 {
         // Coroutine path: consistent hash of (coroutine_name).
@@ -214,14 +223,43 @@ impl<'e> EventLoop<'e> {
             let left_time = timeout_time
                 .saturating_sub(crate::common::now())
                 .min(10_000_000);
+            // the coroutine may be migrated to other event-loops by the previous wait
+            let event_loop = Self::current().unwrap_or(self);
             if left_time == 0 {
                 //timeout
-                return self.wait_just(Some(Duration::ZERO));
+                return event_loop.wait_just(Some(Duration::ZERO));
+            }
+            event_loop.wait_just(Some(Duration::from_nanos(left_time)))?;
+        }
+    }
+
+    /// The error of the syscall parked by the cancelled coroutine, the errno is set too.
+    fn cancelled() -> Error {
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                let errno = windows_sys::Win32::Foundation::ERROR_CANCELLED;
+                #[cfg(feature = "syscall")]
+                crate::syscall::set_errno(errno);
+                Error::from_raw_os_error(c_int::try_from(errno).unwrap_or(c_int::MAX))
+            } else {
+                #[cfg(feature = "syscall")]
+                crate::syscall::set_errno(libc::ECANCELED);
+                Error::from_raw_os_error(libc::ECANCELED)
             }
-            self.wait_just(Some(Duration::from_nanos(left_time)))?;
         }
     }
 
+    /// Cancel the `io_uring` request submitted with `token`, returns `false` if the request is
+    /// not submitted by this event-loop or is done.
+    #[cfg(all(target_os = "linux", feature = "io_uring"))]
+    pub(super) fn try_cancel_syscall(&self, token: u64) -> std::io::Result<bool> {
+        if !self.syscall_wait_table.contains_key(&token) {
+            return Ok(false);
+        }
+        self.operator.async_cancel(token)?;
+        Ok(true)
+    }
+
     pub(super) fn wait_just(&self, timeout: Option<Duration>) -> std::io::Result<()> {
         let mut left_time = timeout;
         if let Some(time) = left_time {
@@ -248,7 +286,9 @@ impl<'e> EventLoop<'e> {
                 if let CoroutineState::Syscall(
                     (),
                     syscall,
-                    SyscallState::Callback | SyscallState::Timeout,
+                    syscall_state @ (SyscallState::Callback
+                    | SyscallState::Timeout
+                    | SyscallState::Cancelled),
                 ) = co.state()
                 {
                     let new_state = SyscallState::Executing;
@@ -260,40 +300,72 @@ impl<'e> EventLoop<'e> {
                             new_state
                         );
                     }
+                    if SyscallState::Cancelled == syscall_state {
+                        return Err(Self::cancelled());
+                    }
                 }
             }
         }
 
+        // the coroutine may be migrated to other event-loops while suspended, never touch the
+        // selector and the queues of the previous one from this thread
+        let event_loop = Self::current().unwrap_or(self);
+        let preemptions = Self::preemptions();
         cfg_if::cfg_if! {
             if #[cfg(all(target_os = "linux", feature = "io_uring"))] {
-                match self.adapt_io_uring(left_time) {
+                match event_loop.adapt_io_uring(left_time) {
                     Ok(t) => left_time = t,
-                    Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => {
+                        Self::interrupted(preemptions);
+                    }
                     Err(e) => return Err(e),
                 }
             } else if #[cfg(all(windows, feature = "iocp"))] {
-                left_time = self.adapt_iocp(left_time)?;
+                left_time = event_loop.adapt_iocp(left_time)?;
             }
         }
 
         // use epoll/kevent/iocp
         let mut events = Events::with_capacity(1024);
         // mio 1.x does not internally retry on EINTR, so handle it here
-        match self.selector.select(&mut events, left_time) {
+        match event_loop.selector.select(&mut events, left_time) {
             Ok(()) => {}
-            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => Self::interrupted(preemptions),
             Err(e) => return Err(e),
         }
         #[allow(clippy::explicit_iter_loop)]
         for event in events.iter() {
             let token = event.get_token();
             if event.readable() || event.writable() {
-                unsafe { self.resume(token) };
+                unsafe { event_loop.resume(token) };
             }
         }
         Ok(())
     }
 
+    /// Returns the number of signals handled while the selectors were waiting, the preemption
+    /// signal is not counted. The signals handled by other threads are not counted either.
+    pub(crate) fn interrupts() -> usize {
+        INTERRUPTS.load(Ordering::Acquire)
+    }
+
+    fn preemptions() -> usize {
+        cfg_if::cfg_if! {
+            if #[cfg(all(unix, feature = "preemptive"))] {
+                crate::monitor::Monitor::preemptions()
+            } else {
+                0
+            }
+        }
+    }
+
+    // the selector was interrupted by a signal handler
+    fn interrupted(preemptions: usize) {
+        if Self::preemptions() == preemptions {
+            _ = INTERRUPTS.fetch_add(1, Ordering::Release);
+        }
+    }
+
     #[cfg(all(target_os = "linux", feature = "io_uring"))]
     fn adapt_io_uring(&self, mut left_time: Option<Duration>) -> std::io::Result<Option<Duration>> {
         if crate::net::operator::support_io_uring() {
@@ -305,7 +377,9 @@ impl<'e> EventLoop<'e> {
             // when completed CQEs are sitting in the completion queue.
             for cqe in &mut cq {
                 let token = cqe.user_data();
-                if crate::common::constants::IO_URING_TIMEOUT_USERDATA == token {
+                if crate::common::constants::IO_URING_TIMEOUT_USERDATA == token
+                    || crate::common::constants::IO_URING_CANCEL_USERDATA == token
+                {
                     continue;
                 }
                 // resolve completed read/write tasks
@@ -354,7 +428,7 @@ impl<'e> EventLoop<'e> {
         self.try_resume(token);
     }
 
-    pub(super) fn start(self) -> std::io::Result<Arc<Self>>
+    pub(super) fn start(self) -> std::io::Result<&'static Self>
     where
         'e: 'static,
     {
@@ -384,15 +458,28 @@ impl<'e> EventLoop<'e> {
                         cvar.notify_one();
                     }
                     // thread per core
-                    info!(
-                        "{} has started, bind to CPU:{}",
-                        consumer.name(),
-                        core_affinity::set_for_current(core_affinity::CoreId { id: consumer.cpu })
-                    );
+                    if let Some(cpu) = consumer.cpu {
+                        // never bind inside the log macros, they may compile to nothing
+                        #[allow(unused_variables)]
+                        let pinned =
+                            core_affinity::set_for_current(core_affinity::CoreId { id: cpu });
+                        #[allow(unused_variables)]
+                        let local_memory = crate::common::topology::prefer_local_memory(cpu);
+                        info!(
+                            "{} has started, bind to CPU:{}, prefer local memory:{:?}",
+                            consumer.name(),
+                            pinned,
+                            local_memory
+                        );
+                    } else {
+                        info!("{} has started, not pinned", consumer.name());
+                    }
                     Self::init_current(consumer);
                     while PoolState::Running == consumer.state()
                         || !consumer.is_local_empty()
                         || consumer.get_running_size() > 0
+                        // the coroutines stolen from other event-loops
+                        || !consumer.is_ready_empty()
                     {
                         _ = consumer.wait_event(Some(SLICE));
                     }
@@ -413,12 +500,9 @@ impl<'e> EventLoop<'e> {
                     info!("{} has exited", consumer.name());
                 })?,
         );
-        unsafe {
-            Ok(Arc::from_raw(
-                BeanFactory::get_bean::<Self>(bean_name)
-                    .unwrap_or_else(|| panic!("bean {bean_name} not exist !")),
-            ))
-        }
+        // the event-loop bean is never dropped, the join handles may outlive its runtime
+        Ok(BeanFactory::get_bean::<Self>(bean_name)
+            .unwrap_or_else(|| panic!("bean {bean_name} not exist !")))
     }
 
     fn get_thread_name(&self) -> String {
@@ -444,7 +528,7 @@ impl<'e> EventLoop<'e> {
                 return Err(Error::new(ErrorKind::TimedOut, "stop timeout !"));
             }
             self.wait_event(Some(Duration::from_nanos(left_time).min(SLICE)))?;
-            if self.is_local_empty() && self.get_running_size() == 0 {
+            if self.is_local_empty() && self.get_running_size() == 0 && self.is_ready_empty() {
                 assert_eq!(PoolState::Stopping, self.stopped()?);
                 return Ok(());
             }
@@ -454,7 +538,7 @@ impl<'e> EventLoop<'e> {
     pub(super) fn stop(&self, wait_time: Duration) -> std::io::Result<()> {
         match self.state() {
             PoolState::Running => {
-                if BeanFactory::remove_bean::<JoinHandle<()>>(&self.get_thread_name()).is_some() {
+                if BeanFactory::get_bean::<JoinHandle<()>>(&self.get_thread_name()).is_some() {
                     assert_eq!(PoolState::Running, self.stopping()?);
                     return self.do_stop(wait_time);
                 }
@@ -482,7 +566,13 @@ impl<'e> EventLoop<'e> {
             return Err(Error::new(ErrorKind::TimedOut, "stop timeout !"));
         }
         assert_eq!(PoolState::Stopping, self.stopped()?);
-        assert!(BeanFactory::remove_bean::<Self>(self.name()).is_some());
+        if let Some(thread) = BeanFactory::remove_bean::<JoinHandle<()>>(&self.get_thread_name()) {
+            if thread.join().is_err() {
+                error!("{} join failed !", self.get_thread_name());
+            }
+        }
+        // the join handles of the tasks may outlive the event-loop
+        assert!(BeanFactory::detach_bean(self.name()));
         Ok(())
     }
 }
@@ -587,7 +677,7 @@ mod tests {
         event_loop.stop_sync(Duration::from_secs(3))
     }
 
-    #[ignore]
+    #[ignore = "event loop started in background may not stop in time"]
     #[test]
     fn test_simple_auto() -> std::io::Result<()> {
         let event_loop = EventLoop::default().start()?;
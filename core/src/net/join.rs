@@ -1,12 +1,12 @@
 use crate::net::event_loop::EventLoop;
+use std::ffi::c_longlong;
 use std::io::{Error, ErrorKind};
-use std::sync::Arc;
 use std::time::Duration;
 
 #[allow(missing_docs)]
 #[repr(C)]
 #[derive(Debug)]
-pub struct JoinHandle(&'static Arc<EventLoop<'static>>, u64);
+pub struct JoinHandle(&'static EventLoop<'static>, u64, bool);
 
 impl Drop for JoinHandle {
     fn drop(&mut self) {
@@ -18,26 +18,59 @@ impl Drop for JoinHandle {
 
 impl JoinHandle {
     /// create `JoinHandle` instance.
-    pub(crate) fn err(pool: &'static Arc<EventLoop<'static>>) -> Self {
-        Self::new(pool, 0)
+    pub(crate) fn err(pool: &'static EventLoop<'static>, error: &Error) -> Self {
+        JoinHandle(pool, 0, ErrorKind::WouldBlock == error.kind())
     }
 
     /// create `JoinHandle` instance.
-    pub(crate) fn new(pool: &'static Arc<EventLoop<'static>>, task_id: u64) -> Self {
-        JoinHandle(pool, task_id)
+    pub(crate) fn new(pool: &'static EventLoop<'static>, task_id: u64) -> Self {
+        JoinHandle(pool, task_id, false)
+    }
+
+    /// Returns `true` if the task was rejected because the task queue is full.
+    #[must_use]
+    pub fn is_rejected(&self) -> bool {
+        self.2
     }
 
     /// get the task id.
     ///
     /// # Errors
-    /// if the task id is invalid.
+    /// if the task id is invalid, or the task was rejected.
     pub fn id(&self) -> std::io::Result<u64> {
+        if self.2 {
+            return Err(Error::new(ErrorKind::WouldBlock, "The task was rejected"));
+        }
         if 0 == self.1 {
             return Err(Error::new(ErrorKind::InvalidInput, "Invalid task id"));
         }
         Ok(self.1)
     }
 
+    /// Try to cancel the task in the runtime it's submitted to.
+    ///
+    /// # Errors
+    /// if the task id is invalid, or the task was rejected.
+    pub fn try_cancel(&self) -> std::io::Result<()> {
+        self.0.try_cancel_task(self.id()?);
+        Ok(())
+    }
+
+    /// Try to change the priority of the task in the runtime it's submitted to, returns `false`
+    /// if the task is not found.
+    /// See [`crate::co_pool::CoroutinePool::try_set_task_priority`].
+    ///
+    /// # Errors
+    /// if the task id is invalid, or the task was rejected.
+    pub fn try_set_priority(&self, priority: Option<c_longlong>) -> std::io::Result<bool> {
+        Ok(self.0.try_set_task_priority(self.id()?, priority))
+    }
+
+    /// Take the result of the task without waiting, `None` if the task is not finished.
+    pub(crate) fn try_join(&self) -> Option<Result<Option<usize>, &'static str>> {
+        self.0.try_take_task_result(self.id().ok()?)
+    }
+
     /// join with `Duration`.
     ///
     /// # Errors
@@ -69,3 +102,35 @@ impl JoinHandle {
         )
     }
 }
+
+/// The handle of a periodic task, see [`crate::net::EventLoops::submit_at_fixed_rate`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ScheduleHandle(&'static EventLoop<'static>, u64);
+
+impl ScheduleHandle {
+    /// create `ScheduleHandle` instance.
+    pub(crate) fn new(pool: &'static EventLoop<'static>, schedule_id: u64) -> Self {
+        ScheduleHandle(pool, schedule_id)
+    }
+
+    /// get the schedule id.
+    ///
+    /// # Errors
+    /// if the schedule id is invalid.
+    pub fn id(&self) -> std::io::Result<u64> {
+        if 0 == self.1 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Invalid schedule id"));
+        }
+        Ok(self.1)
+    }
+
+    /// Cancel the periodic task, the running task is not cancelled.
+    ///
+    /// Returns `false` if the schedule is invalid or already cancelled.
+    #[must_use]
+    pub fn cancel(&self) -> bool {
+        self.id()
+            .is_ok_and(|schedule_id| self.0.try_cancel_schedule(schedule_id))
+    }
+}
@@ -1,20 +1,34 @@
+use crate::co_pool::adaptive::{AdaptiveConfig, AdaptiveController, Decision, LoadSignals};
+use crate::co_pool::listener::TaskListener;
+use crate::co_pool::schedule::OverrunPolicy;
+use crate::co_pool::task::{DeadlineMissPolicy, RetryPolicy, Task, TaskInfo, TimeoutFrom};
+use crate::common::beans::BeanFactory;
+use crate::common::constants::{
+    runtime_bean, COROUTINE_GLOBAL_QUEUE_BEAN, COROUTINE_SUSPEND_QUEUES_BEAN,
+    TASK_GLOBAL_QUEUE_BEAN, TASK_REGISTRY_BEAN,
+};
+use crate::common::ordered_work_steal::OrderedWorkStealQueue;
+use crate::common::topology::CpuTopology;
+use crate::common::{get_timeout_time, now};
 use crate::config::Config;
 use crate::coroutine::suspender::Suspender;
 use crate::net::event_loop::EventLoop;
-use crate::net::join::JoinHandle;
+use crate::net::join::{JoinHandle, ScheduleHandle};
+use crate::net::stats::RuntimeStats;
+use crate::scheduler::SchedulableCoroutine;
 use crate::{error, info};
-use once_cell::sync::OnceCell;
 use std::collections::VecDeque;
-use std::ffi::{c_int, c_longlong};
+use std::ffi::{c_char, c_int, c_longlong, CStr, CString};
+use std::fmt::Debug;
 use std::io::{Error, ErrorKind};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::time::Duration;
 
 cfg_if::cfg_if! {
     if #[cfg(all(target_os = "linux", feature = "io_uring"))] {
         use libc::{epoll_event, iovec, mode_t, msghdr, off_t, size_t, sockaddr, socklen_t};
-        use std::ffi::{c_char, c_uint, c_void};
+        use std::ffi::{c_uint, c_void};
     }
 }
 
@@ -47,15 +61,116 @@ pub mod event_loop;
 /// Task join abstraction and impl.
 pub mod join;
 
-static INSTANCE: OnceCell<EventLoops> = OnceCell::new();
+/// Task dependency graph abstraction and impl.
+pub mod dag;
+
+/// Isolated named pool abstraction and impl.
+pub mod pool;
+
+/// Runtime statistics.
+pub mod stats;
+
+/// The options to submit a task, see [`EventLoops::submit_with_options`].
+#[repr(C)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TaskOptions {
+    /// The name of the task, a unique name is generated if it's `None`.
+    pub name: Option<String>,
+    /// The priority of the task.
+    pub priority: Option<c_longlong>,
+    /// The min stack size of the task, see [`Task::with_stack_size`].
+    pub stack_size: Option<usize>,
+    /// The index of the event-loop to run the task, a round-robin one is used if it's `None`.
+    pub event_loop: Option<usize>,
+    /// The absolute deadline in ns and its miss policy, see [`Task::with_deadline`].
+    pub deadline: Option<(u64, DeadlineMissPolicy)>,
+}
+
+impl TaskOptions {
+    /// Call `f` with the C compatible options, the name is valid until `f` returns.
+    ///
+    /// # Panics
+    /// if the name contains nul.
+    pub fn with_raw<R>(&self, f: impl FnOnce(&RawTaskOptions) -> R) -> R {
+        let name = self
+            .name
+            .as_deref()
+            .map(|name| CString::new(name).expect("the task name contains nul"));
+        let (deadline, miss_policy) = self.deadline.unwrap_or_default();
+        f(&RawTaskOptions {
+            name: name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr()),
+            has_priority: self.priority.is_some(),
+            priority: self.priority.unwrap_or_default(),
+            stack_size: self.stack_size.unwrap_or_default(),
+            has_event_loop: self.event_loop.is_some(),
+            event_loop: self.event_loop.unwrap_or_default(),
+            has_deadline: self.deadline.is_some(),
+            deadline,
+            miss_policy,
+        })
+    }
+
+    /// Read the C compatible options, the invalid utf-8 of the name is replaced.
+    ///
+    /// # Safety
+    /// The `name` of `raw` must be null or a valid nul-terminated string.
+    #[must_use]
+    pub unsafe fn from_raw(raw: &RawTaskOptions) -> Self {
+        TaskOptions {
+            name: (!raw.name.is_null())
+                .then(|| CStr::from_ptr(raw.name).to_string_lossy().into_owned()),
+            priority: raw.has_priority.then_some(raw.priority),
+            stack_size: (raw.stack_size > 0).then_some(raw.stack_size),
+            event_loop: raw.has_event_loop.then_some(raw.event_loop),
+            deadline: raw.has_deadline.then_some((raw.deadline, raw.miss_policy)),
+        }
+    }
+}
+
+/// The C compatible [`TaskOptions`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawTaskOptions {
+    /// The nul-terminated name of the task, a unique name is generated if it's null.
+    pub name: *const c_char,
+    /// Whether the task has a priority.
+    pub has_priority: bool,
+    /// The priority of the task, ignored if `has_priority` is `false`.
+    pub priority: c_longlong,
+    /// The min stack size of the task, the stack size of the coroutine is used if it's 0.
+    pub stack_size: usize,
+    /// Whether the task runs on the event-loop `event_loop`.
+    pub has_event_loop: bool,
+    /// The index of the event-loop to run the task, ignored if `has_event_loop` is `false`.
+    pub event_loop: usize,
+    /// Whether the task has a deadline.
+    pub has_deadline: bool,
+    /// The absolute deadline in ns, ignored if `has_deadline` is `false`.
+    pub deadline: u64,
+    /// What to do if the deadline is missed, ignored if `has_deadline` is `false`.
+    pub miss_policy: DeadlineMissPolicy,
+}
+
+// the default runtime, it's dropped after stopped and the last user released it
+static INSTANCE: RwLock<Option<Arc<EventLoops>>> = RwLock::new(None);
+
+// serialize the init and stop of the default runtime
+static LIFECYCLE: Mutex<()> = Mutex::new(());
 
 /// The manager for `EventLoop`.
 #[repr(C)]
 #[derive(Debug)]
 pub struct EventLoops {
+    // the name of the runtime, "" means the default runtime
+    runtime: String,
     index: AtomicUsize,
-    loops: VecDeque<Arc<EventLoop<'static>>>,
+    loops: VecDeque<&'static EventLoop<'static>>,
     shared_stop: Arc<(Mutex<AtomicUsize>, Condvar)>,
+    // the number of event-loops accepting new submissions
+    active: AtomicUsize,
+    // the adaptive controller thread and its stop flag
+    adaptive: Mutex<Option<std::thread::JoinHandle<()>>>,
+    adaptive_stop: (Mutex<bool>, Condvar),
 }
 
 unsafe impl Send for EventLoops {}
@@ -63,48 +178,129 @@ unsafe impl Send for EventLoops {}
 unsafe impl Sync for EventLoops {}
 
 impl EventLoops {
-    /// Init the `EventLoops`.
+    /// Init the `EventLoops`, it can be initialized again with a different config after
+    /// [`EventLoops::stop`].
     pub fn init(config: &Config) {
-        _ = INSTANCE.get_or_init(|| {
-            #[cfg(feature = "ci")]
-            crate::common::ci::init();
-            let loops = Self::new(
-                config.event_loop_size(),
-                config.stack_size(),
-                config.min_size(),
-                config.max_size(),
-                config.keep_alive_time(),
-            )
-            .expect("init default EventLoops failed !");
-            #[cfg(feature = "log")]
-            let _ = tracing_subscriber::fmt()
-                .with_thread_names(true)
-                .with_line_number(true)
-                .with_timer(tracing_subscriber::fmt::time::OffsetTime::new(
-                    time::UtcOffset::from_hms(8, 0, 0).expect("create UtcOffset failed !"),
-                    time::format_description::well_known::Rfc2822,
-                ))
-                .try_init();
-            info!("open-coroutine init with {config:#?}");
-            loops
-        });
-    }
-
-    /// Create a new `EventLoops`.
+        let _guard = LIFECYCLE.lock().expect("lock failed");
+        if Self::instance().is_some() {
+            return;
+        }
+        #[cfg(feature = "ci")]
+        {
+            static CI: std::sync::Once = std::sync::Once::new();
+            CI.call_once(crate::common::ci::init);
+        }
+        let loops = Self::create("", config).expect("init default EventLoops failed !");
+        #[cfg(feature = "log")]
+        let _ = tracing_subscriber::fmt()
+            .with_thread_names(true)
+            .with_line_number(true)
+            .with_timer(tracing_subscriber::fmt::time::OffsetTime::new(
+                time::UtcOffset::from_hms(8, 0, 0).expect("create UtcOffset failed !"),
+                time::format_description::well_known::Rfc2822,
+            ))
+            .try_init();
+        info!("open-coroutine init with {config:#?}");
+        let loops = Arc::new(loops);
+        loops
+            .start_adaptive(config.adaptive())
+            .expect("start adaptive controller failed !");
+        *INSTANCE.write().expect("lock failed") = Some(loops);
+    }
+
+    fn instance() -> Option<Arc<EventLoops>> {
+        INSTANCE.read().expect("lock failed").clone()
+    }
+
+    // create the event-loops of the `runtime` with its own queues
+    fn create(runtime: &str, config: &Config) -> std::io::Result<Self> {
+        let coroutine_queue = runtime_bean(COROUTINE_GLOBAL_QUEUE_BEAN, runtime);
+        let task_queue = runtime_bean(TASK_GLOBAL_QUEUE_BEAN, runtime);
+        // the global queues must be created before the event-loops take their local queues
+        Self::init_queue::<SchedulableCoroutine>(
+            coroutine_queue,
+            config,
+            config.coroutine_queue_aging(),
+        );
+        Self::init_queue::<Task>(task_queue, config, config.task_queue_aging());
+        let layout = CpuTopology::current().layout(config.pin_policy(), config.event_loop_size());
+        Self::init_distances::<SchedulableCoroutine>(coroutine_queue, &layout);
+        Self::init_distances::<Task>(task_queue, &layout);
+        let loops = Self::with_runtime(
+            runtime,
+            &layout,
+            config.stack_size(),
+            config.min_size(),
+            config.max_size(),
+            config.keep_alive_time(),
+        )?;
+        for event_loop in &loops.loops {
+            event_loop.set_timer_tick(Duration::from_nanos(config.timer_tick()));
+            event_loop.set_scheduling_policy(config.scheduling_policy().create());
+            event_loop.set_migration(config.coroutine_migration());
+            event_loop.set_task_capacity(config.task_queue_capacity());
+            event_loop.set_rejection_policy(config.rejection_policy());
+        }
+        Ok(loops)
+    }
+
+    fn init_queue<T: Debug>(bean_name: &str, config: &Config, aging: u64) {
+        BeanFactory::init_bean(
+            bean_name,
+            OrderedWorkStealQueue::<T>::new(
+                // each event-loop owns a local queue
+                config.local_queue_size().max(config.event_loop_size()),
+                config.local_queue_capacity(),
+            ),
+        );
+        let queue = BeanFactory::get_bean::<OrderedWorkStealQueue<T>>(bean_name)
+            .unwrap_or_else(|| panic!("bean {bean_name} not exist !"));
+        queue.set_aging((aging > 0).then(|| Duration::from_nanos(aging)));
+        // the adaptive controller samples the queue latency
+        queue.set_stats(config.queue_stats() || config.adaptive().enabled());
+        queue.set_steal_batch(config.steal_batch());
+        queue.set_work_stealing(config.work_stealing());
+        queue.set_global_interval(config.global_queue_interval());
+    }
+
+    fn init_distances<T: Debug>(bean_name: &str, layout: &[Option<usize>]) {
+        // the event-loop i owns the local queue i, the extra local queues are remote to all
+        let queue = BeanFactory::get_bean::<OrderedWorkStealQueue<T>>(bean_name)
+            .unwrap_or_else(|| panic!("bean {bean_name} not exist !"));
+        let mut layout = layout.to_vec();
+        layout.resize(queue.local_queue_size().max(layout.len()), None);
+        queue.set_distances(CpuTopology::current().distances(&layout));
+    }
+
+    /// Create a new `EventLoops`, the event-loop i is pinned to `layout[i]`,
+    /// see [`CpuTopology::layout`].
     pub fn new(
-        event_loop_size: usize,
+        layout: &[Option<usize>],
+        stack_size: usize,
+        min_size: usize,
+        max_size: usize,
+        keep_alive_time: u64,
+    ) -> std::io::Result<Self> {
+        Self::with_runtime("", layout, stack_size, min_size, max_size, keep_alive_time)
+    }
+
+    fn with_runtime(
+        runtime: &str,
+        layout: &[Option<usize>],
         stack_size: usize,
         min_size: usize,
         max_size: usize,
         keep_alive_time: u64,
     ) -> std::io::Result<Self> {
         let shared_stop = Arc::new((Mutex::new(AtomicUsize::new(0)), Condvar::new()));
+        let prefix = Self::prefix(runtime);
         let mut loops = VecDeque::new();
-        for i in 0..event_loop_size {
+        for (i, cpu) in layout.iter().enumerate() {
             loops.push_back(
                 EventLoop::new(
-                    format!("open-coroutine-event-loop-{i}"),
-                    i,
+                    format!("{prefix}-event-loop-{i}"),
+                    runtime,
+                    *cpu,
                     stack_size,
                     min_size,
                     max_size,
@@ -115,21 +311,151 @@ impl EventLoops {
             );
         }
         Ok(Self {
+            runtime: runtime.to_string(),
             index: AtomicUsize::new(0),
+            active: AtomicUsize::new(loops.len()),
             loops,
             shared_stop,
+            adaptive: Mutex::new(None),
+            adaptive_stop: (Mutex::new(false), Condvar::new()),
         })
     }
 
-    fn round_robin() -> &'static Arc<EventLoop<'static>> {
-        let instance = INSTANCE.get().expect("EventLoops not init !");
-        let index = instance.index.fetch_add(1, Ordering::Release) % instance.loops.len();
-        instance
-            .loops
+    fn prefix(runtime: &str) -> String {
+        if runtime.is_empty() {
+            String::from("open-coroutine")
+        } else {
+            format!("open-coroutine-{runtime}")
+        }
+    }
+
+    // start the adaptive controller if it's enabled
+    fn start_adaptive(self: &Arc<Self>, config: AdaptiveConfig) -> std::io::Result<()> {
+        if !config.enabled() {
+            return Ok(());
+        }
+        let first = self.loops.front().expect("no event-loop");
+        let name = format!("{}-adaptive", Self::prefix(&self.runtime));
+        let mut controller = AdaptiveController::new(
+            name.clone(),
+            config,
+            Decision {
+                min_size: first.get_min_size(),
+                max_size: first.get_max_size(),
+                event_loops: self.active.load(Ordering::Acquire),
+            },
+            self.loops.len(),
+        );
+        let interval = Duration::from_nanos(config.interval());
+        // the controller keeps the runtime alive until it's joined by `stop_adaptive`
+        let this = self.clone();
+        let thread = std::thread::Builder::new().name(name).spawn(move || {
+            let (lock, cvar) = &this.adaptive_stop;
+            let mut stopped = lock.lock().expect("lock failed");
+            while !*stopped {
+                stopped = cvar
+                    .wait_timeout_while(stopped, interval, |&mut stopped| !stopped)
+                    .expect("lock failed")
+                    .0;
+                if *stopped {
+                    break;
+                }
+                if let Some(decision) = controller.decide(this.sample()) {
+                    this.apply(decision);
+                }
+            }
+        })?;
+        *self.adaptive.lock().expect("lock failed") = Some(thread);
+        Ok(())
+    }
+
+    fn sample(&self) -> LoadSignals {
+        let stats = self.collect();
+        // the idle coroutines waiting for tasks are not busy
+        let mut busy = 0;
+        let mut capacity = 0;
+        for event_loop in &self.loops {
+            busy += event_loop.get_busy_size();
+            capacity += event_loop.get_max_size();
+        }
+        // the plain coroutines in syscalls are not counted as busy
+        let syscall = stats
+            .loads
+            .iter()
+            .map(|load| load.syscall)
+            .sum::<usize>()
+            .min(busy);
+        let percent = |part: usize, total: usize| {
+            u64::try_from(part.saturating_mul(100) / total.max(1)).unwrap_or(u64::MAX)
+        };
+        LoadSignals {
+            latency: u64::try_from(stats.latency().as_nanos()).unwrap_or(u64::MAX),
+            utilization: percent(busy, capacity),
+            syscall_park: percent(syscall, busy),
+            busy,
+        }
+    }
+
+    fn apply(&self, decision: Decision) {
+        for event_loop in &self.loops {
+            event_loop.set_max_size(decision.max_size);
+            event_loop.set_min_size(decision.min_size);
+        }
+        self.active.store(decision.event_loops, Ordering::Release);
+    }
+
+    fn stop_adaptive(&self) {
+        {
+            let (lock, cvar) = &self.adaptive_stop;
+            *lock.lock().expect("lock failed") = true;
+            cvar.notify_one();
+        }
+        if let Some(thread) = self.adaptive.lock().expect("lock failed").take() {
+            if thread.join().is_err() {
+                error!("{} adaptive controller join failed !", self.runtime);
+            }
+        }
+    }
+
+    fn round_robin() -> &'static EventLoop<'static> {
+        Self::instance().expect("EventLoops not init !").next()
+    }
+
+    fn next(&self) -> &'static EventLoop<'static> {
+        // the inactive event-loops still run the stolen tasks and coroutines
+        let active = self
+            .active
+            .load(Ordering::Acquire)
+            .clamp(1, self.loops.len());
+        let index = self.index.fetch_add(1, Ordering::Release) % active;
+        self.loops
             .get(index)
+            .copied()
             .unwrap_or_else(move || panic!("init event-loop-{index} failed!"))
     }
 
+    /// Cancel the `io_uring` request of the cancelled coroutine, the request completes with
+    /// `ECANCELED` if it's not done yet.
+    ///
+    /// # Errors
+    /// if submit the cancel request failed.
+    #[cfg(all(target_os = "linux", feature = "io_uring"))]
+    pub(crate) fn try_cancel_syscall(token: u64) -> std::io::Result<()> {
+        if let Some(event_loop) = EventLoop::current() {
+            if event_loop.try_cancel_syscall(token)? {
+                return Ok(());
+            }
+        }
+        if let Some(instance) = Self::instance() {
+            for event_loop in &instance.loops {
+                if event_loop.try_cancel_syscall(token)? {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Get a `EventLoop`, prefer current.
     fn event_loop() -> &'static EventLoop<'static> {
         EventLoop::current().unwrap_or_else(|| Self::round_robin())
@@ -149,14 +475,215 @@ impl EventLoops {
         event_loop
             .submit_task(name, func, param, priority)
             .map_or_else(
-                |_| JoinHandle::err(event_loop),
+                |e| JoinHandle::err(event_loop, &e),
                 |task_id| JoinHandle::new(event_loop, task_id),
             )
     }
 
+    /// Submit a new task with the absolute `deadline` in ns to event-loop, the timestamp is based
+    /// on [`crate::common::now`]. The tasks with deadlines run in earliest-deadline-first order
+    /// before the tasks with priorities.
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_task_with_deadline(
+        name: Option<String>,
+        func: impl FnOnce(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        deadline: u64,
+        miss_policy: DeadlineMissPolicy,
+    ) -> JoinHandle {
+        let event_loop = Self::round_robin();
+        event_loop
+            .submit_task_with_deadline(name, func, param, deadline, miss_policy)
+            .map_or_else(
+                |e| JoinHandle::err(event_loop, &e),
+                |task_id| JoinHandle::new(event_loop, task_id),
+            )
+    }
+
+    /// Submit a new task with the max run time `timeout` in ns to event-loop, see
+    /// [`crate::co_pool::CoroutinePool::submit_task_with_timeout`].
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_task_with_timeout(
+        name: Option<String>,
+        func: impl FnOnce(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        timeout: u64,
+        from: TimeoutFrom,
+    ) -> JoinHandle {
+        let event_loop = Self::round_robin();
+        event_loop
+            .submit_task_with_timeout(name, func, param, priority, timeout, from)
+            .map_or_else(
+                |e| JoinHandle::err(event_loop, &e),
+                |task_id| JoinHandle::new(event_loop, task_id),
+            )
+    }
+
+    /// Submit a new task retried by the `policy` to event-loop, see
+    /// [`crate::co_pool::CoroutinePool::submit_task_with_retry`].
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_task_with_retry(
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        policy: RetryPolicy,
+    ) -> JoinHandle {
+        let event_loop = Self::round_robin();
+        event_loop
+            .submit_task_with_retry(name, func, param, priority, policy)
+            .map_or_else(
+                |e| JoinHandle::err(event_loop, &e),
+                |task_id| JoinHandle::new(event_loop, task_id),
+            )
+    }
+
+    /// Submit a new task with the `options` to event-loop, the task is rejected if the target
+    /// event-loop doesn't exist.
+    ///
+    /// Allow multiple threads to concurrently submit task to the pool,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_with_options(
+        func: impl FnOnce(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        options: TaskOptions,
+    ) -> JoinHandle {
+        let instance = Self::instance().expect("EventLoops not init !");
+        let event_loop = match options.event_loop {
+            None => instance.next(),
+            Some(index) => match instance.loops.get(index) {
+                Some(event_loop) => *event_loop,
+                None => {
+                    return JoinHandle::err(
+                        instance.next(),
+                        &Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("The event-loop {index} not exists"),
+                        ),
+                    )
+                }
+            },
+        };
+        let mut task = Task::new(
+            options
+                .name
+                .unwrap_or(format!("{}@{}", event_loop.name(), uuid::Uuid::new_v4())),
+            func,
+            param,
+            options.priority,
+        );
+        if let Some((deadline, miss_policy)) = options.deadline {
+            task = task.with_deadline(deadline, miss_policy);
+        }
+        if let Some(stack_size) = options.stack_size {
+            task = task.with_stack_size(stack_size);
+        }
+        event_loop.submit(task).map_or_else(
+            |e| JoinHandle::err(event_loop, &e),
+            |task_id| JoinHandle::new(event_loop, task_id),
+        )
+    }
+
+    /// Submit the task to event-loop after `delay`, see [`crate::co_pool::CoroutinePool::submit_delayed`].
+    #[must_use]
+    pub fn submit_delayed(task: Task<'static>, delay: Duration) -> JoinHandle {
+        let event_loop = Self::round_robin();
+        event_loop.submit_delayed(task, delay).map_or_else(
+            |e| JoinHandle::err(event_loop, &e),
+            |task_id| JoinHandle::new(event_loop, task_id),
+        )
+    }
+
+    /// Run the `func` every `period` after `initial_delay`, see
+    /// [`crate::co_pool::CoroutinePool::submit_at_fixed_rate`].
+    ///
+    /// # Errors
+    /// if the event-loop is not running.
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_at_fixed_rate(
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        initial_delay: Duration,
+        period: Duration,
+        overrun: OverrunPolicy,
+    ) -> std::io::Result<ScheduleHandle> {
+        let event_loop = Self::round_robin();
+        event_loop
+            .submit_at_fixed_rate(name, func, param, priority, initial_delay, period, overrun)
+            .map(|schedule_id| ScheduleHandle::new(event_loop, schedule_id))
+    }
+
+    /// Run the `func` after `initial_delay`, then `delay` after each run finished, see
+    /// [`crate::co_pool::CoroutinePool::submit_with_fixed_delay`].
+    ///
+    /// # Errors
+    /// if the event-loop is not running.
+    pub fn submit_with_fixed_delay(
+        name: Option<String>,
+        func: impl Fn(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+        initial_delay: Duration,
+        delay: Duration,
+    ) -> std::io::Result<ScheduleHandle> {
+        let event_loop = Self::round_robin();
+        event_loop
+            .submit_with_fixed_delay(name, func, param, priority, initial_delay, delay)
+            .map(|schedule_id| ScheduleHandle::new(event_loop, schedule_id))
+    }
+
+    /// Add a task listener to all event-loops.
+    pub fn add_task_listener(listener: &Arc<dyn TaskListener>) {
+        let instance = Self::instance().expect("EventLoops not init !");
+        for event_loop in &instance.loops {
+            event_loop.add_task_listener(listener.clone());
+        }
+    }
+
     /// Try to cancel a task from event-loop.
     pub fn try_cancel_task(task_id: u64) {
-        EventLoop::try_cancel_task(task_id);
+        Self::event_loop().try_cancel_task(task_id);
+    }
+
+    /// Try to cancel the unfinished tasks with the name, returns the number of cancelling tasks.
+    #[must_use]
+    pub fn try_cancel_tasks(name: &str) -> usize {
+        Self::event_loop().try_cancel_tasks(name)
+    }
+
+    /// Get the live task by id, see [`crate::co_pool::CoroutinePool::get_task`].
+    #[must_use]
+    pub fn get_task(task_id: u64) -> Option<TaskInfo> {
+        Self::event_loop().get_task(task_id)
+    }
+
+    /// Find the live tasks by name, see [`crate::co_pool::CoroutinePool::find_tasks`].
+    #[must_use]
+    pub fn find_tasks(name: &str) -> Vec<TaskInfo> {
+        Self::event_loop().find_tasks(name)
+    }
+
+    /// Try to change the priority of a task, returns `false` if the task is not found.
+    /// See [`crate::co_pool::CoroutinePool::try_set_task_priority`].
+    #[must_use]
+    pub fn try_set_task_priority(task_id: u64, priority: Option<c_longlong>) -> bool {
+        Self::event_loop().try_set_task_priority(task_id, priority)
+    }
+
+    /// Try to change the priority of a coroutine, returns `true` if it's re-sorted immediately.
+    /// See [`crate::scheduler::Scheduler::try_set_coroutine_priority`].
+    #[must_use]
+    pub fn try_set_coroutine_priority(co_id: u64, priority: Option<c_longlong>) -> bool {
+        Self::event_loop().try_set_coroutine_priority(co_id, priority)
     }
 
     /// Submit a new coroutine to event-loop.
@@ -193,13 +720,70 @@ impl EventLoops {
         event_loop.wait_just(timeout)
     }
 
+    /// Waiting for any of the fds to become readable or writable.
+    /// This method can only be used in coroutines.
+    pub fn wait_fds_event(
+        read_fds: &[c_int],
+        write_fds: &[c_int],
+        timeout: Option<Duration>,
+    ) -> std::io::Result<()> {
+        let event_loop = Self::event_loop();
+        // fds like regular files can't be registered, waiting for them just falls back to timeout;
+        // only the interests added here are removed after the wait, the others are still in use
+        let read_fds: Vec<c_int> = read_fds
+            .iter()
+            .copied()
+            .filter(|fd| !selector::has_read_event(*fd) && event_loop.add_read_event(*fd).is_ok())
+            .collect();
+        let write_fds: Vec<c_int> = write_fds
+            .iter()
+            .copied()
+            .filter(|fd| !selector::has_write_event(*fd) && event_loop.add_write_event(*fd).is_ok())
+            .collect();
+        let result = event_loop.wait_just(timeout);
+        // the fds may be closed by others during the wait
+        for fd in read_fds {
+            _ = event_loop.del_read_event(fd);
+        }
+        for fd in write_fds {
+            _ = event_loop.del_write_event(fd);
+        }
+        result
+    }
+
+    /// Get the statistics of the runtime.
+    #[must_use]
+    pub fn stats() -> RuntimeStats {
+        Self::instance().map_or_else(
+            || RuntimeStats::collect("", std::iter::empty()),
+            |instance| instance.collect(),
+        )
+    }
+
+    fn collect(&self) -> RuntimeStats {
+        RuntimeStats {
+            active_event_loops: self.active.load(Ordering::Acquire),
+            ..RuntimeStats::collect(
+                &self.runtime,
+                self.loops.iter().map(|event_loop| &***event_loop),
+            )
+        }
+    }
+
+    /// Returns `true` if the current thread is inside the selector of an `EventLoop`.
+    /// The syscalls issued by the selector itself should never be hooked.
+    #[must_use]
+    pub fn in_selector() -> bool {
+        selector::selecting()
+    }
+
     /// Remove read and write event interests.
     /// This method can only be used in coroutines.
     pub fn del_event(fd: c_int) -> std::io::Result<()> {
         if let Some(event_loop) = EventLoop::current() {
             event_loop.del_event(fd)?;
         } else {
-            let instance = INSTANCE.get().expect("EventLoops not init !");
+            let instance = Self::instance().expect("EventLoops not init !");
             for event_loop in &instance.loops {
                 event_loop.del_event(fd)?;
             }
@@ -213,7 +797,7 @@ impl EventLoops {
         if let Some(event_loop) = EventLoop::current() {
             event_loop.del_read_event(fd)?;
         } else {
-            let instance = INSTANCE.get().expect("EventLoops not init !");
+            let instance = Self::instance().expect("EventLoops not init !");
             for event_loop in &instance.loops {
                 event_loop.del_read_event(fd)?;
             }
@@ -227,7 +811,7 @@ impl EventLoops {
         if let Some(event_loop) = EventLoop::current() {
             event_loop.del_write_event(fd)?;
         } else {
-            let instance = INSTANCE.get().expect("EventLoops not init !");
+            let instance = Self::instance().expect("EventLoops not init !");
             for event_loop in &instance.loops {
                 event_loop.del_write_event(fd)?;
             }
@@ -235,13 +819,28 @@ impl EventLoops {
         Ok(())
     }
 
-    /// Stop all `EventLoop`.
+    /// Stop all `EventLoop`, join their threads and remove their beans, then the `EventLoops`
+    /// can be initialized again.
     pub fn stop(wait_time: Duration) -> std::io::Result<()> {
-        if let Some(instance) = INSTANCE.get() {
-            for i in &instance.loops {
-                _ = i.stop(Duration::ZERO);
-            }
-            let (lock, cvar) = &*instance.shared_stop;
+        let _guard = LIFECYCLE.lock().expect("lock failed");
+        if let Some(instance) = Self::instance() {
+            instance.stop_loops(wait_time)?;
+            // the runtime is dropped after the last user released it
+            _ = INSTANCE.write().expect("lock failed").take();
+            #[cfg(feature = "preemptive")]
+            crate::monitor::Monitor::stop();
+        }
+        Ok(())
+    }
+
+    fn stop_loops(&self, wait_time: Duration) -> std::io::Result<()> {
+        let timeout_time = get_timeout_time(wait_time);
+        self.stop_adaptive();
+        for i in &self.loops {
+            _ = i.stop(Duration::ZERO);
+        }
+        {
+            let (lock, cvar) = &*self.shared_stop;
             let guard = lock
                 .lock()
                 .map_err(|_| Error::new(ErrorKind::TimedOut, "wait failed !"))?;
@@ -254,8 +853,22 @@ impl EventLoops {
                 error!("open-coroutine stop timeout !");
                 return Err(Error::new(ErrorKind::TimedOut, "stop timeout !"));
             }
-            #[cfg(feature = "preemptive")]
-            crate::monitor::Monitor::stop();
+        }
+        // all event-loops have exited, join their threads
+        for i in &self.loops {
+            i.stop(Duration::from_nanos(
+                timeout_time.saturating_sub(now()).max(1),
+            ))?;
+        }
+        for bean in [
+            COROUTINE_GLOBAL_QUEUE_BEAN,
+            TASK_GLOBAL_QUEUE_BEAN,
+            COROUTINE_SUSPEND_QUEUES_BEAN,
+            // the next runtime with the same name starts without the tasks of this one
+            TASK_REGISTRY_BEAN,
+        ] {
+            // the stopped event-loops still refer to the queues
+            _ = BeanFactory::detach_bean(runtime_bean(bean, &self.runtime));
         }
         Ok(())
     }
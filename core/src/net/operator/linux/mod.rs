@@ -174,7 +174,9 @@ impl Operator<'_> {
             self,
             SUPPORT_ASYNC_CANCEL,
             AsyncCancel,
-            AsyncCancel::new(user_data).build().user_data(user_data)
+            AsyncCancel::new(user_data)
+                .build()
+                .user_data(crate::common::constants::IO_URING_CANCEL_USERDATA)
         )
     }
 
@@ -0,0 +1,139 @@
+use crate::co_pool::listener::TaskListener;
+use crate::config::Config;
+use crate::coroutine::suspender::Suspender;
+use crate::info;
+use crate::net::join::JoinHandle;
+use crate::net::stats::RuntimeStats;
+use crate::net::EventLoops;
+use dashmap::{DashMap, DashSet};
+use once_cell::sync::Lazy;
+use std::ffi::c_longlong;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::time::Duration;
+
+// a pool is dropped after stopped and the last handle released it
+static POOLS: Lazy<DashMap<String, Arc<EventLoops>>> = Lazy::new(DashMap::new);
+
+// the names of the pools being created
+static CREATING: Lazy<DashSet<String>> = Lazy::new(DashSet::new);
+
+/// The handle of an isolated named pool.
+///
+/// A named pool owns its event-loops, coroutines and queues, configured independently of the
+/// default runtime and of each other. Its tasks are never stolen by other pools, so a slow
+/// dependency running in its own pool can not starve the latency-critical work.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct PoolHandle(Arc<EventLoops>);
+
+impl PoolHandle {
+    /// Create a named pool with the `config`, the name can be reused after the pool stopped.
+    ///
+    /// # Errors
+    /// if the name is empty or already used, or the event-loops can't be created.
+    pub fn create(name: &str, config: &Config) -> std::io::Result<Self> {
+        if name.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The pool name can't be empty",
+            ));
+        }
+        // reserve the name before checking it, the queues of the pool are registered by its
+        // name, and it's created outside the entry lock as starting the threads takes a while
+        if !CREATING.insert(name.to_string()) {
+            return Err(Self::already_exists(name));
+        }
+        let result = if POOLS.contains_key(name) {
+            Err(Self::already_exists(name))
+        } else {
+            Self::start(name, config).map(|pool| {
+                info!("pool {name} created with {config:#?}");
+                _ = POOLS.insert(name.to_string(), pool.clone());
+                Self(pool)
+            })
+        };
+        _ = CREATING.remove(name);
+        result
+    }
+
+    fn already_exists(name: &str) -> Error {
+        Error::new(
+            ErrorKind::AlreadyExists,
+            format!("The pool {name} already exists"),
+        )
+    }
+
+    fn start(name: &str, config: &Config) -> std::io::Result<Arc<EventLoops>> {
+        let pool = Arc::new(EventLoops::create(name, config)?);
+        if let Err(e) = pool.start_adaptive(config.adaptive()) {
+            // don't leak the started event-loops
+            _ = pool.stop_loops(Duration::from_secs(30));
+            return Err(e);
+        }
+        Ok(pool)
+    }
+
+    /// Get the named pool.
+    #[must_use]
+    pub fn get(name: &str) -> Option<Self> {
+        POOLS.get(name).map(|pool| Self(pool.clone()))
+    }
+
+    /// Get the name of this pool.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.0.runtime
+    }
+
+    /// Submit a new task to this pool.
+    pub fn submit_task(
+        &self,
+        name: Option<String>,
+        func: impl FnOnce(Option<usize>) -> Option<usize> + 'static,
+        param: Option<usize>,
+        priority: Option<c_longlong>,
+    ) -> JoinHandle {
+        let event_loop = self.0.next();
+        event_loop
+            .submit_task(name, func, param, priority)
+            .map_or_else(
+                |e| JoinHandle::err(event_loop, &e),
+                |task_id| JoinHandle::new(event_loop, task_id),
+            )
+    }
+
+    /// Submit a new coroutine to this pool.
+    pub fn submit_co(
+        &self,
+        f: impl FnOnce(&Suspender<(), ()>, ()) -> Option<usize> + 'static,
+        stack_size: Option<usize>,
+        priority: Option<c_longlong>,
+    ) -> std::io::Result<()> {
+        self.0.next().submit_co(f, stack_size, priority)
+    }
+
+    /// Add a task listener to all event-loops of this pool.
+    pub fn add_task_listener(&self, listener: &Arc<dyn TaskListener>) {
+        for event_loop in &self.0.loops {
+            event_loop.add_task_listener(listener.clone());
+        }
+    }
+
+    /// Get the statistics of this pool.
+    #[must_use]
+    pub fn stats(&self) -> RuntimeStats {
+        self.0.collect()
+    }
+
+    /// Stop all event-loops of this pool and join their threads, the default runtime and other
+    /// pools keep running.
+    ///
+    /// # Errors
+    /// if stop timeout.
+    pub fn stop(&self, wait_time: Duration) -> std::io::Result<()> {
+        self.0.stop_loops(wait_time)?;
+        _ = POOLS.remove_if(self.name(), |_, pool| Arc::ptr_eq(pool, &self.0));
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use crate::common::constants::SLICE;
 use crate::common::CondvarBlocker;
 use dashmap::{DashMap, DashSet};
 use once_cell::sync::Lazy;
+use std::cell::Cell;
 use std::ffi::c_int;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
@@ -40,6 +41,42 @@ static WRITABLE_RECORDS: Lazy<DashSet<c_int>> = Lazy::new(DashSet::new);
 
 static WRITABLE_TOKEN_RECORDS: Lazy<DashMap<c_int, u64>> = Lazy::new(DashMap::new);
 
+thread_local! {
+    static SELECTING: Cell<bool> = const { Cell::new(false) };
+}
+
+// mark the current thread selecting until dropped, even if the select panics
+struct SelectingGuard;
+
+impl SelectingGuard {
+    fn new() -> Self {
+        SELECTING.set(true);
+        Self
+    }
+}
+
+impl Drop for SelectingGuard {
+    fn drop(&mut self) {
+        SELECTING.set(false);
+    }
+}
+
+/// Returns `true` if the current thread is blocked in the selector,
+/// the hooked multiplexing syscalls use this to reach the kernel directly.
+pub(crate) fn selecting() -> bool {
+    SELECTING.get()
+}
+
+/// Returns `true` if the read event interest of `fd` is registered.
+pub(crate) fn has_read_event(fd: c_int) -> bool {
+    READABLE_RECORDS.contains(&fd)
+}
+
+/// Returns `true` if the write event interest of `fd` is registered.
+pub(crate) fn has_write_event(fd: c_int) -> bool {
+    WRITABLE_RECORDS.contains(&fd)
+}
+
 /// Events abstraction.
 pub(crate) trait EventIterator<E: Event> {
     /// get the iterator.
@@ -61,7 +98,10 @@ pub(crate) trait Selector<I: Interest, E: Event, S: EventIterator<E>> {
             self.blocker().block(timeout.unwrap_or(SLICE));
             return Ok(());
         }
-        let result = self.do_select(events, timeout);
+        let result = {
+            let _selecting = SelectingGuard::new();
+            self.do_select(events, timeout)
+        };
         self.waiting().store(false, Ordering::Release);
         for event in events.iterator() {
             let token = event.get_token();
@@ -0,0 +1,210 @@
+use crate::co_pool::task::Task;
+use crate::co_pool::CoroutinePool;
+use crate::common::beans::BeanFactory;
+use crate::common::constants::{runtime_bean, COROUTINE_GLOBAL_QUEUE_BEAN, TASK_GLOBAL_QUEUE_BEAN};
+use crate::common::ordered_work_steal::{BandStats, OrderedWorkStealQueue};
+use crate::scheduler::{SchedulableCoroutine, SchedulerLoad};
+use std::ffi::c_int;
+use std::time::Duration;
+
+/// The statistics of the runtime.
+#[repr(C)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RuntimeStats {
+    /// The priority bands of the global coroutine queue, empty unless the queue ages or
+    /// records its statistics, see [`crate::config::Config::set_queue_stats`].
+    pub coroutine_bands: Vec<BandStats>,
+    /// The priority bands of the global task queue, empty unless the queue ages or records
+    /// its statistics.
+    pub task_bands: Vec<BandStats>,
+    /// The number of tasks missed their deadlines.
+    pub deadline_misses: u64,
+    /// The number of tasks submitted to the full task queues.
+    pub rejections: u64,
+    /// The number of tasks cancelled due to timeout.
+    pub timeouts: u64,
+    /// The number of runs missed by the fixed-rate schedules.
+    pub overruns: u64,
+    /// The loads of the event-loops, ordered by index.
+    pub loads: Vec<SchedulerLoad>,
+    /// The number of event-loops accepting new submissions.
+    pub active_event_loops: usize,
+}
+
+impl RuntimeStats {
+    /// Collect the statistics of the runtime.
+    pub(crate) fn collect<'p>(
+        runtime: &str,
+        pools: impl Iterator<Item = &'p CoroutinePool<'p>>,
+    ) -> Self {
+        let mut deadline_misses = 0;
+        let mut rejections = 0;
+        let mut timeouts = 0;
+        let mut overruns = 0;
+        let mut loads = Vec::new();
+        for pool in pools {
+            deadline_misses += pool.deadline_misses();
+            rejections += pool.rejections();
+            timeouts += pool.timeouts();
+            overruns += pool.overruns();
+            loads.push(pool.load());
+        }
+        RuntimeStats {
+            coroutine_bands: BeanFactory::get_bean::<OrderedWorkStealQueue<SchedulableCoroutine>>(
+                runtime_bean(COROUTINE_GLOBAL_QUEUE_BEAN, runtime),
+            )
+            .map(OrderedWorkStealQueue::stats)
+            .unwrap_or_default(),
+            task_bands: BeanFactory::get_bean::<OrderedWorkStealQueue<Task>>(runtime_bean(
+                TASK_GLOBAL_QUEUE_BEAN,
+                runtime,
+            ))
+            .map(OrderedWorkStealQueue::stats)
+            .unwrap_or_default(),
+            deadline_misses,
+            rejections,
+            timeouts,
+            overruns,
+            active_event_loops: loads.len(),
+            loads,
+        }
+    }
+
+    /// Copy this statistics to the C compatible `raw` statistics, the bands and the loads are
+    /// truncated if the buffers are too small, but the lengths are always the whole lengths.
+    ///
+    /// # Safety
+    /// The buffers of `raw` must be null or valid for their capacities.
+    pub unsafe fn write_raw(&self, raw: &mut RawRuntimeStats) {
+        raw.coroutine_bands_len = copy(
+            &self.coroutine_bands,
+            raw.coroutine_bands,
+            raw.coroutine_bands_capacity,
+        );
+        raw.task_bands_len = copy(&self.task_bands, raw.task_bands, raw.task_bands_capacity);
+        raw.loads_len = copy(&self.loads, raw.loads, raw.loads_capacity);
+        raw.deadline_misses = self.deadline_misses;
+        raw.rejections = self.rejections;
+        raw.timeouts = self.timeouts;
+        raw.overruns = self.overruns;
+        raw.active_event_loops = self.active_event_loops;
+    }
+
+    /// Read the statistics filled by `f`, `f` is called again with larger buffers if any of
+    /// them is truncated. Returns `None` if `f` fails.
+    pub fn read_raw(mut f: impl FnMut(&mut RawRuntimeStats) -> c_int) -> Option<Self> {
+        let mut stats = RuntimeStats::default();
+        loop {
+            let mut raw = RawRuntimeStats {
+                coroutine_bands: stats.coroutine_bands.as_mut_ptr(),
+                coroutine_bands_capacity: stats.coroutine_bands.capacity(),
+                coroutine_bands_len: 0,
+                task_bands: stats.task_bands.as_mut_ptr(),
+                task_bands_capacity: stats.task_bands.capacity(),
+                task_bands_len: 0,
+                loads: stats.loads.as_mut_ptr(),
+                loads_capacity: stats.loads.capacity(),
+                loads_len: 0,
+                deadline_misses: 0,
+                rejections: 0,
+                timeouts: 0,
+                overruns: 0,
+                active_event_loops: 0,
+            };
+            if 0 != f(&mut raw) {
+                return None;
+            }
+            if raw.coroutine_bands_len > stats.coroutine_bands.capacity()
+                || raw.task_bands_len > stats.task_bands.capacity()
+                || raw.loads_len > stats.loads.capacity()
+            {
+                stats.coroutine_bands.reserve(raw.coroutine_bands_len);
+                stats.task_bands.reserve(raw.task_bands_len);
+                stats.loads.reserve(raw.loads_len);
+                continue;
+            }
+            unsafe {
+                stats.coroutine_bands.set_len(raw.coroutine_bands_len);
+                stats.task_bands.set_len(raw.task_bands_len);
+                stats.loads.set_len(raw.loads_len);
+            }
+            stats.deadline_misses = raw.deadline_misses;
+            stats.rejections = raw.rejections;
+            stats.timeouts = raw.timeouts;
+            stats.overruns = raw.overruns;
+            stats.active_event_loops = raw.active_event_loops;
+            return Some(stats);
+        }
+    }
+
+    /// The max wait time of all priority bands in the last one or two
+    /// [`crate::common::ordered_work_steal::STATS_WINDOW`]s, a long wait means some priorities
+    /// are starving.
+    #[must_use]
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_nanos(
+            self.coroutine_bands
+                .iter()
+                .chain(&self.task_bands)
+                .map(|band| band.max_wait)
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    /// The current wait of all priority bands, it's the queue latency of the runtime.
+    #[must_use]
+    pub fn latency(&self) -> Duration {
+        Duration::from_nanos(
+            self.coroutine_bands
+                .iter()
+                .chain(&self.task_bands)
+                .map(|band| band.wait)
+                .max()
+                .unwrap_or(0),
+        )
+    }
+}
+
+// copy the `src` to the buffer `dst` as much as possible, returns the length of `src`
+unsafe fn copy<T: Copy>(src: &[T], dst: *mut T, capacity: usize) -> usize {
+    if !dst.is_null() {
+        std::ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len().min(capacity));
+    }
+    src.len()
+}
+
+/// The C compatible [`RuntimeStats`], the bands and the loads are copied to the buffers
+/// provided by the caller.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawRuntimeStats {
+    /// The buffer of the coroutine bands, nothing is copied if it's null.
+    pub coroutine_bands: *mut BandStats,
+    /// The capacity of the coroutine bands buffer.
+    pub coroutine_bands_capacity: usize,
+    /// The number of all coroutine bands.
+    pub coroutine_bands_len: usize,
+    /// The buffer of the task bands, nothing is copied if it's null.
+    pub task_bands: *mut BandStats,
+    /// The capacity of the task bands buffer.
+    pub task_bands_capacity: usize,
+    /// The number of all task bands.
+    pub task_bands_len: usize,
+    /// The buffer of the loads, nothing is copied if it's null.
+    pub loads: *mut SchedulerLoad,
+    /// The capacity of the loads buffer.
+    pub loads_capacity: usize,
+    /// The number of all loads.
+    pub loads_len: usize,
+    /// The number of tasks missed their deadlines.
+    pub deadline_misses: u64,
+    /// The number of tasks submitted to the full task queues.
+    pub rejections: u64,
+    /// The number of tasks cancelled due to timeout.
+    pub timeouts: u64,
+    /// The number of runs missed by the fixed-rate schedules.
+    pub overruns: u64,
+    /// The number of event-loops accepting new submissions.
+    pub active_event_loops: usize,
+}
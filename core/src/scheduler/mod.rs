@@ -0,0 +1,739 @@
+use crate::common::beans::BeanFactory;
+use crate::common::constants::{
+    runtime_bean, CoroutineState, SyscallState, COROUTINE_GLOBAL_QUEUE_BEAN,
+    COROUTINE_SUSPEND_QUEUES_BEAN, COROUTINE_TIME_SLICE,
+};
+use crate::common::ordered_work_steal::{OrderedLocalQueue, OrderedWorkStealQueue};
+use crate::common::timing_wheel::TimingWheel;
+use crate::common::{get_timeout_time, now};
+use crate::coroutine::listener::Listener;
+use crate::coroutine::suspender::Suspender;
+use crate::coroutine::Coroutine;
+use crate::scheduler::policy::{SchedulingPolicy, SchedulingPolicyKind};
+use crate::{co, impl_current_for, impl_display_by_debug, impl_for_named, trace, warn};
+use dashmap::{DashMap, DashSet};
+#[cfg(unix)]
+use nix::sys::pthread::Pthread;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::c_longlong;
+use std::io::Error;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Scheduling policy abstraction and impls.
+pub mod policy;
+
+/// A type for Scheduler.
+pub type SchedulableCoroutineState = CoroutineState<(), Option<usize>>;
+
+/// A type for Scheduler.
+pub type SchedulableCoroutine<'s> = Coroutine<'s, (), (), Option<usize>>;
+
+/// A type for Scheduler.
+pub type SchedulableSuspender<'s> = Suspender<'s, (), ()>;
+
+#[cfg(unix)]
+static RUNNING_COROUTINES: Lazy<DashMap<u64, Pthread>> = Lazy::new(DashMap::new);
+#[cfg(windows)]
+static RUNNING_COROUTINES: Lazy<DashMap<u64, usize>> = Lazy::new(DashMap::new);
+
+// the live coroutines of all schedulers, only their cancellations are kept
+static COROUTINES: Lazy<DashSet<u64>> = Lazy::new(DashSet::new);
+
+static CANCEL_COROUTINES: Lazy<DashSet<u64>> = Lazy::new(DashSet::new);
+
+// co_id -> the new priority, applied the next time the coroutine is pushed to the ready queue
+static PRIORITY_COROUTINES: Lazy<DashMap<u64, Option<c_longlong>>> = Lazy::new(DashMap::new);
+
+/// The load of a scheduler.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct SchedulerLoad {
+    /// The number of coroutines waiting for their timers.
+    pub suspended: usize,
+    /// The number of coroutines in syscalls.
+    pub syscall: usize,
+    /// The number of coroutines migrated in from other schedulers.
+    pub migrated_in: u64,
+    /// The number of coroutines migrated out to other schedulers.
+    pub migrated_out: u64,
+}
+
+// the suspend queue shared with other schedulers when migration is enabled
+#[derive(Debug, Default)]
+struct SuspendQueue<'s> {
+    wheel: Mutex<TimingWheel<SchedulableCoroutine<'s>>>,
+    // co_id -> the timer id in the wheel, only changed with the wheel locked
+    timers: DashMap<u64, u64>,
+    migrated_out: AtomicU64,
+}
+
+type SuspendQueues<'s> = DashMap<String, Arc<SuspendQueue<'s>>>;
+
+/// The scheduler impls.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Scheduler<'s> {
+    name: String,
+    // the bean name of the suspend queues shared by the schedulers of the same runtime
+    suspend_queues: &'static str,
+    stack_size: AtomicUsize,
+    listeners: VecDeque<&'s dyn Listener<(), Option<usize>>>,
+    policy: RwLock<Box<dyn SchedulingPolicy>>,
+    #[doc = include_str!("../../docs/en/ordered-work-steal.md")]
+    ready: OrderedLocalQueue<'s, SchedulableCoroutine<'s>>,
+    suspend: Arc<SuspendQueue<'s>>,
+    migration: AtomicBool,
+    migrated_in: AtomicU64,
+    migrated_at: AtomicU64,
+    syscall: DashMap<u64, SchedulableCoroutine<'s>>,
+    syscall_suspend: Mutex<TimingWheel<u64>>,
+    // co_id -> the timer id in syscall_suspend
+    syscall_timers: DashMap<u64, u64>,
+}
+
+impl Default for Scheduler<'_> {
+    fn default() -> Self {
+        Self::new(
+            format!("open-coroutine-scheduler-{:?}", std::thread::current().id()),
+            crate::common::constants::DEFAULT_STACK_SIZE,
+        )
+    }
+}
+
+impl Drop for Scheduler<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        self.set_migration(false);
+        let name = self.name.clone();
+        _ = self
+            .try_timed_schedule(Duration::from_secs(30))
+            .unwrap_or_else(|e| panic!("Failed to stop scheduler {name} due to {e} !"));
+        assert!(
+            self.ready.is_empty(),
+            "There are still coroutines to be carried out in the ready queue:{:#?} !",
+            self.ready
+        );
+        let suspend = self.suspend.wheel.lock().expect("lock failed");
+        assert!(
+            suspend.is_empty(),
+            "There are still coroutines to be carried out in the suspend queue:{suspend:#?} !"
+        );
+        assert!(
+            self.syscall.is_empty(),
+            "There are still coroutines to be carried out in the syscall queue:{:#?} !",
+            self.syscall
+        );
+    }
+}
+
+impl_for_named!(Scheduler<'s>);
+
+impl_current_for!(SCHEDULER, Scheduler<'s>);
+
+impl_display_by_debug!(Scheduler<'s>);
+
+#[allow(clippy::type_complexity)]
+impl<'s> Scheduler<'s> {
+    /// Creates a new scheduler.
+    #[must_use]
+    pub fn new(name: String, stack_size: usize) -> Self {
+        Self::with_runtime(name, "", stack_size)
+    }
+
+    /// Creates a new scheduler shares the queues of the `runtime`, the schedulers of different
+    /// runtimes never steal or migrate coroutines from each other.
+    #[must_use]
+    pub fn with_runtime(name: String, runtime: &str, stack_size: usize) -> Self {
+        Scheduler {
+            name,
+            suspend_queues: runtime_bean(COROUTINE_SUSPEND_QUEUES_BEAN, runtime),
+            stack_size: AtomicUsize::new(stack_size),
+            listeners: VecDeque::new(),
+            policy: RwLock::new(SchedulingPolicyKind::default().create()),
+            ready: BeanFactory::get_or_default::<OrderedWorkStealQueue<SchedulableCoroutine>>(
+                runtime_bean(COROUTINE_GLOBAL_QUEUE_BEAN, runtime),
+            )
+            .local_queue(),
+            suspend: Arc::default(),
+            migration: AtomicBool::new(false),
+            migrated_in: AtomicU64::new(0),
+            migrated_at: AtomicU64::new(0),
+            syscall: DashMap::default(),
+            syscall_suspend: Mutex::default(),
+            syscall_timers: DashMap::default(),
+        }
+    }
+
+    /// Get the name of this scheduler.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the default stack size for the coroutines in this scheduler.
+    /// If it has not been set, it will be [`crate::common::constants::DEFAULT_STACK_SIZE`].
+    pub fn stack_size(&self) -> usize {
+        self.stack_size.load(Ordering::Acquire)
+    }
+
+    /// Get the tick resolution of the suspend and syscall timeout queues.
+    pub fn timer_tick(&self) -> Duration {
+        self.suspend.wheel.lock().expect("lock failed").tick()
+    }
+
+    /// Set the tick resolution of the suspend and syscall timeout queues,
+    /// a coarser tick costs less but wakes up the coroutines later.
+    ///
+    /// # Panics
+    /// if the `tick` is zero.
+    pub fn set_timer_tick(&self, tick: Duration) {
+        self.suspend
+            .wheel
+            .lock()
+            .expect("lock failed")
+            .set_tick(tick);
+        self.syscall_suspend
+            .lock()
+            .expect("lock failed")
+            .set_tick(tick);
+    }
+
+    /// Set the policy which decides which ready coroutine runs next and for how long,
+    /// the coroutines already in the ready queue keep their order.
+    pub fn set_scheduling_policy(&self, policy: Box<dyn SchedulingPolicy>) {
+        *self.policy.write().expect("lock failed") = policy;
+    }
+
+    /// Returns `true` if the coroutines can be migrated between this scheduler and others.
+    pub fn migration(&self) -> bool {
+        self.migration.load(Ordering::Acquire)
+    }
+
+    /// Enable or disable the migration of coroutines between this scheduler and the other
+    /// schedulers enabled migration.
+    ///
+    /// The ready coroutines are always shared through the global coroutine queue. With migration
+    /// enabled, an idle scheduler also takes the expired coroutines from the busy schedulers, and
+    /// balances the coroutines waiting for their timers, including the ones in `sleep` syscalls.
+    /// The coroutines in other syscalls are never migrated, because their events are registered
+    /// in the selector of their schedulers.
+    pub fn set_migration(&self, migration: bool) {
+        self.migration.store(migration, Ordering::Release);
+        let queues = BeanFactory::get_or_default::<SuspendQueues<'s>>(self.suspend_queues);
+        if migration {
+            _ = queues.insert(self.name.clone(), self.suspend.clone());
+        } else {
+            _ = queues.remove(&self.name);
+        }
+    }
+
+    /// Get the load of this scheduler.
+    pub fn load(&self) -> SchedulerLoad {
+        SchedulerLoad {
+            suspended: self.suspend.wheel.lock().expect("lock failed").len(),
+            syscall: self.syscall.len(),
+            migrated_in: self.migrated_in.load(Ordering::Acquire),
+            migrated_out: self.suspend.migrated_out.load(Ordering::Acquire),
+        }
+    }
+
+    /// Returns `true` if the local&global ready queue is empty.
+    ///
+    /// The coroutines stolen from other schedulers are in the local ready queue.
+    pub fn is_ready_empty(&self) -> bool {
+        self.ready.is_local_empty() && self.ready.is_global_empty()
+    }
+
+    /// Submit a closure to create new coroutine, then the coroutine will be push into ready queue.
+    ///
+    /// Allow multiple threads to concurrently submit coroutine to the scheduler,
+    /// but only allow one thread to execute scheduling.
+    ///
+    /// # Errors
+    /// if create coroutine fails.
+    pub fn submit_co(
+        &self,
+        f: impl FnOnce(&Suspender<(), ()>, ()) -> Option<usize> + 'static,
+        stack_size: Option<usize>,
+        priority: Option<c_longlong>,
+    ) -> std::io::Result<u64> {
+        self.submit_raw_co(co!(
+            Some(format!("{}@{}", self.name(), uuid::Uuid::new_v4())),
+            f,
+            Some(stack_size.unwrap_or(self.stack_size())),
+            priority
+        )?)
+    }
+
+    /// Add a listener to this scheduler.
+    pub fn add_listener(&mut self, listener: impl Listener<(), Option<usize>> + 's) {
+        self.listeners.push_back(Box::leak(Box::new(listener)));
+    }
+
+    /// Submit a raw coroutine, then the coroutine will be push into ready queue.
+    ///
+    /// Allow multiple threads to concurrently submit coroutine to the scheduler,
+    /// but only allow one thread to execute scheduling.
+    pub fn submit_raw_co(&self, mut co: SchedulableCoroutine<'s>) -> std::io::Result<u64> {
+        for listener in self.listeners.clone() {
+            co.add_raw_listener(listener);
+        }
+        let co_id = co.id;
+        _ = COROUTINES.insert(co_id);
+        self.push_ready(co);
+        Ok(co_id)
+    }
+
+    /// Resume a coroutine from the syscall table to the ready queue,
+    /// it's generally only required for framework level crates.
+    ///
+    /// If we can't find the coroutine, nothing happens.
+    ///
+    /// # Errors
+    /// if change to ready fails.
+    pub fn try_resume(&self, co_id: u64) {
+        if let Some((_, co)) = self.syscall.remove(&co_id) {
+            if let Some((_, timer)) = self.syscall_timers.remove(&co_id) {
+                _ = self
+                    .syscall_suspend
+                    .lock()
+                    .expect("lock failed")
+                    .cancel(timer);
+            }
+            match co.state() {
+                CoroutineState::Syscall(val, syscall, SyscallState::Suspend(_)) => {
+                    co.syscall(val, syscall, SyscallState::Callback)
+                        .expect("change syscall state failed");
+                }
+                _ => unreachable!("try_resume unexpect CoroutineState"),
+            }
+            self.push_ready(co);
+        }
+    }
+
+    /// Schedule the coroutines.
+    ///
+    /// Allow multiple threads to concurrently submit coroutine to the scheduler,
+    /// but only allow one thread to execute scheduling.
+    ///
+    /// # Errors
+    /// see `try_timeout_schedule`.
+    pub fn try_schedule(&mut self) -> std::io::Result<HashMap<u64, Result<Option<usize>, &str>>> {
+        self.try_timeout_schedule(u64::MAX)
+            .map(|(_, results)| results)
+    }
+
+    /// Try scheduling the coroutines for up to `dur`.
+    ///
+    /// Allow multiple threads to concurrently submit coroutine to the scheduler,
+    /// but only allow one thread to execute scheduling.
+    ///
+    /// # Errors
+    /// see `try_timeout_schedule`.
+    pub fn try_timed_schedule(
+        &mut self,
+        dur: Duration,
+    ) -> std::io::Result<(u64, HashMap<u64, Result<Option<usize>, &str>>)> {
+        self.try_timeout_schedule(get_timeout_time(dur))
+    }
+
+    /// Attempt to schedule the coroutines before the `timeout_time` timestamp.
+    ///
+    /// Allow multiple threads to concurrently submit coroutine to the scheduler,
+    /// but only allow one thread to schedule.
+    ///
+    /// Returns the left time in ns.
+    ///
+    /// # Errors
+    /// if change to ready fails.
+    pub fn try_timeout_schedule(
+        &mut self,
+        timeout_time: u64,
+    ) -> std::io::Result<(u64, HashMap<u64, Result<Option<usize>, &str>>)> {
+        Self::init_current(self);
+        let r = self.do_schedule(timeout_time);
+        Self::clean_current();
+        r
+    }
+
+    fn do_schedule(
+        &mut self,
+        timeout_time: u64,
+    ) -> std::io::Result<(u64, HashMap<u64, Result<Option<usize>, &str>>)> {
+        let mut results = HashMap::new();
+        loop {
+            let left_time = timeout_time.saturating_sub(now());
+            if 0 == left_time {
+                return Ok((0, results));
+            }
+            self.check_ready()?;
+            // schedule coroutines
+            if let Some(mut coroutine) = self.ready.pop() {
+                let co_id = coroutine.id;
+                if CANCEL_COROUTINES.remove(&co_id).is_some() && Self::cancel(&coroutine)? {
+                    continue;
+                }
+                cfg_if::cfg_if! {
+                    if #[cfg(windows)] {
+                        let current_thread = unsafe {
+                            windows_sys::Win32::System::Threading::GetCurrentThread()
+                        } as usize;
+                    } else {
+                        let current_thread = nix::sys::pthread::pthread_self();
+                    }
+                }
+                let start = {
+                    let policy = self.policy.read().expect("lock failed");
+                    _ = coroutine.put(COROUTINE_TIME_SLICE, policy.time_slice(&coroutine));
+                    policy.on_scheduled(&coroutine);
+                    now()
+                };
+                _ = RUNNING_COROUTINES.insert(co_id, current_thread);
+                let state = coroutine.resume().inspect(|_| {
+                    _ = RUNNING_COROUTINES.remove(&co_id);
+                })?;
+                self.policy.read().expect("lock failed").on_yield(
+                    &coroutine,
+                    Duration::from_nanos(now().saturating_sub(start)),
+                );
+                match state {
+                    CoroutineState::Syscall((), syscall, state) => {
+                        if let SyscallState::Suspend(timestamp) = state {
+                            if timestamp <= now() {
+                                //已经超时，直接放入就绪队列尾部
+                                coroutine.syscall((), syscall, SyscallState::Timeout)?;
+                                self.push_ready(coroutine);
+                                continue;
+                            }
+                            if syscall.is_sleep() {
+                                //没有注册事件，可以和普通挂起一样迁移
+                                self.suspend(timestamp, coroutine);
+                                continue;
+                            }
+                            let timer = self
+                                .syscall_suspend
+                                .get_mut()
+                                .expect("lock failed")
+                                .insert(timestamp, co_id);
+                            if let Some(old) = self.syscall_timers.insert(co_id, timer) {
+                                _ = self
+                                    .syscall_suspend
+                                    .get_mut()
+                                    .expect("lock failed")
+                                    .cancel(old);
+                            }
+                        }
+                        //挂起协程到系统调用表
+                        //如果已包含，说明当前系统调用还有上层父系统调用，因此直接忽略插入结果
+                        _ = self.syscall.insert(co_id, coroutine);
+                    }
+                    CoroutineState::Suspend((), timestamp) => {
+                        if timestamp > now() {
+                            //挂起协程到时间轮
+                            self.suspend(timestamp, coroutine);
+                        } else {
+                            //放入就绪队列尾部
+                            self.push_ready(coroutine);
+                        }
+                    }
+                    CoroutineState::Cancelled => {
+                        Self::clean(&coroutine);
+                    }
+                    CoroutineState::Complete(result) => {
+                        Self::clean(&coroutine);
+                        assert!(
+                            results.insert(co_id, Ok(result)).is_none(),
+                            "not consume result"
+                        );
+                    }
+                    CoroutineState::Error(message) => {
+                        Self::clean(&coroutine);
+                        assert!(
+                            results.insert(co_id, Err(message)).is_none(),
+                            "not consume result"
+                        );
+                    }
+                    _ => {
+                        return Err(Error::other(
+                            "try_timeout_schedule should never execute to here",
+                        ));
+                    }
+                }
+                continue;
+            }
+            return Ok((left_time, results));
+        }
+    }
+
+    fn check_ready(&mut self) -> std::io::Result<()> {
+        // Check if the elements in the suspend queue are ready
+        let now = now();
+        loop {
+            let mut wheel = self.suspend.wheel.lock().expect("lock failed");
+            let Some(coroutine) = wheel.poll(now) else {
+                break;
+            };
+            _ = self.suspend.timers.remove(&coroutine.id);
+            drop(wheel);
+            Self::wake(&coroutine)?;
+            self.push_ready(coroutine);
+        }
+        // Check if the elements in the syscall suspend queue are ready
+        while let Some(co_id) = self
+            .syscall_suspend
+            .get_mut()
+            .expect("lock failed")
+            .poll(now)
+        {
+            _ = self.syscall_timers.remove(&co_id);
+            if let Some((_, co)) = self.syscall.remove(&co_id) {
+                match co.state() {
+                    CoroutineState::Syscall(val, syscall, SyscallState::Suspend(_)) => {
+                        co.syscall(val, syscall, SyscallState::Timeout)?;
+                        self.push_ready(co);
+                    }
+                    _ => unreachable!("check_ready should never execute to here"),
+                }
+            }
+        }
+        if !CANCEL_COROUTINES.is_empty() {
+            self.wake_cancelled();
+        }
+        self.migrate(now)
+    }
+
+    /// Cancel the popped coroutine, returns `false` if the coroutine is in a syscall.
+    ///
+    /// The coroutine in a syscall is never dropped, it's resumed to fail the parked syscall
+    /// with `ECANCELED`, then it unwinds by itself.
+    fn cancel(coroutine: &SchedulableCoroutine<'s>) -> std::io::Result<bool> {
+        // never bind inside the log macros, they may compile to nothing
+        #[allow(unused_variables)]
+        let co_id = coroutine.id;
+        if let CoroutineState::Syscall((), syscall, state) = coroutine.state() {
+            if SyscallState::Executing != state {
+                coroutine.syscall((), syscall, SyscallState::Cancelled)?;
+            }
+            warn!("Cancel coroutine:{} in syscall {} !", co_id, syscall);
+            return Ok(false);
+        }
+        coroutine.cancel()?;
+        Self::clean(coroutine);
+        warn!("Cancel coroutine:{} successfully !", co_id);
+        Ok(true)
+    }
+
+    /// Move the cancelling coroutines waiting for their timers or syscalls in this scheduler to
+    /// the ready queue, then they are cancelled when popped, or resumed to fail their syscalls.
+    /// The cancelling coroutines of other schedulers are woken by their owners.
+    fn wake_cancelled(&self) {
+        let ids: Vec<u64> = CANCEL_COROUTINES.iter().map(|co_id| *co_id).collect();
+        for co_id in ids {
+            let suspended = {
+                let mut wheel = self.suspend.wheel.lock().expect("lock failed");
+                self.suspend
+                    .timers
+                    .remove(&co_id)
+                    .and_then(|(_, timer)| wheel.cancel(timer))
+            };
+            if let Some(coroutine) = suspended {
+                self.push_ready(coroutine);
+            } else if let Some((_, coroutine)) = self.syscall.remove(&co_id) {
+                if let Some((_, timer)) = self.syscall_timers.remove(&co_id) {
+                    _ = self
+                        .syscall_suspend
+                        .lock()
+                        .expect("lock failed")
+                        .cancel(timer);
+                }
+                self.push_ready(coroutine);
+            }
+        }
+    }
+
+    /// Take the expired coroutines from other schedulers, and balance the coroutines waiting
+    /// for their timers. At most once per timer tick.
+    fn migrate(&self, now: u64) -> std::io::Result<()> {
+        if !self.migration() {
+            return Ok(());
+        }
+        let next = self
+            .migrated_at
+            .load(Ordering::Acquire)
+            .saturating_add(u64::try_from(self.timer_tick().as_nanos()).unwrap_or(u64::MAX));
+        if now < next {
+            return Ok(());
+        }
+        self.migrated_at.store(now, Ordering::Release);
+        let Some(queues) = BeanFactory::get_bean::<SuspendQueues<'s>>(self.suspend_queues) else {
+            return Ok(());
+        };
+        let own = self.suspend.wheel.lock().expect("lock failed").len();
+        let mut expired = Vec::new();
+        let mut balanced = Vec::new();
+        let mut busiest: Option<(Arc<SuspendQueue<'s>>, String, usize)> = None;
+        for entry in queues {
+            if Arc::ptr_eq(entry.value(), &self.suspend) {
+                continue;
+            }
+            // never wait for others, avoid deadlocks
+            let Ok(mut wheel) = entry.value().wheel.try_lock() else {
+                continue;
+            };
+            while let Some(coroutine) = wheel.poll(now) {
+                _ = entry.value().timers.remove(&coroutine.id);
+                _ = entry.value().migrated_out.fetch_add(1, Ordering::Release);
+                expired.push((entry.key().clone(), coroutine));
+            }
+            if busiest
+                .as_ref()
+                .is_none_or(|(_, _, len)| wheel.len() > *len)
+            {
+                busiest = Some((entry.value().clone(), entry.key().clone(), wheel.len()));
+            }
+        }
+        if let Some((queue, from, len)) = busiest {
+            if len > own.saturating_add(1) {
+                let mut wheel = queue.wheel.lock().expect("lock failed");
+                for (timestamp, coroutine) in wheel.take((len - own) / 2) {
+                    _ = queue.timers.remove(&coroutine.id);
+                    _ = queue.migrated_out.fetch_add(1, Ordering::Release);
+                    balanced.push((from.clone(), timestamp, coroutine));
+                }
+            }
+        }
+        let migrated = expired.len() + balanced.len();
+        for (from, coroutine) in expired {
+            self.on_migrate(&from, &coroutine);
+            Self::wake(&coroutine)?;
+            self.push_ready(coroutine);
+        }
+        for (from, timestamp, coroutine) in balanced {
+            self.on_migrate(&from, &coroutine);
+            self.suspend(timestamp, coroutine);
+        }
+        if migrated > 0 {
+            trace!("{} migrated {} coroutines in", self.name(), migrated);
+        }
+        Ok(())
+    }
+
+    fn suspend(&self, timestamp: u64, coroutine: SchedulableCoroutine<'s>) {
+        let co_id = coroutine.id;
+        let mut wheel = self.suspend.wheel.lock().expect("lock failed");
+        let timer = wheel.insert(timestamp, coroutine);
+        _ = self.suspend.timers.insert(co_id, timer);
+    }
+
+    /// Change the expired coroutine from the suspend queue to ready.
+    fn wake(coroutine: &SchedulableCoroutine<'s>) -> std::io::Result<()> {
+        if let CoroutineState::Syscall(val, syscall, SyscallState::Suspend(_)) = coroutine.state() {
+            return coroutine.syscall(val, syscall, SyscallState::Timeout);
+        }
+        coroutine.ready()
+    }
+
+    fn on_migrate(&self, from: &str, coroutine: &SchedulableCoroutine<'s>) {
+        _ = self.migrated_in.fetch_add(1, Ordering::Release);
+        coroutine.on_migrate(coroutine, from);
+    }
+
+    /// Clean the coroutine-related data after it finished.
+    fn clean(co: &SchedulableCoroutine<'s>) {
+        _ = co.remove::<Duration>(COROUTINE_TIME_SLICE);
+        _ = PRIORITY_COROUTINES.remove(&co.id);
+        _ = COROUTINES.remove(&co.id);
+        _ = CANCEL_COROUTINES.remove(&co.id);
+    }
+
+    fn push_ready(&self, mut co: SchedulableCoroutine<'s>) {
+        if !PRIORITY_COROUTINES.is_empty() {
+            if let Some((_, priority)) = PRIORITY_COROUTINES.remove(&co.id) {
+                co.set_priority(priority);
+            }
+        }
+        let precedence = self.policy.read().expect("lock failed").precedence(&co);
+        self.ready.push_with_priority(precedence, co);
+    }
+
+    /// Change the priority of the coroutine by id, whether it's ready, suspended, in a syscall
+    /// or running. The ready coroutine is re-sorted immediately, the suspended coroutine and the
+    /// coroutine in a syscall are changed in place, the running coroutine changes its priority
+    /// the next time it's pushed to the ready queue.
+    ///
+    /// Returns `true` if the coroutine is re-sorted immediately.
+    pub fn try_set_coroutine_priority(&self, co_id: u64, priority: Option<c_longlong>) -> bool {
+        if RUNNING_COROUTINES.contains_key(&co_id) {
+            _ = PRIORITY_COROUTINES.insert(co_id, priority);
+            if RUNNING_COROUTINES.contains_key(&co_id) {
+                return false;
+            }
+            // yielded or finished meanwhile, the pending priority may never be taken
+            _ = PRIORITY_COROUTINES.remove(&co_id);
+        }
+        if let Some(mut co) = self.syscall.get_mut(&co_id) {
+            co.set_priority(priority);
+            return false;
+        }
+        if self.set_suspended_priority(co_id, priority) {
+            return false;
+        }
+        let policy = self.policy.read().expect("lock failed");
+        let mut found = false;
+        // the bands of the policies depend on the runtime state, so all the bands are searched
+        _ = self.ready.reprioritize(None, |co| {
+            if co.id != co_id {
+                return None;
+            }
+            found = true;
+            co.set_priority(priority);
+            Some(policy.precedence(co))
+        });
+        found
+    }
+
+    /// Change the priority of the coroutine waiting for its timer, which may be migrated to
+    /// other schedulers.
+    fn set_suspended_priority(&self, co_id: u64, priority: Option<c_longlong>) -> bool {
+        let set = |queue: &SuspendQueue<'s>| {
+            queue
+                .wheel
+                .lock()
+                .expect("lock failed")
+                .find_mut(|co| co.id == co_id)
+                .map(|co| co.set_priority(priority))
+                .is_some()
+        };
+        if set(&self.suspend) {
+            return true;
+        }
+        BeanFactory::get_bean::<SuspendQueues<'s>>(self.suspend_queues)
+            .is_some_and(|queues| queues.iter().any(|queue| set(queue.value())))
+    }
+
+    /// Cancel the coroutine by name.
+    pub fn try_cancel_coroutine(co_id: u64) {
+        // the live entry is held, so a finished coroutine never leaves its id behind
+        if let Some(_live) = COROUTINES.get(&co_id) {
+            _ = CANCEL_COROUTINES.insert(co_id);
+        }
+    }
+
+    /// Get the scheduling thread of the coroutine.
+    #[cfg(unix)]
+    pub fn get_scheduling_thread(co_id: u64) -> Option<Pthread> {
+        RUNNING_COROUTINES.get(&co_id).map(|r| *r)
+    }
+
+    /// Get the scheduling thread of the coroutine.
+    #[cfg(windows)]
+    pub fn get_scheduling_thread(co_id: u64) -> Option<windows_sys::Win32::Foundation::HANDLE> {
+        RUNNING_COROUTINES
+            .get(&co_id)
+            .map(|r| *r as windows_sys::Win32::Foundation::HANDLE)
+    }
+}
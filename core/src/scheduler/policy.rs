@@ -0,0 +1,440 @@
+use crate::common::constants::{CoroutineState, SLICE};
+use crate::common::ordered_work_steal::{Ordered, DEFAULT_PRECEDENCE, LOWEST_PRECEDENCE};
+use crate::scheduler::SchedulableCoroutine;
+use std::ffi::c_longlong;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The built-in scheduling policies, used to select the policy of each `EventLoop` by `Config`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum SchedulingPolicyKind {
+    /// See [`Fifo`].
+    Fifo,
+    /// See [`Priority`].
+    #[default]
+    Priority,
+    /// See [`FairShare`].
+    FairShare,
+    /// See [`WeightedRoundRobin`].
+    WeightedRoundRobin,
+}
+
+impl SchedulingPolicyKind {
+    /// Create the policy with the default arguments.
+    #[must_use]
+    pub fn create(self) -> Box<dyn SchedulingPolicy> {
+        match self {
+            SchedulingPolicyKind::Fifo => Box::new(Fifo::default()),
+            SchedulingPolicyKind::Priority => Box::new(Priority::default()),
+            SchedulingPolicyKind::FairShare => Box::new(FairShare::default()),
+            SchedulingPolicyKind::WeightedRoundRobin => Box::new(WeightedRoundRobin::default()),
+        }
+    }
+}
+
+/// Decides which ready coroutine runs next and for how long.
+///
+/// The ready queue pops the coroutine with the smallest precedence first, and the coroutines with
+/// the same precedence in FIFO order. The ready queue is shared by all schedulers for work
+/// stealing, so a policy only decides the order of the coroutines pushed by its own scheduler.
+pub trait SchedulingPolicy: Debug + Send + Sync {
+    /// Returns the precedence of the coroutine, called when it's pushed into the ready queue.
+    fn precedence(&self, co: &SchedulableCoroutine) -> c_longlong;
+
+    /// Returns how long the coroutine can run before it's preempted.
+    fn time_slice(&self, _co: &SchedulableCoroutine) -> Duration {
+        SLICE
+    }
+
+    /// Called before the coroutine is resumed.
+    fn on_scheduled(&self, _co: &SchedulableCoroutine) {}
+
+    /// Called after the coroutine ran for `elapsed`.
+    fn on_yield(&self, _co: &SchedulableCoroutine, _elapsed: Duration) {}
+}
+
+/// The number of precedences used by [`FairShare`] and [`WeightedRoundRobin`], every precedence
+/// allocates queues, so they are bounded.
+const BUCKETS: u64 = 64;
+
+fn is_finished(co: &SchedulableCoroutine) -> bool {
+    matches!(
+        co.state(),
+        CoroutineState::Cancelled | CoroutineState::Complete(_) | CoroutineState::Error(_)
+    )
+}
+
+fn to_nanos(dur: Duration) -> u64 {
+    u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Run the coroutines in the order they become ready, the priorities are ignored.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Fifo {
+    slice: Duration,
+}
+
+impl Fifo {
+    /// Create a FIFO policy, the coroutines are preempted after running for `slice`.
+    #[must_use]
+    pub fn new(slice: Duration) -> Self {
+        Fifo { slice }
+    }
+}
+
+impl Default for Fifo {
+    fn default() -> Self {
+        Self::new(SLICE)
+    }
+}
+
+impl SchedulingPolicy for Fifo {
+    fn precedence(&self, _: &SchedulableCoroutine) -> c_longlong {
+        DEFAULT_PRECEDENCE
+    }
+
+    fn time_slice(&self, _: &SchedulableCoroutine) -> Duration {
+        self.slice
+    }
+}
+
+/// Always run the coroutine with the highest priority first, the lower priorities may starve.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Priority {
+    slice: Duration,
+}
+
+impl Priority {
+    /// Create a strict priority policy, the coroutines are preempted after running for `slice`.
+    #[must_use]
+    pub fn new(slice: Duration) -> Self {
+        Priority { slice }
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self::new(SLICE)
+    }
+}
+
+impl SchedulingPolicy for Priority {
+    fn precedence(&self, co: &SchedulableCoroutine) -> c_longlong {
+        co.priority().unwrap_or(DEFAULT_PRECEDENCE)
+    }
+
+    fn time_slice(&self, _: &SchedulableCoroutine) -> Duration {
+        self.slice
+    }
+}
+
+/// The coroutine local key of the virtual runtime in ns.
+const VRUNTIME: &str = "FAIR_SHARE_VRUNTIME";
+
+/// The weight of priority 0.
+const NICE_0_WEIGHT: u64 = 1024;
+
+/// The weights of the priorities from -20 to 19, same as the nice values of linux.
+const WEIGHTS: [u64; 40] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904,
+    3906, 3121, 2501, 1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87,
+    70, 56, 45, 36, 29, 23, 18, 15,
+];
+
+/// Share the cpu time fairly by virtual runtime, the coroutine ran the least runs first.
+///
+/// The virtual runtime grows slower for higher priorities, the priorities are treated as nice
+/// values and clamped to `-20..=19`. Newly created coroutines and coroutines waked up after a long
+/// wait start from the min virtual runtime, so they can't monopolize the scheduler.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FairShare {
+    slice: Duration,
+    // the virtual runtime difference in ns between two adjacent precedences
+    granularity: u64,
+    min_vruntime: AtomicU64,
+}
+
+impl FairShare {
+    /// Create a fair-share policy, the coroutines are preempted after running for `slice`,
+    /// and the coroutines whose virtual runtimes differ less than `granularity` run in FIFO order.
+    ///
+    /// # Panics
+    /// if the `granularity` is zero.
+    #[must_use]
+    pub fn new(slice: Duration, granularity: Duration) -> Self {
+        let granularity = to_nanos(granularity);
+        assert!(granularity > 0, "granularity must be greater than 0");
+        FairShare {
+            slice,
+            granularity,
+            min_vruntime: AtomicU64::new(0),
+        }
+    }
+
+    fn weight(co: &SchedulableCoroutine) -> u64 {
+        let nice = co.priority().unwrap_or(DEFAULT_PRECEDENCE).clamp(-20, 19);
+        WEIGHTS[usize::try_from(nice + 20).expect("overflow")]
+    }
+
+    fn vruntime(&self, co: &SchedulableCoroutine) -> u64 {
+        let min = self.min_vruntime.load(Ordering::Acquire);
+        match co.get::<u64>(VRUNTIME) {
+            Some(vruntime) if *vruntime >= min => *vruntime,
+            _ => {
+                _ = co.put(VRUNTIME, min);
+                min
+            }
+        }
+    }
+}
+
+impl Default for FairShare {
+    fn default() -> Self {
+        Self::new(SLICE, Duration::from_millis(1))
+    }
+}
+
+impl SchedulingPolicy for FairShare {
+    fn precedence(&self, co: &SchedulableCoroutine) -> c_longlong {
+        let lag = self
+            .vruntime(co)
+            .saturating_sub(self.min_vruntime.load(Ordering::Acquire));
+        c_longlong::try_from((lag / self.granularity).min(BUCKETS - 1)).expect("overflow")
+    }
+
+    fn time_slice(&self, _: &SchedulableCoroutine) -> Duration {
+        self.slice
+    }
+
+    fn on_scheduled(&self, co: &SchedulableCoroutine) {
+        if let Some(vruntime) = co.get::<u64>(VRUNTIME) {
+            _ = self.min_vruntime.fetch_max(*vruntime, Ordering::AcqRel);
+        }
+    }
+
+    fn on_yield(&self, co: &SchedulableCoroutine, elapsed: Duration) {
+        if is_finished(co) {
+            _ = co.remove::<u64>(VRUNTIME);
+            return;
+        }
+        let delta = to_nanos(elapsed).saturating_mul(NICE_0_WEIGHT) / Self::weight(co);
+        let vruntime = self.vruntime(co).saturating_add(delta);
+        _ = co.put(VRUNTIME, vruntime);
+    }
+}
+
+/// The coroutine local key of the pass.
+const PASS: &str = "WEIGHTED_ROUND_ROBIN_PASS";
+
+/// The pass of weight 1.
+const STRIDE: u64 = 1 << 20;
+
+#[repr(C)]
+#[derive(Debug)]
+struct Band {
+    max_priority: c_longlong,
+    stride: u64,
+    pass: AtomicU64,
+}
+
+/// Run the priority bands in weighted round-robin, a band with weight 2 runs twice as many
+/// coroutines as a band with weight 1, and the coroutines in the same band run in FIFO order.
+#[repr(C)]
+#[derive(Debug)]
+pub struct WeightedRoundRobin {
+    slice: Duration,
+    bands: Vec<Band>,
+    // the pass difference between two adjacent precedences
+    unit: u64,
+    pass: AtomicU64,
+}
+
+impl WeightedRoundRobin {
+    /// Create a weighted round-robin policy, the coroutines are preempted after running for
+    /// `slice`. Each band is described by `(max_priority, weight)`, a coroutine belongs to the
+    /// first band whose `max_priority` is not less than its priority, or the last band.
+    ///
+    /// # Panics
+    /// if the `bands` is empty or any weight is zero.
+    #[must_use]
+    pub fn new(slice: Duration, bands: &[(c_longlong, u32)]) -> Self {
+        assert!(!bands.is_empty(), "bands must not be empty");
+        let mut bands: Vec<Band> = bands
+            .iter()
+            .map(|&(max_priority, weight)| {
+                assert!(weight > 0, "weight must be greater than 0");
+                Band {
+                    max_priority,
+                    stride: (STRIDE / u64::from(weight)).max(1),
+                    pass: AtomicU64::new(0),
+                }
+            })
+            .collect();
+        bands.sort_by_key(|band| band.max_priority);
+        let unit = bands.iter().map(|band| band.stride).min().unwrap_or(1);
+        WeightedRoundRobin {
+            slice,
+            bands,
+            unit,
+            pass: AtomicU64::new(0),
+        }
+    }
+
+    fn band(&self, co: &SchedulableCoroutine) -> &Band {
+        let priority = co.priority().unwrap_or(DEFAULT_PRECEDENCE);
+        self.bands
+            .iter()
+            .find(|band| priority <= band.max_priority)
+            .unwrap_or_else(|| self.bands.last().expect("no band"))
+    }
+}
+
+impl Default for WeightedRoundRobin {
+    /// The coroutines with negative priorities have weight 4, the coroutines with the default
+    /// priority have weight 2, and the others have weight 1.
+    fn default() -> Self {
+        Self::new(
+            SLICE,
+            &[
+                (DEFAULT_PRECEDENCE - 1, 4),
+                (DEFAULT_PRECEDENCE, 2),
+                (LOWEST_PRECEDENCE, 1),
+            ],
+        )
+    }
+}
+
+impl SchedulingPolicy for WeightedRoundRobin {
+    fn precedence(&self, co: &SchedulableCoroutine) -> c_longlong {
+        let band = self.band(co);
+        let current = self.pass.load(Ordering::Acquire);
+        // an idle band can't save up its turns
+        let pass = band
+            .pass
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pass| {
+                Some(pass.max(current).saturating_add(band.stride))
+            })
+            .unwrap_or_else(|pass| pass)
+            .max(current);
+        _ = co.put(PASS, pass);
+        let lag = pass.saturating_sub(current) / self.unit;
+        c_longlong::try_from(lag.min(BUCKETS - 1)).expect("overflow")
+    }
+
+    fn time_slice(&self, _: &SchedulableCoroutine) -> Duration {
+        self.slice
+    }
+
+    fn on_scheduled(&self, co: &SchedulableCoroutine) {
+        if let Some(pass) = co.get::<u64>(PASS) {
+            _ = self.pass.fetch_max(*pass, Ordering::AcqRel);
+        }
+    }
+
+    fn on_yield(&self, co: &SchedulableCoroutine, _: Duration) {
+        if is_finished(co) {
+            _ = co.remove::<u64>(PASS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::co;
+    use crate::common::ordered_work_steal::OrderedWorkStealQueue;
+
+    fn coroutine(name: &str, priority: Option<c_longlong>) -> SchedulableCoroutine<'static> {
+        co!(Some(name.to_string()), |_, ()| None, None, priority).expect("create coroutine failed")
+    }
+
+    /// Push the coroutines by the policy, then pop all of them.
+    fn schedule(
+        policy: &dyn SchedulingPolicy,
+        coroutines: Vec<SchedulableCoroutine<'static>>,
+    ) -> Vec<String> {
+        let queue = OrderedWorkStealQueue::new(1, 256);
+        let local = queue.local_queue();
+        for co in coroutines {
+            local.push_with_priority(policy.precedence(&co), co);
+        }
+        let mut names = Vec::new();
+        while let Some(co) = local.pop() {
+            policy.on_scheduled(&co);
+            names.push(co.name().to_string());
+        }
+        names
+    }
+
+    #[test]
+    fn test_fifo() {
+        let names = schedule(
+            &Fifo::default(),
+            vec![
+                coroutine("low", Some(1)),
+                coroutine("high", Some(-1)),
+                coroutine("default", None),
+            ],
+        );
+        assert_eq!(vec!["low", "high", "default"], names);
+    }
+
+    #[test]
+    fn test_priority() {
+        let names = schedule(
+            &Priority::default(),
+            vec![
+                coroutine("low", Some(1)),
+                coroutine("high", Some(-1)),
+                coroutine("default", None),
+            ],
+        );
+        assert_eq!(vec!["high", "default", "low"], names);
+    }
+
+    #[test]
+    fn test_fair_share() {
+        let policy = FairShare::new(SLICE, Duration::from_millis(1));
+        let busy = coroutine("busy", None);
+        let idle = coroutine("idle", None);
+        let important = coroutine("important", Some(-10));
+        for co in [&busy, &idle, &important] {
+            _ = policy.precedence(co);
+        }
+        policy.on_yield(&busy, Duration::from_millis(10));
+        policy.on_yield(&important, Duration::from_millis(10));
+        // the higher priority, the slower the virtual runtime grows
+        assert_eq!(10_000_000, *busy.get::<u64>(VRUNTIME).unwrap());
+        assert_eq!(1_072_475, *important.get::<u64>(VRUNTIME).unwrap());
+        let names = schedule(&policy, vec![busy, idle, important]);
+        assert_eq!(vec!["idle", "important", "busy"], names);
+        // newcomers start from the min virtual runtime
+        let newcomer = coroutine("newcomer", None);
+        assert_eq!(0, policy.precedence(&newcomer));
+        assert_eq!(10_000_000, *newcomer.get::<u64>(VRUNTIME).unwrap());
+    }
+
+    #[test]
+    fn test_weighted_round_robin() {
+        let policy = WeightedRoundRobin::default();
+        let mut coroutines = Vec::new();
+        for i in 0..4 {
+            coroutines.push(coroutine(&format!("low{i}"), Some(1)));
+            coroutines.push(coroutine(&format!("default{i}"), None));
+            coroutines.push(coroutine(&format!("high{i}"), Some(-1)));
+        }
+        let names = schedule(&policy, coroutines);
+        assert_eq!(
+            vec![
+                "low0", "default0", "high0", "high1", "default1", "high2", "high3", "low1",
+                "default2", "default3", "low2", "low3"
+            ],
+            names
+        );
+    }
+}
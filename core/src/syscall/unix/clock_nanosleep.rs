@@ -0,0 +1,126 @@
+use crate::net::event_loop::EventLoop;
+use crate::net::EventLoops;
+use libc::{clockid_t, timespec};
+use std::ffi::c_int;
+use std::time::Duration;
+
+trait ClockNanosleepSyscall {
+    extern "C" fn clock_nanosleep(
+        &self,
+        fn_ptr: Option<&extern "C" fn(clockid_t, c_int, *const timespec, *mut timespec) -> c_int>,
+        clockid: clockid_t,
+        flags: c_int,
+        rqtp: *const timespec,
+        rmtp: *mut timespec,
+    ) -> c_int;
+}
+
+impl_syscall!(ClockNanosleepSyscallFacade, NioClockNanosleepSyscall, RawClockNanosleepSyscall,
+    clock_nanosleep(clockid: clockid_t, flags: c_int, rqtp: *const timespec, rmtp: *mut timespec) -> c_int
+);
+
+impl_facade!(ClockNanosleepSyscallFacade, ClockNanosleepSyscall,
+    clock_nanosleep(clockid: clockid_t, flags: c_int, rqtp: *const timespec, rmtp: *mut timespec) -> c_int
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioClockNanosleepSyscall<I: ClockNanosleepSyscall> {
+    inner: I,
+}
+
+impl<I: ClockNanosleepSyscall> ClockNanosleepSyscall for NioClockNanosleepSyscall<I> {
+    extern "C" fn clock_nanosleep(
+        &self,
+        fn_ptr: Option<&extern "C" fn(clockid_t, c_int, *const timespec, *mut timespec) -> c_int>,
+        clockid: clockid_t,
+        flags: c_int,
+        rqtp: *const timespec,
+        rmtp: *mut timespec,
+    ) -> c_int {
+        // only the coroutines and the event-loops wait in the event loop, the other threads
+        // (or all threads if the runtime is not running) sleep in the kernel; the cpu-time
+        // clocks can't be waited by the event loop
+        if EventLoop::current().is_none()
+            || !matches!(
+                clockid,
+                libc::CLOCK_REALTIME | libc::CLOCK_MONOTONIC | libc::CLOCK_BOOTTIME
+            )
+        {
+            return self.inner.clock_nanosleep(fn_ptr, clockid, flags, rqtp, rmtp);
+        }
+        // returns the error number instead of setting errno
+        if rqtp.is_null() {
+            return libc::EINVAL;
+        }
+        let rqtp = unsafe { *rqtp };
+        let (Ok(secs), Ok(nanos)) = (u64::try_from(rqtp.tv_sec), u32::try_from(rqtp.tv_nsec))
+        else {
+            return libc::EINVAL;
+        };
+        if nanos > 999_999_999 {
+            return libc::EINVAL;
+        }
+        let time = Duration::new(secs, nanos);
+        if flags & libc::TIMER_ABSTIME == 0 {
+            sleep(time);
+            if !rmtp.is_null() {
+                unsafe {
+                    (*rmtp).tv_sec = 0;
+                    (*rmtp).tv_nsec = 0;
+                }
+            }
+            return 0;
+        }
+        // the clock may be changed during sleeping, so check it again after waking up
+        loop {
+            let now = match clock_time(clockid) {
+                Ok(now) => now,
+                Err(errno) => return errno,
+            };
+            match time.checked_sub(now) {
+                Some(left) if !left.is_zero() => sleep(left),
+                _ => return 0,
+            }
+        }
+    }
+}
+
+// returns the error number if the clock can't be read
+fn clock_time(clockid: clockid_t) -> Result<Duration, c_int> {
+    let mut now = timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(clockid, &raw mut now) } == -1 {
+        return Err(std::io::Error::last_os_error()
+            .raw_os_error()
+            .unwrap_or(libc::EINVAL));
+    }
+    match (u64::try_from(now.tv_sec), u32::try_from(now.tv_nsec)) {
+        (Ok(secs), Ok(nanos)) => Ok(Duration::new(secs, nanos)),
+        _ => Err(libc::EINVAL),
+    }
+}
+
+fn sleep(time: Duration) {
+    if let Some(co) = crate::scheduler::SchedulableCoroutine::current() {
+        let syscall = crate::common::constants::SyscallName::clock_nanosleep;
+        let new_state = crate::common::constants::SyscallState::Suspend(
+            crate::common::get_timeout_time(time),
+        );
+        if co.syscall((), syscall, new_state).is_err() {
+            crate::error!(
+                "{} change to syscall {} {} failed !",
+                co.name(),
+                syscall,
+                new_state
+            );
+        }
+    }
+    _ = EventLoops::wait_event(Some(time));
+}
+
+impl_raw!(RawClockNanosleepSyscall, ClockNanosleepSyscall,
+    clock_nanosleep(clockid: clockid_t, flags: c_int, rqtp: *const timespec, rmtp: *mut timespec) -> c_int
+);
@@ -0,0 +1,63 @@
+use crate::common::constants::SLICE;
+use crate::common::{get_timeout_time, now};
+use crate::net::EventLoops;
+use libc::epoll_event;
+use std::ffi::c_int;
+use std::time::Duration;
+
+trait EpollWaitSyscall {
+    extern "C" fn epoll_wait(
+        &self,
+        fn_ptr: Option<&extern "C" fn(c_int, *mut epoll_event, c_int, c_int) -> c_int>,
+        epfd: c_int,
+        events: *mut epoll_event,
+        maxevents: c_int,
+        timeout: c_int,
+    ) -> c_int;
+}
+
+impl_syscall!(EpollWaitSyscallFacade, NioEpollWaitSyscall, RawEpollWaitSyscall,
+    epoll_wait(epfd: c_int, events: *mut epoll_event, maxevents: c_int, timeout: c_int) -> c_int
+);
+
+impl_facade!(EpollWaitSyscallFacade, EpollWaitSyscall,
+    epoll_wait(epfd: c_int, events: *mut epoll_event, maxevents: c_int, timeout: c_int) -> c_int
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioEpollWaitSyscall<I: EpollWaitSyscall> {
+    inner: I,
+}
+
+impl<I: EpollWaitSyscall> EpollWaitSyscall for NioEpollWaitSyscall<I> {
+    extern "C" fn epoll_wait(
+        &self,
+        fn_ptr: Option<&extern "C" fn(c_int, *mut epoll_event, c_int, c_int) -> c_int>,
+        epfd: c_int,
+        events: *mut epoll_event,
+        maxevents: c_int,
+        timeout: c_int,
+    ) -> c_int {
+        let timeout_time = u64::try_from(timeout).map_or(u64::MAX, |t| {
+            get_timeout_time(Duration::from_millis(t))
+        });
+        loop {
+            let r = self.inner.epoll_wait(fn_ptr, epfd, events, maxevents, 0);
+            let left_time = timeout_time.saturating_sub(now());
+            if r != 0 || left_time == 0 {
+                return r;
+            }
+            // an epoll fd becomes readable when any of its fds is ready
+            _ = EventLoops::wait_fds_event(
+                &[epfd],
+                &[],
+                Some(Duration::from_nanos(left_time).min(SLICE)),
+            );
+        }
+    }
+}
+
+impl_raw!(RawEpollWaitSyscall, EpollWaitSyscall,
+    epoll_wait(epfd: c_int, events: *mut epoll_event, maxevents: c_int, timeout: c_int) -> c_int
+);
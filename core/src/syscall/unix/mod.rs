@@ -160,14 +160,37 @@ macro_rules! impl_io_uring {
                         //回来的时候，系统调用已经执行完毕
                     }
                     if let Some(co) = SchedulableCoroutine::current() {
-                        if let CoroutineState::Syscall((), syscall, SyscallState::Callback) = co.state()
-                        {
-                            let new_state = SyscallState::Executing;
-                            if co.syscall((), syscall, new_state).is_err() {
-                                $crate::error!(
-                                    "{} change to syscall {} {} failed !",
-                                    co.name(), syscall, new_state
-                                );
+                        if let CoroutineState::Syscall((), syscall, syscall_state) = co.state() {
+                            match syscall_state {
+                                SyscallState::Callback => {
+                                    let new_state = SyscallState::Executing;
+                                    if co.syscall((), syscall, new_state).is_err() {
+                                        $crate::error!(
+                                            "{} change to syscall {} {} failed !",
+                                            co.name(), syscall, new_state
+                                        );
+                                    }
+                                },
+                                SyscallState::Cancelled => {
+                                    let new_state = SyscallState::Executing;
+                                    if co.syscall((), syscall, new_state).is_err() {
+                                        $crate::error!(
+                                            "{} change to syscall {} {} failed !",
+                                            co.name(), syscall, new_state
+                                        );
+                                    }
+                                    // the request refers to the memory of the coroutine, wait for
+                                    // it to be cancelled or done
+                                    if $crate::net::EventLoops::try_cancel_syscall(co.id()).is_err() {
+                                        $crate::error!("{} cancel syscall {} failed !", co.name(), syscall);
+                                    }
+                                    while arc.0.lock().expect("lock failed").is_none() {
+                                        _ = $crate::net::EventLoops::wait_event(
+                                            Some($crate::common::constants::SLICE)
+                                        );
+                                    }
+                                },
+                                _ => {}
                             }
                         }
                     }
@@ -253,6 +276,25 @@ macro_rules! impl_io_uring_read {
                                         );
                                     }
                                 },
+                                SyscallState::Cancelled => {
+                                    let new_state = SyscallState::Executing;
+                                    if co.syscall((), syscall, new_state).is_err() {
+                                        $crate::error!(
+                                            "{} change to syscall {} {} failed !",
+                                            co.name(), syscall, new_state
+                                        );
+                                    }
+                                    // the request refers to the memory of the coroutine, wait for
+                                    // it to be cancelled or done
+                                    if $crate::net::EventLoops::try_cancel_syscall(co.id()).is_err() {
+                                        $crate::error!("{} cancel syscall {} failed !", co.name(), syscall);
+                                    }
+                                    while arc.0.lock().expect("lock failed").is_none() {
+                                        _ = $crate::net::EventLoops::wait_event(
+                                            Some($crate::common::constants::SLICE)
+                                        );
+                                    }
+                                },
                                 _ => {}
                             }
                         }
@@ -339,6 +381,25 @@ macro_rules! impl_io_uring_write {
                                         );
                                     }
                                 },
+                                SyscallState::Cancelled => {
+                                    let new_state = SyscallState::Executing;
+                                    if co.syscall((), syscall, new_state).is_err() {
+                                        $crate::error!(
+                                            "{} change to syscall {} {} failed !",
+                                            co.name(), syscall, new_state
+                                        );
+                                    }
+                                    // the request refers to the memory of the coroutine, wait for
+                                    // it to be cancelled or done
+                                    if $crate::net::EventLoops::try_cancel_syscall(co.id()).is_err() {
+                                        $crate::error!("{} cancel syscall {} failed !", co.name(), syscall);
+                                    }
+                                    while arc.0.lock().expect("lock failed").is_none() {
+                                        _ = $crate::net::EventLoops::wait_event(
+                                            Some($crate::common::constants::SLICE)
+                                        );
+                                    }
+                                },
                                 _ => {}
                             }
                         }
@@ -498,7 +559,8 @@ macro_rules! impl_nio_read_buf {
                             $fd,
                             Some(wait_time)
                         ).is_err() {
-                            r = received.try_into().expect("received overflow");
+                            // fail with the error of the wait if nothing is received
+                            if received > 0 { r = received.try_into().expect("received overflow"); }
                             break;
                         }
                     } else if error_kind != std::io::ErrorKind::Interrupted {
@@ -623,7 +685,8 @@ macro_rules! impl_nio_read_iovec {
                                 $fd,
                                 Some(wait_time)
                             ).is_err() {
-                                r = received.try_into().expect("received overflow");
+                                // fail with the error of the wait if nothing is received
+                                if received > 0 { r = received.try_into().expect("received overflow"); }
                                 std::mem::forget(vec);
                                 if blocking {
                                     $crate::syscall::set_blocking($fd);
@@ -723,7 +786,8 @@ macro_rules! impl_nio_write_buf {
                             $fd,
                             Some(wait_time),
                         ).is_err() {
-                            r = sent.try_into().expect("sent overflow");
+                            // fail with the error of the wait if nothing is sent
+                            if sent > 0 { r = sent.try_into().expect("sent overflow"); }
                             break;
                         }
                     } else if error_kind != std::io::ErrorKind::Interrupted {
@@ -840,7 +904,8 @@ macro_rules! impl_nio_write_iovec {
                                 $fd,
                                 Some(wait_time)
                             ).is_err() {
-                                r = sent.try_into().expect("sent overflow");
+                                // fail with the error of the wait if nothing is sent
+                                if sent > 0 { r = sent.try_into().expect("sent overflow"); }
                                 std::mem::forget(vec);
                                 if blocking {
                                     $crate::syscall::set_blocking($fd);
@@ -897,7 +962,12 @@ macro_rules! impl_raw {
 #[cfg(target_os = "linux")]
 syscall_mod!(
     accept4;
+    clock_nanosleep;
+    epoll_wait;
+    ppoll;
     renameat2;
+    wait4;
+    waitid;
 );
 syscall_mod!(
     accept;
@@ -905,6 +975,7 @@ syscall_mod!(
     connect;
     listen;
     nanosleep;
+    pause;
     poll;
     pread;
     preadv;
@@ -920,6 +991,7 @@ syscall_mod!(
     recvfrom;
     recvmsg;
     select;
+    pselect;
     send;
     sendmsg;
     sendto;
@@ -928,6 +1000,7 @@ syscall_mod!(
     socket;
     setsockopt;
     usleep;
+    waitpid;
     write;
     writev;
     mkdir;
@@ -935,6 +1008,7 @@ syscall_mod!(
     fsync;
     rmdir;
     renameat;
+    sched_yield;
     lseek;
     link;
     unlink;
@@ -0,0 +1,46 @@
+use crate::common::constants::SLICE;
+use crate::net::event_loop::EventLoop;
+use crate::net::EventLoops;
+use crate::syscall::set_errno;
+use std::ffi::c_int;
+
+trait PauseSyscall {
+    extern "C" fn pause(&self, fn_ptr: Option<&extern "C" fn() -> c_int>) -> c_int;
+}
+
+impl_syscall!(PauseSyscallFacade, NioPauseSyscall, RawPauseSyscall, pause() -> c_int);
+
+impl_facade!(PauseSyscallFacade, PauseSyscall, pause() -> c_int);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioPauseSyscall<I: PauseSyscall> {
+    inner: I,
+}
+
+impl<I: PauseSyscall> PauseSyscall for NioPauseSyscall<I> {
+    extern "C" fn pause(&self, fn_ptr: Option<&extern "C" fn() -> c_int>) -> c_int {
+        if crate::scheduler::SchedulableCoroutine::current().is_none() {
+            return self.inner.pause(fn_ptr);
+        }
+        // the signal handlers run on the threads instead of the coroutine, so the coroutine
+        // keeps parking until it's cancelled or a signal handler interrupted the selector of
+        // an event-loop; the signals handled by the other threads can't wake it up
+        let interrupts = EventLoop::interrupts();
+        loop {
+            if let Err(e) = EventLoops::wait_event(Some(SLICE)) {
+                // ECANCELED if cancelled, the errno is set by the event-loop already
+                if let Some(errno) = e.raw_os_error() {
+                    set_errno(errno);
+                }
+                return -1;
+            }
+            if EventLoop::interrupts() != interrupts {
+                set_errno(libc::EINTR);
+                return -1;
+            }
+        }
+    }
+}
+
+impl_raw!(RawPauseSyscall, PauseSyscall, pause() -> c_int);
@@ -1,3 +1,5 @@
+use crate::common::constants::SLICE;
+use crate::common::{get_timeout_time, now};
 use crate::net::EventLoops;
 use libc::{nfds_t, pollfd};
 use std::ffi::c_int;
@@ -35,25 +37,46 @@ impl<I: PollSyscall> PollSyscall for NioPollSyscall<I> {
         nfds: nfds_t,
         timeout: c_int,
     ) -> c_int {
-        let mut t = if timeout < 0 { c_int::MAX } else { timeout };
-        let mut x = 1;
-        let mut r;
-        // just check poll every x ms
+        let timeout_time = u64::try_from(timeout).map_or(u64::MAX, |t| {
+            get_timeout_time(Duration::from_millis(t))
+        });
+        let (read_fds, write_fds) = poll_interests(fds, nfds);
         loop {
-            r = self.inner.poll(fn_ptr, fds, nfds, 0);
-            if r != 0 || t == 0 {
-                break;
-            }
-            _ = EventLoops::wait_event(Some(Duration::from_millis(t.min(x).try_into().expect("overflow"))));
-            if t != c_int::MAX {
-                t = if t > x { t - x } else { 0 };
-            }
-            if x < 16 {
-                x <<= 1;
+            let r = self.inner.poll(fn_ptr, fds, nfds, 0);
+            let left_time = timeout_time.saturating_sub(now());
+            if r != 0 || left_time == 0 {
+                return r;
             }
+            // park until any fd is ready, wake up every slice in case some fds can't be registered
+            _ = EventLoops::wait_fds_event(
+                &read_fds,
+                &write_fds,
+                Some(Duration::from_nanos(left_time).min(SLICE)),
+            );
+        }
+    }
+}
+
+/// Split the fds into read interests and write interests.
+pub(super) fn poll_interests(fds: *const pollfd, nfds: nfds_t) -> (Vec<c_int>, Vec<c_int>) {
+    let mut read_fds = Vec::new();
+    let mut write_fds = Vec::new();
+    if fds.is_null() {
+        return (read_fds, write_fds);
+    }
+    let fds = unsafe { std::slice::from_raw_parts(fds, usize::try_from(nfds).expect("overflow")) };
+    for pfd in fds {
+        if pfd.fd < 0 {
+            continue;
+        }
+        if pfd.events & (libc::POLLIN | libc::POLLPRI) != 0 {
+            read_fds.push(pfd.fd);
+        }
+        if pfd.events & libc::POLLOUT != 0 {
+            write_fds.push(pfd.fd);
         }
-        r
     }
+    (read_fds, write_fds)
 }
 
 impl_raw!(RawPollSyscall, PollSyscall,
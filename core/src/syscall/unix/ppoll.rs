@@ -0,0 +1,99 @@
+use crate::common::constants::SLICE;
+use crate::common::{get_timeout_time, now};
+use crate::net::EventLoops;
+use crate::syscall::set_errno;
+use libc::{nfds_t, pollfd, sigset_t, timespec};
+use std::ffi::c_int;
+use std::time::Duration;
+
+trait PpollSyscall {
+    extern "C" fn ppoll(
+        &self,
+        fn_ptr: Option<
+            &extern "C" fn(*mut pollfd, nfds_t, *const timespec, *const sigset_t) -> c_int,
+        >,
+        fds: *mut pollfd,
+        nfds: nfds_t,
+        timeout: *const timespec,
+        sigmask: *const sigset_t,
+    ) -> c_int;
+}
+
+impl_syscall!(PpollSyscallFacade, NioPpollSyscall, RawPpollSyscall,
+    ppoll(
+        fds: *mut pollfd,
+        nfds: nfds_t,
+        timeout: *const timespec,
+        sigmask: *const sigset_t
+    ) -> c_int
+);
+
+impl_facade!(PpollSyscallFacade, PpollSyscall,
+    ppoll(
+        fds: *mut pollfd,
+        nfds: nfds_t,
+        timeout: *const timespec,
+        sigmask: *const sigset_t
+    ) -> c_int
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioPpollSyscall<I: PpollSyscall> {
+    inner: I,
+}
+
+impl<I: PpollSyscall> PpollSyscall for NioPpollSyscall<I> {
+    extern "C" fn ppoll(
+        &self,
+        fn_ptr: Option<
+            &extern "C" fn(*mut pollfd, nfds_t, *const timespec, *const sigset_t) -> c_int,
+        >,
+        fds: *mut pollfd,
+        nfds: nfds_t,
+        timeout: *const timespec,
+        sigmask: *const sigset_t,
+    ) -> c_int {
+        let timeout_time = if timeout.is_null() {
+            u64::MAX
+        } else {
+            let time = unsafe { *timeout };
+            if time.tv_sec < 0 || time.tv_nsec < 0 || time.tv_nsec > 999_999_999 {
+                set_errno(libc::EINVAL);
+                return -1;
+            }
+            get_timeout_time(Duration::new(
+                u64::try_from(time.tv_sec).expect("overflow"),
+                u32::try_from(time.tv_nsec).expect("overflow"),
+            ))
+        };
+        let (read_fds, write_fds) = super::poll::poll_interests(fds, nfds);
+        let zero = timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        loop {
+            // the sigmask only takes effect while checking the fds,
+            // signals arriving during the park are handled with the coroutine's thread mask
+            let r = self.inner.ppoll(fn_ptr, fds, nfds, &raw const zero, sigmask);
+            let left_time = timeout_time.saturating_sub(now());
+            if r != 0 || left_time == 0 {
+                return r;
+            }
+            _ = EventLoops::wait_fds_event(
+                &read_fds,
+                &write_fds,
+                Some(Duration::from_nanos(left_time).min(SLICE)),
+            );
+        }
+    }
+}
+
+impl_raw!(RawPpollSyscall, PpollSyscall,
+    ppoll(
+        fds: *mut pollfd,
+        nfds: nfds_t,
+        timeout: *const timespec,
+        sigmask: *const sigset_t
+    ) -> c_int
+);
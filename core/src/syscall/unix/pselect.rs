@@ -0,0 +1,152 @@
+use crate::common::constants::SLICE;
+use crate::common::{get_timeout_time, now};
+use crate::net::EventLoops;
+use crate::syscall::set_errno;
+use libc::{fd_set, sigset_t, timespec};
+use std::ffi::c_int;
+use std::time::Duration;
+
+trait PselectSyscall {
+    extern "C" fn pselect(
+        &self,
+        fn_ptr: Option<
+            &extern "C" fn(
+                c_int,
+                *mut fd_set,
+                *mut fd_set,
+                *mut fd_set,
+                *const timespec,
+                *const sigset_t,
+            ) -> c_int,
+        >,
+        nfds: c_int,
+        readfds: *mut fd_set,
+        writefds: *mut fd_set,
+        errorfds: *mut fd_set,
+        timeout: *const timespec,
+        sigmask: *const sigset_t,
+    ) -> c_int;
+}
+
+impl_syscall!(PselectSyscallFacade, NioPselectSyscall, RawPselectSyscall,
+    pselect(
+        nfds: c_int,
+        readfds: *mut fd_set,
+        writefds: *mut fd_set,
+        errorfds: *mut fd_set,
+        timeout: *const timespec,
+        sigmask: *const sigset_t
+    ) -> c_int
+);
+
+impl_facade!(PselectSyscallFacade, PselectSyscall,
+    pselect(
+        nfds: c_int,
+        readfds: *mut fd_set,
+        writefds: *mut fd_set,
+        errorfds: *mut fd_set,
+        timeout: *const timespec,
+        sigmask: *const sigset_t
+    ) -> c_int
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioPselectSyscall<I: PselectSyscall> {
+    inner: I,
+}
+
+impl<I: PselectSyscall> PselectSyscall for NioPselectSyscall<I> {
+    extern "C" fn pselect(
+        &self,
+        fn_ptr: Option<
+            &extern "C" fn(
+                c_int,
+                *mut fd_set,
+                *mut fd_set,
+                *mut fd_set,
+                *const timespec,
+                *const sigset_t,
+            ) -> c_int,
+        >,
+        nfds: c_int,
+        readfds: *mut fd_set,
+        writefds: *mut fd_set,
+        errorfds: *mut fd_set,
+        timeout: *const timespec,
+        sigmask: *const sigset_t,
+    ) -> c_int {
+        let timeout_time = if timeout.is_null() {
+            u64::MAX
+        } else {
+            let time = unsafe { *timeout };
+            if time.tv_sec < 0 || time.tv_nsec < 0 || time.tv_nsec > 999_999_999 {
+                set_errno(libc::EINVAL);
+                return -1;
+            }
+            get_timeout_time(Duration::new(
+                u64::try_from(time.tv_sec).expect("overflow"),
+                u32::try_from(time.tv_nsec).expect("overflow"),
+            ))
+        };
+        let zero = timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        let mut s: [fd_set; 3] = unsafe { std::mem::zeroed() };
+        if !readfds.is_null() {
+            s[0] = unsafe { *readfds };
+        }
+        if !writefds.is_null() {
+            s[1] = unsafe { *writefds };
+        }
+        if !errorfds.is_null() {
+            s[2] = unsafe { *errorfds };
+        }
+        let (read_fds, write_fds) =
+            super::select::select_interests(nfds, readfds, writefds, errorfds);
+        loop {
+            // the sigmask only takes effect while checking the fds,
+            // signals arriving during the park are handled with the coroutine's thread mask
+            let r = self.inner.pselect(
+                fn_ptr,
+                nfds,
+                readfds,
+                writefds,
+                errorfds,
+                &raw const zero,
+                sigmask,
+            );
+            let left_time = timeout_time.saturating_sub(now());
+            if r != 0 || left_time == 0 {
+                return r;
+            }
+            _ = EventLoops::wait_fds_event(
+                &read_fds,
+                &write_fds,
+                Some(Duration::from_nanos(left_time).min(SLICE)),
+            );
+
+            if !readfds.is_null() {
+                unsafe { *readfds = s[0] };
+            }
+            if !writefds.is_null() {
+                unsafe { *writefds = s[1] };
+            }
+            if !errorfds.is_null() {
+                unsafe { *errorfds = s[2] };
+            }
+        }
+    }
+}
+
+impl_raw!(RawPselectSyscall, PselectSyscall,
+    pselect(
+        nfds: c_int,
+        readfds: *mut fd_set,
+        writefds: *mut fd_set,
+        errorfds: *mut fd_set,
+        timeout: *const timespec,
+        sigmask: *const sigset_t
+    ) -> c_int
+);
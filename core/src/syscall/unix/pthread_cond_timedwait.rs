@@ -1,4 +1,4 @@
-use crate::common::now;
+use crate::common::{from_realtime, now, to_realtime};
 use crate::net::EventLoops;
 use libc::{pthread_cond_t, pthread_mutex_t, timespec};
 use std::ffi::c_int;
@@ -68,6 +68,7 @@ impl<I: PthreadCondTimedwaitSyscall> PthreadCondTimedwaitSyscall
                 abstime,
             );
         }
+        // the abstime is based on CLOCK_REALTIME, convert it to the monotonic clock at the boundary
         let abstimeout = if abstime.is_null() {
             u64::MAX
         } else {
@@ -75,18 +76,18 @@ impl<I: PthreadCondTimedwaitSyscall> PthreadCondTimedwaitSyscall
             if abstime.tv_sec < 0 || abstime.tv_nsec < 0 || abstime.tv_nsec > 999_999_999 {
                 return libc::EINVAL;
             }
-            u64::try_from(Duration::new(
+            from_realtime(u64::try_from(Duration::new(
                     abstime.tv_sec.try_into().expect("overflow"),
                     abstime.tv_nsec.try_into().expect("overflow")
                 ).as_nanos()
-            ).unwrap_or(u64::MAX)
+            ).unwrap_or(u64::MAX))
         };
         loop {
             let mut left_time = abstimeout.saturating_sub(now());
             if 0 == left_time {
                 return libc::ETIMEDOUT;
             }
-            let next_timeout = now().saturating_add(wait_time(left_time));
+            let next_timeout = to_realtime(now().saturating_add(wait_time(left_time)));
             let r = self.inner.pthread_cond_timedwait(
                 fn_ptr,
                 cond,
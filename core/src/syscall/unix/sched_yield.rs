@@ -0,0 +1,68 @@
+use crate::common::constants::{CoroutineState, SyscallState};
+use crate::common::now;
+use crate::scheduler::{SchedulableCoroutine, SchedulableSuspender};
+use std::ffi::c_int;
+
+trait SchedYieldSyscall {
+    extern "C" fn sched_yield(&self, fn_ptr: Option<&extern "C" fn() -> c_int>) -> c_int;
+}
+
+#[must_use]
+pub extern "C" fn sched_yield(fn_ptr: Option<&extern "C" fn() -> c_int>) -> c_int {
+    static CHAIN: once_cell::sync::Lazy<
+        SchedYieldSyscallFacade<NioSchedYieldSyscall<RawSchedYieldSyscall>>,
+    > = once_cell::sync::Lazy::new(Default::default);
+    // the locks taken while changing the coroutine state spin with sched_yield, yield the
+    // thread directly, or the hook changes the state again and recurses until stack overflow
+    if SchedulableCoroutine::current()
+        .is_some_and(|co| !matches!(co.state(), CoroutineState::Running))
+    {
+        return RawSchedYieldSyscall::default().sched_yield(fn_ptr);
+    }
+    CHAIN.sched_yield(fn_ptr)
+}
+
+impl_facade!(SchedYieldSyscallFacade, SchedYieldSyscall, sched_yield() -> c_int);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioSchedYieldSyscall<I: SchedYieldSyscall> {
+    inner: I,
+}
+
+impl<I: SchedYieldSyscall> SchedYieldSyscall for NioSchedYieldSyscall<I> {
+    extern "C" fn sched_yield(&self, fn_ptr: Option<&extern "C" fn() -> c_int>) -> c_int {
+        let (Some(co), Some(suspender)) = (
+            SchedulableCoroutine::current(),
+            SchedulableSuspender::current(),
+        ) else {
+            return self.inner.sched_yield(fn_ptr);
+        };
+        // an already expired suspend moves the coroutine to the back of the ready queue
+        let syscall = crate::common::constants::SyscallName::sched_yield;
+        let new_state = SyscallState::Suspend(now());
+        if co.syscall((), syscall, new_state).is_err() {
+            crate::error!(
+                "{} change to syscall {} {} failed !",
+                co.name(),
+                syscall,
+                new_state
+            );
+        }
+        suspender.suspend();
+        if let Some(co) = SchedulableCoroutine::current() {
+            let new_state = SyscallState::Executing;
+            if co.syscall((), syscall, new_state).is_err() {
+                crate::error!(
+                    "{} change to syscall {} {} failed !",
+                    co.name(),
+                    syscall,
+                    new_state
+                );
+            }
+        }
+        0
+    }
+}
+
+impl_raw!(RawSchedYieldSyscall, SchedYieldSyscall, sched_yield() -> c_int);
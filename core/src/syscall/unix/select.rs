@@ -1,6 +1,8 @@
+use crate::common::constants::SLICE;
+use crate::common::{get_timeout_time, now};
 use crate::net::EventLoops;
 use libc::{fd_set, timeval};
-use std::ffi::{c_int, c_uint};
+use std::ffi::c_int;
 use std::time::Duration;
 
 trait SelectSyscall {
@@ -55,14 +57,16 @@ impl<I: SelectSyscall> SelectSyscall for NioSelectSyscall<I> {
         errorfds: *mut fd_set,
         timeout: *mut timeval,
     ) -> c_int {
-        let mut t = if timeout.is_null() {
-            c_uint::MAX
+        let timeout_time = if timeout.is_null() {
+            u64::MAX
         } else {
-            unsafe {
-                c_uint::try_from((*timeout).tv_sec).expect("overflow")
-                    .saturating_mul(1_000_000)
-                    .saturating_add(c_uint::try_from((*timeout).tv_usec).expect("overflow"))
-            }
+            let time = unsafe { *timeout };
+            get_timeout_time(Duration::new(
+                u64::try_from(time.tv_sec).expect("overflow"),
+                u32::try_from(time.tv_usec)
+                    .expect("overflow")
+                    .saturating_mul(1_000),
+            ))
         };
         let mut o = timeval {
             tv_sec: 0,
@@ -78,23 +82,21 @@ impl<I: SelectSyscall> SelectSyscall for NioSelectSyscall<I> {
         if !errorfds.is_null() {
             s[2] = unsafe { *errorfds };
         }
-        let mut x = 1;
-        let mut r;
-        // just check select every x ms
+        let (read_fds, write_fds) = select_interests(nfds, readfds, writefds, errorfds);
         loop {
-            r = self
+            let r = self
                 .inner
                 .select(fn_ptr, nfds, readfds, writefds, errorfds, &raw mut o);
-            if r != 0 || t == 0 {
-                break;
-            }
-            _ = EventLoops::wait_event(Some(Duration::from_millis(u64::from(t.min(x)))));
-            if t != c_uint::MAX {
-                t = t.saturating_sub(x);
-            }
-            if x < 16 {
-                x <<= 1;
+            let left_time = timeout_time.saturating_sub(now());
+            if r != 0 || left_time == 0 {
+                return r;
             }
+            // park until any fd is ready, wake up every slice in case some fds can't be registered
+            _ = EventLoops::wait_fds_event(
+                &read_fds,
+                &write_fds,
+                Some(Duration::from_nanos(left_time).min(SLICE)),
+            );
 
             if !readfds.is_null() {
                 unsafe { *readfds = s[0] };
@@ -108,10 +110,33 @@ impl<I: SelectSyscall> SelectSyscall for NioSelectSyscall<I> {
             o.tv_sec = 0;
             o.tv_usec = 0;
         }
-        r
     }
 }
 
+/// Collect the fds in the sets, exceptional conditions are treated as read interests.
+pub(super) fn select_interests(
+    nfds: c_int,
+    readfds: *const fd_set,
+    writefds: *const fd_set,
+    errorfds: *const fd_set,
+) -> (Vec<c_int>, Vec<c_int>) {
+    let mut read_fds = Vec::new();
+    let mut write_fds = Vec::new();
+    for fd in 0..nfds {
+        unsafe {
+            if (!readfds.is_null() && libc::FD_ISSET(fd, readfds))
+                || (!errorfds.is_null() && libc::FD_ISSET(fd, errorfds))
+            {
+                read_fds.push(fd);
+            }
+            if !writefds.is_null() && libc::FD_ISSET(fd, writefds) {
+                write_fds.push(fd);
+            }
+        }
+    }
+    (read_fds, write_fds)
+}
+
 impl_raw!(RawSelectSyscall, SelectSyscall,
     select(
         nfds: c_int,
@@ -0,0 +1,58 @@
+use super::waitpid::{close_pidfd, pidfd_open, wait_exit};
+use libc::{pid_t, rusage};
+use std::ffi::c_int;
+
+trait Wait4Syscall {
+    extern "C" fn wait4(
+        &self,
+        fn_ptr: Option<&extern "C" fn(pid_t, *mut c_int, c_int, *mut rusage) -> pid_t>,
+        pid: pid_t,
+        status: *mut c_int,
+        options: c_int,
+        rusage: *mut rusage,
+    ) -> pid_t;
+}
+
+impl_syscall!(Wait4SyscallFacade, NioWait4Syscall, RawWait4Syscall,
+    wait4(pid: pid_t, status: *mut c_int, options: c_int, rusage: *mut rusage) -> pid_t
+);
+
+impl_facade!(Wait4SyscallFacade, Wait4Syscall,
+    wait4(pid: pid_t, status: *mut c_int, options: c_int, rusage: *mut rusage) -> pid_t
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioWait4Syscall<I: Wait4Syscall> {
+    inner: I,
+}
+
+impl<I: Wait4Syscall> Wait4Syscall for NioWait4Syscall<I> {
+    extern "C" fn wait4(
+        &self,
+        fn_ptr: Option<&extern "C" fn(pid_t, *mut c_int, c_int, *mut rusage) -> pid_t>,
+        pid: pid_t,
+        status: *mut c_int,
+        options: c_int,
+        rusage: *mut rusage,
+    ) -> pid_t {
+        if options & libc::WNOHANG != 0 {
+            return self.inner.wait4(fn_ptr, pid, status, options, rusage);
+        }
+        let pidfd = pidfd_open(pid);
+        loop {
+            let r = self
+                .inner
+                .wait4(fn_ptr, pid, status, options | libc::WNOHANG, rusage);
+            if r != 0 {
+                close_pidfd(pidfd);
+                return r;
+            }
+            wait_exit(pidfd);
+        }
+    }
+}
+
+impl_raw!(RawWait4Syscall, Wait4Syscall,
+    wait4(pid: pid_t, status: *mut c_int, options: c_int, rusage: *mut rusage) -> pid_t
+);
@@ -0,0 +1,75 @@
+use super::waitpid::{close_pidfd, pidfd_open, wait_exit};
+use crate::common::constants::SLICE;
+use crate::net::EventLoops;
+use libc::{id_t, idtype_t, pid_t, siginfo_t};
+use std::ffi::c_int;
+
+trait WaitidSyscall {
+    extern "C" fn waitid(
+        &self,
+        fn_ptr: Option<&extern "C" fn(idtype_t, id_t, *mut siginfo_t, c_int) -> c_int>,
+        idtype: idtype_t,
+        id: id_t,
+        infop: *mut siginfo_t,
+        options: c_int,
+    ) -> c_int;
+}
+
+impl_syscall!(WaitidSyscallFacade, NioWaitidSyscall, RawWaitidSyscall,
+    waitid(idtype: idtype_t, id: id_t, infop: *mut siginfo_t, options: c_int) -> c_int
+);
+
+impl_facade!(WaitidSyscallFacade, WaitidSyscall,
+    waitid(idtype: idtype_t, id: id_t, infop: *mut siginfo_t, options: c_int) -> c_int
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioWaitidSyscall<I: WaitidSyscall> {
+    inner: I,
+}
+
+impl<I: WaitidSyscall> WaitidSyscall for NioWaitidSyscall<I> {
+    extern "C" fn waitid(
+        &self,
+        fn_ptr: Option<&extern "C" fn(idtype_t, id_t, *mut siginfo_t, c_int) -> c_int>,
+        idtype: idtype_t,
+        id: id_t,
+        infop: *mut siginfo_t,
+        options: c_int,
+    ) -> c_int {
+        if options & libc::WNOHANG != 0 || infop.is_null() {
+            return self.inner.waitid(fn_ptr, idtype, id, infop, options);
+        }
+        let (pidfd, owned) = match idtype {
+            libc::P_PID => (pid_t::try_from(id).ok().and_then(pidfd_open), true),
+            // the caller already holds a pidfd
+            libc::P_PIDFD => (c_int::try_from(id).ok(), false),
+            _ => (None, true),
+        };
+        loop {
+            // with WNOHANG, si_pid stays zero if no child has changed state
+            unsafe { std::ptr::write_bytes(infop, 0, 1) };
+            let r = self
+                .inner
+                .waitid(fn_ptr, idtype, id, infop, options | libc::WNOHANG);
+            if r != 0 || unsafe { (*infop).si_pid() } != 0 {
+                if owned {
+                    close_pidfd(pidfd);
+                }
+                return r;
+            }
+            match pidfd {
+                // the pidfd of the caller is kept, only the interest added here is removed
+                Some(fd) if !owned => {
+                    _ = EventLoops::wait_fds_event(&[fd], &[], Some(SLICE));
+                }
+                _ => wait_exit(pidfd),
+            }
+        }
+    }
+}
+
+impl_raw!(RawWaitidSyscall, WaitidSyscall,
+    waitid(idtype: idtype_t, id: id_t, infop: *mut siginfo_t, options: c_int) -> c_int
+);
@@ -0,0 +1,90 @@
+use crate::common::constants::SLICE;
+use crate::net::EventLoops;
+use libc::pid_t;
+use std::ffi::c_int;
+
+trait WaitpidSyscall {
+    extern "C" fn waitpid(
+        &self,
+        fn_ptr: Option<&extern "C" fn(pid_t, *mut c_int, c_int) -> pid_t>,
+        pid: pid_t,
+        status: *mut c_int,
+        options: c_int,
+    ) -> pid_t;
+}
+
+impl_syscall!(WaitpidSyscallFacade, NioWaitpidSyscall, RawWaitpidSyscall,
+    waitpid(pid: pid_t, status: *mut c_int, options: c_int) -> pid_t
+);
+
+impl_facade!(WaitpidSyscallFacade, WaitpidSyscall,
+    waitpid(pid: pid_t, status: *mut c_int, options: c_int) -> pid_t
+);
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct NioWaitpidSyscall<I: WaitpidSyscall> {
+    inner: I,
+}
+
+impl<I: WaitpidSyscall> WaitpidSyscall for NioWaitpidSyscall<I> {
+    extern "C" fn waitpid(
+        &self,
+        fn_ptr: Option<&extern "C" fn(pid_t, *mut c_int, c_int) -> pid_t>,
+        pid: pid_t,
+        status: *mut c_int,
+        options: c_int,
+    ) -> pid_t {
+        if options & libc::WNOHANG != 0 {
+            return self.inner.waitpid(fn_ptr, pid, status, options);
+        }
+        let pidfd = pidfd_open(pid);
+        loop {
+            let r = self
+                .inner
+                .waitpid(fn_ptr, pid, status, options | libc::WNOHANG);
+            if r != 0 {
+                close_pidfd(pidfd);
+                return r;
+            }
+            wait_exit(pidfd);
+        }
+    }
+}
+
+impl_raw!(RawWaitpidSyscall, WaitpidSyscall,
+    waitpid(pid: pid_t, status: *mut c_int, options: c_int) -> pid_t
+);
+
+/// Open a pidfd for the child, so that its exit can be waited through the selector.
+/// Returns `None` if `pid` does not refer to a single child or the kernel lacks `pidfd_open`.
+pub(super) fn pidfd_open(pid: pid_t) -> Option<c_int> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "linux")] {
+            if pid <= 0 {
+                return None;
+            }
+            let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+            c_int::try_from(fd).ok().filter(|fd| *fd >= 0)
+        } else {
+            _ = pid;
+            None
+        }
+    }
+}
+
+/// Park the current coroutine until the child exits, wake up every slice in case
+/// other children are waited or the pidfd is unavailable.
+pub(super) fn wait_exit(pidfd: Option<c_int>) {
+    _ = match pidfd {
+        Some(fd) => EventLoops::wait_read_event(fd, Some(SLICE)),
+        None => EventLoops::wait_event(Some(SLICE)),
+    };
+}
+
+pub(super) fn close_pidfd(pidfd: Option<c_int>) {
+    if let Some(fd) = pidfd {
+        _ = EventLoops::del_event(fd);
+        _ = unsafe { libc::close(fd) };
+    }
+}
@@ -1,3 +1,16 @@
+use open_coroutine_core::co_pool::listener::TaskListener;
+use open_coroutine_core::co_pool::schedule::OverrunPolicy;
+use open_coroutine_core::co_pool::task::{
+    Backoff, RejectionPolicy, RetryPolicy, Task, TimeoutFrom, TIMEOUT_ERROR,
+};
+use open_coroutine_core::co_pool::CoroutinePool;
+use open_coroutine_core::common::constants::DEFAULT_STACK_SIZE;
+use open_coroutine_core::common::now;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 #[cfg(not(feature = "preemptive"))]
 #[test]
 fn co_pool_basic() -> std::io::Result<()> {
@@ -52,8 +65,12 @@ fn co_pool_suspend() -> std::io::Result<()> {
         None,
     )?;
     pool.try_schedule_task()?;
+    // the delayed task is still busy, the other coroutine is idle
+    assert_eq!(1, pool.get_busy_size());
     std::thread::sleep(std::time::Duration::from_millis(200));
-    pool.try_schedule_task()
+    pool.try_schedule_task()?;
+    assert_eq!(0, pool.get_busy_size());
+    Ok(())
 }
 
 #[cfg(not(feature = "preemptive"))]
@@ -87,7 +104,7 @@ fn co_pool_cancel() -> std::io::Result<()> {
         None,
     )?;
     assert!(!pool.is_empty());
-    open_coroutine_core::co_pool::CoroutinePool::try_cancel_task(task_id);
+    pool.try_cancel_task(task_id);
     pool.submit_task(
         Some(String::from("test_simple")),
         |_| {
@@ -99,3 +116,207 @@ fn co_pool_cancel() -> std::io::Result<()> {
     )?;
     pool.try_schedule_task()
 }
+
+#[cfg(not(feature = "preemptive"))]
+#[test]
+fn co_pool_rejection_policy() -> std::io::Result<()> {
+    let mut pool = CoroutinePool::default();
+    pool.set_max_size(1);
+    pool.set_task_capacity(1);
+    let oldest = pool.submit_task(None, |_| Some(1), None, None)?;
+    let error = pool
+        .submit_task(None, |_| Some(2), None, None)
+        .expect_err("the task should be rejected");
+    assert_eq!(ErrorKind::WouldBlock, error.kind());
+    pool.set_rejection_policy(RejectionPolicy::CallerRuns);
+    let task_id = pool.submit_task(None, |_| Some(3), None, None)?;
+    assert_eq!(Some(Ok(Some(3))), pool.try_take_task_result(task_id));
+    pool.set_rejection_policy(RejectionPolicy::DiscardOldest);
+    let task_id = pool.submit_task(None, |_| Some(4), None, None)?;
+    assert_eq!(
+        Some(Err("The task was discarded")),
+        pool.try_take_task_result(oldest)
+    );
+    assert_eq!(1, pool.get_queued_size());
+    assert_eq!(3, pool.rejections());
+    pool.try_schedule_task()?;
+    assert_eq!(Some(Ok(Some(4))), pool.try_take_task_result(task_id));
+    assert_eq!(0, pool.get_queued_size());
+    Ok(())
+}
+
+#[cfg(not(feature = "preemptive"))]
+#[test]
+fn co_pool_timeout() -> std::io::Result<()> {
+    let mut pool = CoroutinePool::default();
+    // timed out in the queue
+    let expired =
+        pool.submit_task_with_timeout(None, |_| Some(1), None, None, 0, TimeoutFrom::Submit)?;
+    let finished = pool.submit_task_with_timeout(
+        None,
+        |_| Some(2),
+        None,
+        None,
+        Duration::from_secs(10)
+            .as_nanos()
+            .try_into()
+            .expect("overflow"),
+        TimeoutFrom::Start,
+    )?;
+    pool.try_schedule_task()?;
+    assert_eq!(Some(Err(TIMEOUT_ERROR)), pool.try_take_task_result(expired));
+    assert_eq!(Some(Ok(Some(2))), pool.try_take_task_result(finished));
+    assert_eq!(1, pool.timeouts());
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct AttemptRecorder(Mutex<Vec<(u32, bool)>>);
+
+impl TaskListener for AttemptRecorder {
+    fn on_attempt(
+        &self,
+        _: u64,
+        _: &str,
+        attempt: u32,
+        _: &Result<Option<usize>, &str>,
+        retry_delay: Option<Duration>,
+    ) {
+        self.0
+            .lock()
+            .expect("lock failed")
+            .push((attempt, retry_delay.is_some()));
+    }
+}
+
+#[cfg(not(feature = "preemptive"))]
+#[test]
+fn co_pool_retry() -> std::io::Result<()> {
+    let mut pool = CoroutinePool::default();
+    let recorder = Arc::new(AttemptRecorder::default());
+    pool.add_task_listener(recorder.clone());
+    let failures = AtomicUsize::new(2);
+    let task_id = pool.submit_task_with_retry(
+        None,
+        move |_| {
+            assert!(
+                failures
+                    .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+                    .is_err(),
+                "test retry, just ignore it"
+            );
+            Some(1)
+        },
+        None,
+        None,
+        RetryPolicy::new(5, Backoff::Fixed(1_000_000)).with_jitter(true),
+    )?;
+    let result = loop {
+        _ = pool.try_timed_schedule_task(Duration::from_millis(10))?;
+        if let Some(result) = pool.try_take_task_result(task_id) {
+            break result;
+        }
+    };
+    assert_eq!(Ok(Some(1)), result);
+    assert_eq!(
+        vec![(1, true), (2, true), (3, false)],
+        *recorder.0.lock().expect("lock failed")
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "preemptive"))]
+#[test]
+fn co_pool_schedule() -> std::io::Result<()> {
+    let mut pool = CoroutinePool::default();
+    let start = now();
+    let delayed = pool.submit_delayed(
+        Task::new(
+            String::from("delayed"),
+            move |_| usize::try_from(now().saturating_sub(start)).ok(),
+            None,
+            None,
+        ),
+        Duration::from_millis(20),
+    )?;
+    let rate = Arc::new(AtomicUsize::new(0));
+    let counter = rate.clone();
+    let fixed_rate = pool.submit_at_fixed_rate(
+        None,
+        move |_| {
+            _ = counter.fetch_add(1, Ordering::Release);
+            None
+        },
+        None,
+        None,
+        Duration::ZERO,
+        Duration::from_millis(5),
+        OverrunPolicy::Skip,
+    )?;
+    let delay = Arc::new(AtomicUsize::new(0));
+    let counter = delay.clone();
+    let fixed_delay = pool.submit_with_fixed_delay(
+        None,
+        move |_| {
+            _ = counter.fetch_add(1, Ordering::Release);
+            None
+        },
+        None,
+        None,
+        Duration::ZERO,
+        Duration::from_millis(5),
+    )?;
+    let cancelled = pool.submit_delayed(
+        Task::new(String::from("cancelled"), |_| Some(0), None, None),
+        Duration::from_millis(10),
+    )?;
+    assert!(pool.try_cancel_schedule(cancelled));
+    let elapsed = loop {
+        _ = pool.try_timed_schedule_task(Duration::from_millis(1))?;
+        if let Some(result) = pool.try_take_task_result(delayed) {
+            break result.expect("delayed task failed").expect("no result");
+        }
+    };
+    assert!(elapsed >= 20_000_000);
+    while rate.load(Ordering::Acquire) < 3 || delay.load(Ordering::Acquire) < 3 {
+        _ = pool.try_timed_schedule_task(Duration::from_millis(1))?;
+    }
+    assert!(pool.try_cancel_schedule(fixed_rate));
+    assert!(pool.try_cancel_schedule(fixed_delay));
+    assert!(!pool.try_cancel_schedule(fixed_delay));
+    let runs = (rate.load(Ordering::Acquire), delay.load(Ordering::Acquire));
+    _ = pool.try_timed_schedule_task(Duration::from_millis(20))?;
+    // the queued runs may still run after the schedules are cancelled
+    assert!(rate.load(Ordering::Acquire) <= runs.0 + 1);
+    assert!(delay.load(Ordering::Acquire) <= runs.1 + 1);
+    assert_eq!(None, pool.try_take_task_result(cancelled));
+    Ok(())
+}
+
+#[cfg(not(feature = "preemptive"))]
+#[test]
+fn co_pool_isolation() -> std::io::Result<()> {
+    let new_pool = |runtime: &str| {
+        CoroutinePool::with_runtime(
+            format!("{runtime}-pool"),
+            runtime,
+            DEFAULT_STACK_SIZE,
+            0,
+            1,
+            0,
+        )
+    };
+    let mut pool = new_pool("isolation-a");
+    let mut other = new_pool("isolation-b");
+    let task_id = other.submit_task(None, |_| Some(1), None, None)?;
+    assert!(pool.get_task(task_id).is_none());
+    assert!(other.get_task(task_id).is_some());
+    // neither cancelling nor stopping a pool affects the tasks of other runtimes
+    pool.try_cancel_task(task_id);
+    pool.stop(Duration::from_secs(1))?;
+    other.try_schedule_task()?;
+    assert_eq!(Some(Ok(Some(1))), other.try_take_task_result(task_id));
+    // the task is removed once it's finished
+    assert!(other.get_task(task_id).is_none());
+    Ok(())
+}
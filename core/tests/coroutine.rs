@@ -1,3 +1,9 @@
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::manual_dangling_ptr,
+    clippy::match_like_matches_macro
+)]
+
 use corosensei::stack::{DefaultStack, Stack};
 use open_coroutine_core::co;
 use open_coroutine_core::common::constants::CoroutineState;
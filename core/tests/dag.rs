@@ -0,0 +1,75 @@
+use open_coroutine_core::config::Config;
+use open_coroutine_core::net::dag::{FailurePolicy, NodeStatus, TaskGraph, TaskGraphHandle};
+use open_coroutine_core::net::EventLoops;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+fn init() {
+    // the failure test needs two nodes running in parallel
+    let mut config = Config::default();
+    _ = config.set_event_loop_size(2);
+    EventLoops::init(&config);
+}
+
+#[test]
+fn dag_basic() -> std::io::Result<()> {
+    init();
+    let mut graph = TaskGraph::new(FailurePolicy::FailFast);
+    let a = graph.add_node("a", &[], |_| Ok(Some(1)))?;
+    let b = graph.add_node("b", &[a], |inputs| Ok(inputs[0].map(|v| v + 1)))?;
+    let c = graph.add_node("c", &[a], |inputs| Ok(inputs[0].map(|v| v * 10)))?;
+    let d = graph.add_node("d", &[b, c], |inputs| {
+        Ok(Some(inputs.into_iter().flatten().sum()))
+    })?;
+    assert!(graph.add_node("e", &[d + 1], |_| Ok(None)).is_err());
+    let report = graph.submit().timeout_join(Duration::from_secs(5))?;
+    assert!(report.is_success());
+    assert_eq!(Some(Ok(Some(12))), report.nodes[d].result);
+    assert!(report.nodes.iter().all(|node| node.elapsed().is_some()));
+    Ok(())
+}
+
+#[test]
+fn dag_failure() -> std::io::Result<()> {
+    init();
+    for policy in [FailurePolicy::FailFast, FailurePolicy::ContinueIndependent] {
+        let (started, wait_started) = channel();
+        let submitted = Arc::new(OnceLock::new());
+        let mut graph = TaskGraph::new(policy);
+        let failed = graph.add_node("failed", &[], move |_| {
+            // make sure the independent node is running
+            wait_started
+                .recv_timeout(Duration::from_secs(5))
+                .expect("the independent node never started");
+            Err("test error, just ignore it")
+        })?;
+        let dependent = graph.add_node("dependent", &[failed], |_| Ok(Some(1)))?;
+        let graph_handle = submitted.clone();
+        let independent = graph.add_node("independent", &[], move |_| {
+            started.send(()).expect("send failed");
+            // finish after the failure is handled
+            while !graph_handle.get().is_some_and(|handle: &TaskGraphHandle| {
+                handle.report().nodes[failed].status.is_terminal()
+            }) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Ok(Some(2))
+        })?;
+        let downstream = graph.add_node("downstream", &[independent], |inputs| Ok(inputs[0]))?;
+        let handle = graph.submit();
+        submitted.set(handle.clone()).expect("set failed");
+        let report = handle.timeout_join(Duration::from_secs(5))?;
+        assert!(!report.is_success());
+        assert_eq!(NodeStatus::Failed, report.nodes[failed].status);
+        assert_eq!(NodeStatus::Cancelled, report.nodes[dependent].status);
+        // the running node is never interrupted
+        assert_eq!(NodeStatus::Completed, report.nodes[independent].status);
+        let expected = match policy {
+            FailurePolicy::FailFast => NodeStatus::Cancelled,
+            FailurePolicy::ContinueIndependent => NodeStatus::Completed,
+        };
+        assert_eq!(expected, report.nodes[downstream].status);
+    }
+    Ok(())
+}
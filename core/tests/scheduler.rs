@@ -157,3 +157,25 @@ fn scheduler_try_cancel_coroutine() -> std::io::Result<()> {
     scheduler.try_schedule()?;
     Ok(())
 }
+
+#[test]
+fn scheduler_try_cancel_suspended_coroutine() -> std::io::Result<()> {
+    let mut scheduler = Scheduler::default();
+    let co_id = scheduler.submit_co(
+        |suspender, _| {
+            println!("[coroutine] delay");
+            suspender.delay(Duration::from_secs(3600));
+            unreachable!("[coroutine] cancelled while delayed");
+        },
+        None,
+        None,
+    )?;
+    _ = scheduler.try_timed_schedule(Duration::from_millis(10))?;
+    // the id of no coroutine is dropped
+    Scheduler::try_cancel_coroutine(u64::MAX);
+    Scheduler::try_cancel_coroutine(co_id);
+    let (_, results) = scheduler.try_timed_schedule(Duration::from_secs(1))?;
+    assert!(results.is_empty());
+    assert_eq!(0, scheduler.load().suspended);
+    Ok(())
+}
@@ -45,24 +45,35 @@
     clippy::separated_literal_suffix, // conflicts with clippy::unseparated_literal_suffix
     clippy::single_char_lifetime_names, // TODO: change lifetime names
     clippy::test_attr_in_doctest,
+    clippy::not_unsafe_ptr_arg_deref, // the C API checks the pointers passed by the caller
     unknown_lints, // for windows nightly
     linker_messages, // for windows nightly
 )]
 #![doc = include_str!("../docs/en/hook.md")]
 
-use once_cell::sync::OnceCell;
-use open_coroutine_core::co_pool::task::UserTaskFunc;
+use open_coroutine_core::co_pool::listener::{RawAttempt, RawTaskListener, TaskListener};
+use open_coroutine_core::co_pool::task::{
+    DeadlineMissPolicy, RawTaskInfo, TimeoutFrom, UserTaskFree, UserTaskFunc, TIMEOUT_ERROR,
+};
 use open_coroutine_core::config::Config;
+use open_coroutine_core::net::dag::{
+    FailurePolicy, RawNodeFunc, RawNodeReport, RawNodeResult, TaskGraph, TaskGraphHandle,
+};
 use open_coroutine_core::net::join::JoinHandle;
-use open_coroutine_core::net::{EventLoops, UserFunc};
+use open_coroutine_core::net::pool::PoolHandle;
+use open_coroutine_core::net::stats::RawRuntimeStats;
+use open_coroutine_core::net::{EventLoops, RawTaskOptions, TaskOptions, UserFunc};
 use open_coroutine_core::scheduler::SchedulableCoroutine;
-use std::ffi::{c_int, c_longlong, c_uint};
+use std::ffi::{c_char, c_int, c_longlong, c_uint, c_void, CStr};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
-static HOOK: OnceCell<bool> = OnceCell::new();
+static HOOK: AtomicBool = AtomicBool::new(false);
 
 pub(crate) fn hook() -> bool {
-    HOOK.get().map_or_else(|| false, |v| *v)
+    HOOK.load(Ordering::Acquire)
 }
 
 #[allow(
@@ -75,11 +86,197 @@ pub(crate) fn hook() -> bool {
 )]
 pub mod syscall;
 
+// the param of a C task, freed if the task is dropped without running
+struct TaskParam(usize, Option<UserTaskFree>);
+
+impl TaskParam {
+    // the task owns the param once it runs
+    fn run(self, f: UserTaskFunc) -> usize {
+        f(self.take())
+    }
+
+    fn take(mut self) -> usize {
+        self.1 = None;
+        self.0
+    }
+}
+
+impl Drop for TaskParam {
+    fn drop(&mut self) {
+        if let Some(free) = self.1.take() {
+            free(self.0);
+        }
+    }
+}
+
+// the utf-8 name passed by C, `None` if it's null or invalid
+fn to_str<'a>(name: *const c_char) -> Option<&'a str> {
+    if name.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(name) }.to_str().ok()
+}
+
+// the named pool, sets the errno if it's not found
+fn get_pool(name: *const c_char) -> Option<PoolHandle> {
+    let Some(name) = to_str(name) else {
+        set_errno(ErrorKind::InvalidInput);
+        return None;
+    };
+    let pool = PoolHandle::get(name);
+    if pool.is_none() {
+        set_errno(ErrorKind::NotFound);
+    }
+    pool
+}
+
+// sets the errno if the task is not submitted
+fn checked(handle: JoinHandle) -> JoinHandle {
+    if let Err(e) = handle.id() {
+        set_errno(e.kind());
+    }
+    handle
+}
+
+// the errno of the failed C API, it's the last error on windows
+fn set_errno(kind: ErrorKind) {
+    #[cfg(unix)]
+    open_coroutine_core::syscall::set_errno(match kind {
+        ErrorKind::NotFound => libc::ENOENT,
+        ErrorKind::AlreadyExists => libc::EEXIST,
+        ErrorKind::TimedOut => libc::ETIMEDOUT,
+        ErrorKind::WouldBlock => libc::EAGAIN,
+        _ => libc::EINVAL,
+    });
+    #[cfg(windows)]
+    open_coroutine_core::syscall::set_errno(match kind {
+        ErrorKind::NotFound => windows_sys::Win32::Foundation::ERROR_NOT_FOUND,
+        ErrorKind::AlreadyExists => windows_sys::Win32::Foundation::ERROR_ALREADY_EXISTS,
+        ErrorKind::TimedOut => windows_sys::Win32::Foundation::ERROR_TIMEOUT,
+        ErrorKind::WouldBlock => windows_sys::Win32::Foundation::ERROR_BUSY,
+        _ => windows_sys::Win32::Foundation::ERROR_INVALID_PARAMETER,
+    });
+}
+
+// the C listener, its context is freed once it's dropped
+#[derive(Debug)]
+struct CTaskListener(RawTaskListener);
+
+// the callbacks are called by the event-loop threads
+unsafe impl Send for CTaskListener {}
+
+unsafe impl Sync for CTaskListener {}
+
+impl TaskListener for CTaskListener {
+    fn on_deadline_missed(&self, task_id: u64, task_name: &str, deadline: u64, started: bool) {
+        if let Some(f) = self.0.on_deadline_missed {
+            f(
+                self.0.context,
+                task_id,
+                task_name.as_ptr().cast(),
+                task_name.len(),
+                deadline,
+                started,
+            );
+        }
+    }
+
+    fn on_rejected(&self, task_id: u64, task_name: &str) {
+        if let Some(f) = self.0.on_rejected {
+            f(
+                self.0.context,
+                task_id,
+                task_name.as_ptr().cast(),
+                task_name.len(),
+            );
+        }
+    }
+
+    fn on_overrun(&self, schedule_id: u64, task_name: &str, missed: u64) {
+        if let Some(f) = self.0.on_overrun {
+            f(
+                self.0.context,
+                schedule_id,
+                task_name.as_ptr().cast(),
+                task_name.len(),
+                missed,
+            );
+        }
+    }
+
+    fn on_attempt(
+        &self,
+        task_id: u64,
+        task_name: &str,
+        attempt: u32,
+        result: &Result<Option<usize>, &str>,
+        retry_delay: Option<Duration>,
+    ) {
+        let Some(f) = self.0.on_attempt else {
+            return;
+        };
+        let error = result.err().unwrap_or_default();
+        let attempt = RawAttempt {
+            attempt,
+            succeeded: result.is_ok(),
+            has_result: matches!(result, Ok(Some(_))),
+            result: result.ok().flatten().unwrap_or_default(),
+            error: error.as_ptr().cast(),
+            error_len: error.len(),
+            retry: retry_delay.is_some(),
+            retry_delay: retry_delay.map_or(0, |delay| {
+                u64::try_from(delay.as_nanos()).unwrap_or(u64::MAX)
+            }),
+        };
+        f(
+            self.0.context,
+            task_id,
+            task_name.as_ptr().cast(),
+            task_name.len(),
+            &attempt,
+        );
+    }
+}
+
+impl Drop for CTaskListener {
+    fn drop(&mut self) {
+        if let Some(free) = self.0.free {
+            free(self.0.context);
+        }
+    }
+}
+
+fn task_main(
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+) -> impl FnOnce(Option<usize>) -> Option<usize> {
+    let param = TaskParam(param, free);
+    move |_| Some(param.run(f))
+}
+
+fn node_main(
+    f: RawNodeFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+) -> impl FnOnce(Vec<Option<usize>>) -> Result<Option<usize>, &'static str> {
+    let param = TaskParam(param, free);
+    move |inputs| {
+        let inputs: Vec<RawNodeResult> = inputs
+            .into_iter()
+            .map(|input| RawNodeResult::from(Ok(input)))
+            .collect();
+        let mut output = RawNodeResult::from(Ok(None));
+        f(param.take(), inputs.as_ptr(), inputs.len(), &raw mut output);
+        unsafe { output.into_result() }
+    }
+}
+
 /// Start the framework.
 #[no_mangle]
 pub extern "C" fn open_coroutine_init(config: Config) -> c_int {
     EventLoops::init(&config);
-    _ = HOOK.get_or_init(|| config.hook());
+    HOOK.store(config.hook(), Ordering::Release);
     0
 }
 
@@ -87,34 +284,263 @@ pub extern "C" fn open_coroutine_init(config: Config) -> c_int {
 #[no_mangle]
 pub extern "C" fn open_coroutine_stop(secs: c_uint) -> c_int {
     if EventLoops::stop(Duration::from_secs(u64::from(secs))).is_ok() {
+        HOOK.store(false, Ordering::Release);
         return 0;
     }
     -1
 }
 
+/// Get the statistics of the framework.
+#[no_mangle]
+pub extern "C" fn open_coroutine_stats(stats: *mut RawRuntimeStats) -> c_int {
+    let Some(stats) = (unsafe { stats.as_mut() }) else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    unsafe { EventLoops::stats().write_raw(stats) };
+    0
+}
+
+///创建隔离的命名协程池，失败时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn open_coroutine_create_pool(name: *const c_char, config: Config) -> c_int {
+    let Some(name) = to_str(name) else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    match PoolHandle::create(name, &config) {
+        Ok(_) => 0,
+        Err(e) => {
+            set_errno(e.kind());
+            -1
+        }
+    }
+}
+
+///停止命名协程池，失败时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn open_coroutine_stop_pool(name: *const c_char, secs: c_uint) -> c_int {
+    let Some(pool) = get_pool(name) else {
+        return -1;
+    };
+    match pool.stop(Duration::from_secs(u64::from(secs))) {
+        Ok(()) => 0,
+        Err(e) => {
+            set_errno(e.kind());
+            -1
+        }
+    }
+}
+
+///获取命名协程池的统计信息，失败时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn open_coroutine_pool_stats(
+    name: *const c_char,
+    stats: *mut RawRuntimeStats,
+) -> c_int {
+    let Some(stats) = (unsafe { stats.as_mut() }) else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    let Some(pool) = get_pool(name) else {
+        return -1;
+    };
+    unsafe { pool.stats().write_raw(stats) };
+    0
+}
+
+///在命名协程池中创建任务，成功时写入`handle`，失败时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn task_crate_in(
+    pool: *const c_char,
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+    priority: c_longlong,
+    handle: *mut JoinHandle,
+) -> c_int {
+    if handle.is_null() {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    }
+    let Some(pool) = get_pool(pool) else {
+        return -1;
+    };
+    let join_handle =
+        pool.submit_task(None, task_main(f, param, free), Some(param), Some(priority));
+    if let Err(e) = join_handle.id() {
+        set_errno(e.kind());
+        return -1;
+    }
+    unsafe { handle.write(join_handle) };
+    0
+}
+
 ///创建任务
 #[no_mangle]
 pub extern "C" fn task_crate(f: UserTaskFunc, param: usize, priority: c_longlong) -> JoinHandle {
-    EventLoops::submit_task(
+    checked(EventLoops::submit_task(
+        None,
+        task_main(f, param, None),
+        Some(param),
+        Some(priority),
+    ))
+}
+
+///创建任务，任务未运行就被丢弃时用`free`释放`param`
+#[no_mangle]
+pub extern "C" fn task_crate_with_free(
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+    priority: c_longlong,
+) -> JoinHandle {
+    checked(EventLoops::submit_task(
         None,
-        move |p| Some(f(p.unwrap_or(0))),
+        task_main(f, param, free),
         Some(param),
         Some(priority),
-    )
+    ))
+}
+
+///创建有名字的任务，`name`为空时自动生成名字
+#[no_mangle]
+pub extern "C" fn task_crate_named(
+    name: *const c_char,
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+    priority: c_longlong,
+) -> JoinHandle {
+    checked(EventLoops::submit_task(
+        to_str(name).map(String::from),
+        task_main(f, param, free),
+        Some(param),
+        Some(priority),
+    ))
+}
+
+///按id查询任务，找不到时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn open_coroutine_get_task(task_id: u64, info: *mut RawTaskInfo) -> c_int {
+    let Some(info) = (unsafe { info.as_mut() }) else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    let Some(task) = EventLoops::get_task(task_id) else {
+        set_errno(ErrorKind::NotFound);
+        return -1;
+    };
+    unsafe { task.write_raw(info) };
+    0
+}
+
+///按名字查询任务，最多写入`capacity`个id，返回匹配的任务总数
+#[no_mangle]
+pub extern "C" fn open_coroutine_find_tasks(
+    name: *const c_char,
+    ids: *mut u64,
+    capacity: usize,
+) -> usize {
+    let Some(name) = to_str(name) else {
+        return 0;
+    };
+    let tasks = EventLoops::find_tasks(name);
+    if !ids.is_null() {
+        for (i, task) in tasks.iter().take(capacity).enumerate() {
+            unsafe { ids.add(i).write(task.id) };
+        }
+    }
+    tasks.len()
+}
+
+///按名字取消任务
+#[no_mangle]
+pub extern "C" fn open_coroutine_cancel_tasks(name: *const c_char) -> usize {
+    to_str(name).map_or(0, EventLoops::try_cancel_tasks)
+}
+
+///创建有截止时间的任务
+#[no_mangle]
+pub extern "C" fn task_crate_with_deadline(
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+    deadline: u64,
+    miss_policy: DeadlineMissPolicy,
+) -> JoinHandle {
+    checked(EventLoops::submit_task_with_deadline(
+        None,
+        task_main(f, param, free),
+        Some(param),
+        deadline,
+        miss_policy,
+    ))
+}
+
+///创建带选项的任务，`options`为空时使用默认选项，失败时返回无效的句柄并设置errno
+#[no_mangle]
+pub extern "C" fn task_crate_with_options(
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+    options: *const RawTaskOptions,
+) -> JoinHandle {
+    let options = unsafe { options.as_ref() }
+        .map(|options| unsafe { TaskOptions::from_raw(options) })
+        .unwrap_or_default();
+    checked(EventLoops::submit_with_options(
+        task_main(f, param, free),
+        Some(param),
+        options,
+    ))
+}
+
+///创建有最大运行时间的任务
+#[no_mangle]
+pub extern "C" fn task_crate_with_timeout(
+    f: UserTaskFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+    priority: c_longlong,
+    timeout: u64,
+    from: TimeoutFrom,
+) -> JoinHandle {
+    checked(EventLoops::submit_task_with_timeout(
+        None,
+        task_main(f, param, free),
+        Some(param),
+        Some(priority),
+        timeout,
+        from,
+    ))
+}
+
+///添加任务监听器
+#[no_mangle]
+pub extern "C" fn open_coroutine_add_task_listener(listener: RawTaskListener) {
+    let listener: Arc<dyn TaskListener> = Arc::new(CTaskListener(listener));
+    EventLoops::add_task_listener(&listener);
 }
 
 ///尝试异步取消任务
 #[no_mangle]
 pub extern "C" fn task_cancel(handle: &JoinHandle) -> c_longlong {
-    match handle.id() {
-        Ok(task_id) => {
-            EventLoops::try_cancel_task(task_id);
-            0
-        }
+    match handle.try_cancel() {
+        Ok(()) => 0,
         Err(_) => -1,
     }
 }
 
+///尝试修改任务优先级
+#[no_mangle]
+pub extern "C" fn task_set_priority(handle: &JoinHandle, priority: c_longlong) -> c_longlong {
+    match handle.try_set_priority(Some(priority)) {
+        Ok(true) => 0,
+        Ok(false) | Err(_) => -1,
+    }
+}
+
 ///等待任务完成
 #[no_mangle]
 pub extern "C" fn task_join(handle: &JoinHandle) -> c_longlong {
@@ -124,6 +550,7 @@ pub extern "C" fn task_join(handle: &JoinHandle) -> c_longlong {
                 Some(ptr) => c_longlong::try_from(ptr).expect("overflow"),
                 None => 0,
             },
+            Err(TIMEOUT_ERROR) => -2,
             Err(_) => -1,
         },
         Err(_) => -1,
@@ -139,12 +566,116 @@ pub extern "C" fn task_timeout_join(handle: &JoinHandle, ns_time: u64) -> c_long
                 Some(ptr) => c_longlong::try_from(ptr).expect("overflow"),
                 None => 0,
             },
+            Err(TIMEOUT_ERROR) => -2,
             Err(_) => -1,
         },
         Err(_) => -1,
     }
 }
 
+///创建任务图，提交前用`open_coroutine_graph_free`释放
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_create(policy: FailurePolicy) -> *mut c_void {
+    Box::into_raw(Box::new(TaskGraph::new(policy))).cast()
+}
+
+///向任务图添加依赖`deps`的节点，成功时返回节点下标，失败时返回-1并设置errno，
+///节点未添加或未运行就被丢弃时用`free`释放`param`
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_add_node(
+    graph: *mut c_void,
+    name: *const c_char,
+    deps: *const usize,
+    len: usize,
+    f: RawNodeFunc,
+    param: usize,
+    free: Option<UserTaskFree>,
+) -> isize {
+    let func = node_main(f, param, free);
+    let (Some(graph), Some(name)) = (unsafe { graph.cast::<TaskGraph>().as_mut() }, to_str(name))
+    else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    let deps = if deps.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(deps, len) }
+    };
+    match graph.add_node(name, deps, func) {
+        Ok(index) => isize::try_from(index).expect("overflow"),
+        Err(e) => {
+            set_errno(e.kind());
+            -1
+        }
+    }
+}
+
+///释放未提交的任务图
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_free(graph: *mut c_void) {
+    if !graph.is_null() {
+        drop(unsafe { Box::from_raw(graph.cast::<TaskGraph>()) });
+    }
+}
+
+///提交任务图，之后不能再使用`graph`，返回的句柄用`open_coroutine_graph_handle_free`释放
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_submit(graph: *mut c_void) -> *mut c_void {
+    if graph.is_null() {
+        set_errno(ErrorKind::InvalidInput);
+        return std::ptr::null_mut();
+    }
+    let graph = unsafe { Box::from_raw(graph.cast::<TaskGraph>()) };
+    Box::into_raw(Box::new(graph.submit())).cast()
+}
+
+///等待任务图的所有节点完成，`timeout_time`为0时不等待，超时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_join(handle: *const c_void, timeout_time: u64) -> c_int {
+    let Some(handle) = (unsafe { handle.cast::<TaskGraphHandle>().as_ref() }) else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    match handle.timeout_at_join(timeout_time) {
+        Ok(_) => 0,
+        Err(e) => {
+            set_errno(e.kind());
+            -1
+        }
+    }
+}
+
+///获取任务图中节点的状态和耗时，节点不存在时返回-1并设置errno
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_node(
+    handle: *const c_void,
+    index: usize,
+    report: *mut RawNodeReport,
+) -> c_int {
+    let (Some(handle), Some(report)) = (
+        unsafe { handle.cast::<TaskGraphHandle>().as_ref() },
+        unsafe { report.as_mut() },
+    ) else {
+        set_errno(ErrorKind::InvalidInput);
+        return -1;
+    };
+    let Some(node) = handle.node_report(index) else {
+        set_errno(ErrorKind::NotFound);
+        return -1;
+    };
+    node.write_raw(report);
+    0
+}
+
+///释放任务图的句柄，未完成的节点继续运行
+#[no_mangle]
+pub extern "C" fn open_coroutine_graph_handle_free(handle: *mut c_void) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle.cast::<TaskGraphHandle>()) });
+    }
+}
+
 ///如果当前协程栈不够，切换到新栈上执行
 #[no_mangle]
 pub extern "C" fn maybe_grow_stack(
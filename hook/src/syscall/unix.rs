@@ -1,6 +1,6 @@
 use libc::{
-    fd_set, iovec, mode_t, msghdr, off_t, pthread_cond_t, pthread_mutex_t, size_t, sockaddr,
-    socklen_t, ssize_t, timespec, timeval,
+    fd_set, iovec, mode_t, msghdr, nfds_t, off_t, pid_t, pollfd, pthread_cond_t, pthread_mutex_t,
+    sigset_t, size_t, sockaddr, socklen_t, ssize_t, timespec, timeval,
 };
 use std::ffi::{c_char, c_int, c_uint, c_void};
 
@@ -12,9 +12,9 @@ macro_rules! impl_hook {
         pub extern "C" fn $syscall(
             $($arg: $arg_type),*
         ) -> $result {
-            static $field_name: once_cell::sync::Lazy<
+            static $field_name: std::sync::LazyLock<
                 extern "C" fn($($arg_type, )*) -> $result,
-            > = once_cell::sync::Lazy::new(|| unsafe {
+            > = std::sync::LazyLock::new(|| unsafe {
                 let syscall: &str = open_coroutine_core::common::constants::SyscallName::$syscall.into();
                 let symbol = std::ffi::CString::new(String::from(syscall))
                     .unwrap_or_else(|_| panic!("can not transfer \"{syscall}\" to CString"));
@@ -22,7 +22,7 @@ macro_rules! impl_hook {
                 assert!(!ptr.is_null(), "syscall \"{syscall}\" not found !");
                 std::mem::transmute(ptr)
             });
-            let fn_ptr = once_cell::sync::Lazy::force(&$field_name);
+            let fn_ptr = std::sync::LazyLock::force(&$field_name);
             if $crate::hook()
                 || open_coroutine_core::scheduler::SchedulableCoroutine::current().is_some()
                 || cfg!(feature = "ci")
@@ -34,11 +34,51 @@ macro_rules! impl_hook {
     }
 }
 
+// Like `impl_hook`, but only the calls issued by coroutines are hooked, and the calls issued
+// by the selector of `EventLoop` itself always reach the kernel directly.
+#[allow(unused_macros)]
+macro_rules! impl_co_hook {
+    ( $field_name: ident, $syscall: ident($($arg: ident : $arg_type: ty),*) -> $result: ty ) => {
+        #[no_mangle]
+        pub extern "C" fn $syscall(
+            $($arg: $arg_type),*
+        ) -> $result {
+            static $field_name: std::sync::LazyLock<
+                extern "C" fn($($arg_type, )*) -> $result,
+            > = std::sync::LazyLock::new(|| unsafe {
+                let syscall: &str = open_coroutine_core::common::constants::SyscallName::$syscall.into();
+                let symbol = std::ffi::CString::new(String::from(syscall))
+                    .unwrap_or_else(|_| panic!("can not transfer \"{syscall}\" to CString"));
+                let ptr = libc::dlsym(libc::RTLD_NEXT, symbol.as_ptr());
+                assert!(!ptr.is_null(), "syscall \"{syscall}\" not found !");
+                std::mem::transmute(ptr)
+            });
+            let fn_ptr = std::sync::LazyLock::force(&$field_name);
+            if !open_coroutine_core::net::EventLoops::in_selector()
+                && open_coroutine_core::scheduler::SchedulableCoroutine::current().is_some()
+            {
+                return open_coroutine_core::syscall::$syscall(Some(fn_ptr), $($arg, )*);
+            }
+            (fn_ptr)($($arg),*)
+        }
+    }
+}
+
 // The following are supported syscall
 impl_hook!(SLEEP, sleep(secs: c_uint) -> c_uint);
 impl_hook!(USLEEP, usleep(microseconds: c_uint) -> c_int);
 impl_hook!(NANOSLEEP, nanosleep(rqtp: *const timespec, rmtp: *mut timespec) -> c_int);
+#[cfg(target_os = "linux")]
+impl_hook!(CLOCK_NANOSLEEP, clock_nanosleep(clockid: libc::clockid_t, flags: c_int, rqtp: *const timespec, rmtp: *mut timespec) -> c_int);
+impl_co_hook!(SCHED_YIELD, sched_yield() -> c_int);
+impl_co_hook!(PAUSE, pause() -> c_int);
 impl_hook!(SELECT, select(nfds: c_int, readfds: *mut fd_set, writefds: *mut fd_set, errorfds: *mut fd_set, timeout: *mut timeval) -> c_int);
+impl_co_hook!(POLL, poll(fds: *mut pollfd, nfds: nfds_t, timeout: c_int) -> c_int);
+#[cfg(target_os = "linux")]
+impl_co_hook!(PPOLL, ppoll(fds: *mut pollfd, nfds: nfds_t, timeout: *const timespec, sigmask: *const sigset_t) -> c_int);
+impl_co_hook!(PSELECT, pselect(nfds: c_int, readfds: *mut fd_set, writefds: *mut fd_set, errorfds: *mut fd_set, timeout: *const timespec, sigmask: *const sigset_t) -> c_int);
+#[cfg(target_os = "linux")]
+impl_co_hook!(EPOLL_WAIT, epoll_wait(epfd: c_int, events: *mut libc::epoll_event, maxevents: c_int, timeout: c_int) -> c_int);
 impl_hook!(SOCKET, socket(domain: c_int, type_: c_int, protocol: c_int) -> c_int);
 impl_hook!(SETSOCKOPT, setsockopt(socket: c_int, level: c_int, name: c_int, value: *const c_void, option_len: socklen_t) -> c_int);
 impl_hook!(CONNECT, connect(fd: c_int, address: *const sockaddr, len: socklen_t) -> c_int);
@@ -69,6 +109,11 @@ impl_hook!(PWRITEV, pwritev(fd: c_int, iov: *const iovec, iovcnt: c_int, offset:
 impl_hook!(SENDMSG, sendmsg(fd: c_int, msg: *const msghdr, flags: c_int) -> ssize_t);
 impl_hook!(PTHREAD_COND_TIMEDWAIT, pthread_cond_timedwait(cond: *mut pthread_cond_t, lock: *mut pthread_mutex_t, abstime: *const timespec) -> c_int);
 impl_hook!(PTHREAD_MUTEX_TRYLOCK, pthread_mutex_trylock(lock: *mut pthread_mutex_t) -> c_int);
+impl_co_hook!(WAITPID, waitpid(pid: pid_t, status: *mut c_int, options: c_int) -> pid_t);
+#[cfg(target_os = "linux")]
+impl_co_hook!(WAIT4, wait4(pid: pid_t, status: *mut c_int, options: c_int, rusage: *mut libc::rusage) -> pid_t);
+#[cfg(target_os = "linux")]
+impl_co_hook!(WAITID, waitid(idtype: libc::idtype_t, id: libc::id_t, infop: *mut libc::siginfo_t, options: c_int) -> c_int);
 impl_hook!(MKDIR, mkdir(path: *const c_char, mode: mode_t) -> c_int);
 impl_hook!(RMDIR, rmdir(path: *const c_char) -> c_int);
 impl_hook!(LSEEK, lseek(fd: c_int, offset: off_t, whence: c_int) -> off_t);
@@ -80,9 +125,6 @@ impl_hook!(RENAMEAT, renameat(olddirfd: c_int, oldpath: *const c_char, newdirfd:
 #[cfg(target_os = "linux")]
 impl_hook!(RENAMEAT2, renameat2(olddirfd: c_int, oldpath: *const c_char, newdirfd: c_int, newpath: *const c_char, flags: c_uint) -> c_int);
 
-// NOTE: unhook poll due to mio's poller
-// impl_hook!(POLL, poll(fds: *mut pollfd, nfds: nfds_t, timeout: c_int) -> c_int);
-
 // NOTE: unhook pthread_mutex_lock/pthread_mutex_unlock due to stack overflow or bug
 // impl_hook!(PTHREAD_MUTEX_LOCK, pthread_mutex_lock(lock: *mut pthread_mutex_t) -> c_int);
 // impl_hook!(PTHREAD_MUTEX_UNLOCK, pthread_mutex_unlock(lock: *mut pthread_mutex_t) -> c_int);
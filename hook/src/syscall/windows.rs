@@ -19,8 +19,8 @@ use windows_sys::Win32::System::IO::OVERLAPPED;
 #[allow(unused_macros)]
 macro_rules! impl_hook {
     ( $module_name: expr, $field_name: ident, $syscall: ident($($arg: ident: $arg_type: ty),*) -> $result: ty ) => {
-        static $field_name: once_cell::sync::OnceCell<extern "system" fn($($arg_type),*) -> $result> =
-            once_cell::sync::OnceCell::new();
+        static $field_name: std::sync::OnceLock<extern "system" fn($($arg_type),*) -> $result> =
+            std::sync::OnceLock::new();
         _ = $field_name.get_or_init(|| unsafe {
             let syscall: &str = open_coroutine_core::common::constants::SyscallName::$syscall.into();
             let ptr = minhook::MinHook::create_hook_api($module_name, syscall, $syscall as _)
@@ -49,7 +49,206 @@
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemFn, LitBool, LitInt};
+use syn::{parse_macro_input, Ident, ItemFn, LitBool, LitInt, LitStr};
+
+/// Map the `scheduling_policy` argument to the variant of `SchedulingPolicyKind`.
+fn scheduling_policy_kind(value: &LitStr) -> syn::Result<Ident> {
+    let kind = match value.value().as_str() {
+        "fifo" => "Fifo",
+        "priority" => "Priority",
+        "fair_share" => "FairShare",
+        "weighted_round_robin" => "WeightedRoundRobin",
+        other => {
+            return Err(syn::Error::new(
+                value.span(),
+                format!("unknown scheduling_policy {other}"),
+            ))
+        }
+    };
+    Ok(Ident::new(kind, value.span()))
+}
+
+/// Map the `pin_policy` argument to the variant of `PinPolicy`.
+fn pin_policy(value: &LitStr) -> syn::Result<Ident> {
+    let policy = match value.value().as_str() {
+        "none" => "None",
+        "compact" => "Compact",
+        "scatter" => "Scatter",
+        other => {
+            return Err(syn::Error::new(
+                value.span(),
+                format!("unknown pin_policy {other}"),
+            ))
+        }
+    };
+    Ok(Ident::new(policy, value.span()))
+}
+
+/// Map the `rejection_policy` argument to the variant of `RejectionPolicy`.
+fn rejection_policy(value: &LitStr) -> syn::Result<Ident> {
+    let policy = match value.value().as_str() {
+        "abort" => "Abort",
+        "caller_runs" => "CallerRuns",
+        "discard_oldest" => "DiscardOldest",
+        "block" => "Block",
+        other => {
+            return Err(syn::Error::new(
+                value.span(),
+                format!("unknown rejection_policy {other}"),
+            ))
+        }
+    };
+    Ok(Ident::new(policy, value.span()))
+}
+
+/// The arguments of the scheduling, a sentinel means the argument is not set.
+#[derive(Debug)]
+struct SchedulingArgs {
+    timer_tick: u64,
+    scheduling_policy: Option<Ident>,
+    coroutine_queue_aging: u64,
+    task_queue_aging: u64,
+    queue_stats: bool,
+    coroutine_migration: bool,
+    local_queue_size: usize,
+    local_queue_capacity: usize,
+    steal_batch: usize,
+    global_queue_interval: u32,
+    work_stealing: bool,
+    pin_policy: Option<Ident>,
+    task_queue_capacity: usize,
+    rejection_policy: Option<Ident>,
+}
+
+impl Default for SchedulingArgs {
+    fn default() -> Self {
+        SchedulingArgs {
+            timer_tick: u64::MAX,
+            scheduling_policy: None,
+            coroutine_queue_aging: u64::MAX,
+            task_queue_aging: u64::MAX,
+            queue_stats: false,
+            coroutine_migration: false,
+            local_queue_size: usize::MAX,
+            local_queue_capacity: usize::MAX,
+            steal_batch: usize::MAX,
+            global_queue_interval: u32::MAX,
+            work_stealing: true,
+            pin_policy: None,
+            task_queue_capacity: usize::MAX,
+            rejection_policy: None,
+        }
+    }
+}
+
+impl SchedulingArgs {
+    /// Parse the argument, ignore the unknown arguments.
+    fn parse(&mut self, meta: &syn::meta::ParseNestedMeta<'_>) -> syn::Result<()> {
+        if meta.path.is_ident("timer_tick") {
+            self.timer_tick = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("coroutine_queue_aging") {
+            self.coroutine_queue_aging = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("task_queue_aging") {
+            self.task_queue_aging = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("queue_stats") {
+            self.queue_stats = meta.value()?.parse::<LitBool>()?.value();
+        } else if meta.path.is_ident("coroutine_migration") {
+            self.coroutine_migration = meta.value()?.parse::<LitBool>()?.value();
+        } else if meta.path.is_ident("local_queue_size") {
+            self.local_queue_size = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("local_queue_capacity") {
+            self.local_queue_capacity = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("steal_batch") {
+            self.steal_batch = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("global_queue_interval") {
+            self.global_queue_interval = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("work_stealing") {
+            self.work_stealing = meta.value()?.parse::<LitBool>()?.value();
+        } else if meta.path.is_ident("scheduling_policy") {
+            self.scheduling_policy = Some(scheduling_policy_kind(&meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("pin_policy") {
+            self.pin_policy = Some(pin_policy(&meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("task_queue_capacity") {
+            self.task_queue_capacity = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        } else if meta.path.is_ident("rejection_policy") {
+            self.rejection_policy = Some(rejection_policy(&meta.value()?.parse()?)?);
+        }
+        Ok(())
+    }
+
+    /// Generate the statements to set the config.
+    fn setters(&self) -> impl quote::ToTokens {
+        let SchedulingArgs {
+            timer_tick,
+            scheduling_policy,
+            coroutine_queue_aging,
+            task_queue_aging,
+            queue_stats,
+            coroutine_migration,
+            local_queue_size,
+            local_queue_capacity,
+            steal_batch,
+            global_queue_interval,
+            work_stealing,
+            pin_policy,
+            task_queue_capacity,
+            rejection_policy,
+        } = self;
+        let scheduling_policy = scheduling_policy.as_ref().map(|kind| {
+            quote! {
+                open_coroutine_config.set_scheduling_policy(open_coroutine::SchedulingPolicyKind::#kind);
+            }
+        });
+        let pin_policy = pin_policy.as_ref().map(|policy| {
+            quote! {
+                open_coroutine_config.set_pin_policy(open_coroutine::PinPolicy::#policy);
+            }
+        });
+        let rejection_policy = rejection_policy.as_ref().map(|policy| {
+            quote! {
+                open_coroutine_config.set_rejection_policy(open_coroutine::RejectionPolicy::#policy);
+            }
+        });
+        quote! {
+            if #timer_tick != u64::MAX {
+                open_coroutine_config.set_timer_tick(#timer_tick);
+            }
+            #scheduling_policy
+            if #coroutine_queue_aging != u64::MAX {
+                open_coroutine_config.set_coroutine_queue_aging(#coroutine_queue_aging);
+            }
+            if #task_queue_aging != u64::MAX {
+                open_coroutine_config.set_task_queue_aging(#task_queue_aging);
+            }
+            if #queue_stats {
+                open_coroutine_config.set_queue_stats(true);
+            }
+            if #coroutine_migration {
+                open_coroutine_config.set_coroutine_migration(true);
+            }
+            if #local_queue_size != usize::MAX {
+                open_coroutine_config.set_local_queue_size(#local_queue_size);
+            }
+            if #local_queue_capacity != usize::MAX {
+                open_coroutine_config.set_local_queue_capacity(#local_queue_capacity);
+            }
+            if #steal_batch != usize::MAX {
+                open_coroutine_config.set_steal_batch(#steal_batch);
+            }
+            if #global_queue_interval != u32::MAX {
+                open_coroutine_config.set_global_queue_interval(#global_queue_interval);
+            }
+            if !#work_stealing {
+                open_coroutine_config.set_work_stealing(false);
+            }
+            #pin_policy
+            if #task_queue_capacity != usize::MAX {
+                open_coroutine_config.set_task_queue_capacity(#task_queue_capacity);
+            }
+            #rejection_policy
+        }
+    }
+}
 
 /// use this macro like `#[open_coroutine::main(event_loop_size = 2, max_size = 2, keep_alive_time = 0)]`.
 #[proc_macro_attribute]
@@ -62,6 +261,7 @@ pub fn main(args: TokenStream, func: TokenStream) -> TokenStream {
     let mut min_memory_count = usize::MAX;
     let mut memory_keep_alive_time = u64::MAX;
     let mut hook = true;
+    let mut scheduling = SchedulingArgs::default();
     if !args.is_empty() {
         let tea_parser = syn::meta::parser(|meta| {
             if meta.path.is_ident("event_loop_size") {
@@ -80,6 +280,8 @@ pub fn main(args: TokenStream, func: TokenStream) -> TokenStream {
                 memory_keep_alive_time = meta.value()?.parse::<LitInt>()?.base10_parse()?;
             } else if meta.path.is_ident("hook") {
                 hook = meta.value()?.parse::<LitBool>()?.value();
+            } else {
+                scheduling.parse(&meta)?;
             }
             Ok(())
         });
@@ -87,6 +289,7 @@ pub fn main(args: TokenStream, func: TokenStream) -> TokenStream {
     }
 
     let func = parse_macro_input!(func as ItemFn);
+    let func_attrs = &func.attrs; // like #[allow(..)]
     let func_vis = &func.vis; // like pub
     let func_block = &func.block; // { some statement or expression here }
 
@@ -96,8 +299,10 @@ pub fn main(args: TokenStream, func: TokenStream) -> TokenStream {
     let func_inputs = &func_decl.inputs;
     let func_output = &func_decl.output;
 
+    let scheduling = scheduling.setters();
     let caller = quote! {
         // rebuild the function, add a func named is_expired to check user login session expire or not.
+        #(#func_attrs)*
         #func_vis fn #func_name #func_generics(#func_inputs) #func_output {
             let mut open_coroutine_config = open_coroutine::Config::default();
             if #event_loop_size != usize::MAX {
@@ -124,6 +329,7 @@ pub fn main(args: TokenStream, func: TokenStream) -> TokenStream {
             if #hook != true {
                 open_coroutine_config.set_hook(#hook);
             }
+            #scheduling
             open_coroutine::init(open_coroutine_config);
             let _open_coroutine_result = #func_block;
             open_coroutine::shutdown();
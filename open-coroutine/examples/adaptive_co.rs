@@ -0,0 +1,34 @@
+use open_coroutine::{AdaptiveConfig, Config};
+use std::time::Duration;
+
+#[open_coroutine::main(event_loop_size = 1)]
+pub fn main() {
+    let mut adaptive = AdaptiveConfig::default();
+    _ = adaptive
+        .set_interval(10_000_000)
+        .set_coroutine_bounds(4, 64)
+        .set_event_loop_bounds(1, 2);
+    let mut config = Config::default();
+    _ = config.set_event_loop_size(2).set_adaptive(adaptive);
+    let pool = open_coroutine::create_pool("adaptive", config).expect("create pool failed");
+    // the idle pool shrinks to the min event-loops
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(1, pool.stats().active_event_loops);
+    let handles: Vec<_> = (0..32)
+        .map(|i| {
+            pool.crate_task(
+                |i| {
+                    std::thread::sleep(Duration::from_millis(10));
+                    i
+                },
+                i,
+                0,
+            )
+            .expect("submit to pool failed")
+        })
+        .collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(Some(i), handle.join().expect("task failed"));
+    }
+    pool.shutdown();
+}
@@ -0,0 +1,55 @@
+use open_coroutine::task;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 1, task_queue_aging = 1_000_000)]
+pub fn main() {
+    // occupy the only worker first, so the low priority task is queued behind the high ones
+    let started = Arc::new(AtomicBool::new(false));
+    let _blocker = task!(
+        |started: Arc<AtomicBool>| {
+            started.store(true, Ordering::Release);
+            busy(Duration::from_millis(10));
+        },
+        started.clone(),
+        -1
+    );
+    let start = Instant::now();
+    while !started.load(Ordering::Acquire) && start.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let low = Arc::new(AtomicBool::new(false));
+    let _low = task!(
+        |low: Arc<AtomicBool>| low.store(true, Ordering::Release),
+        low.clone(),
+        100
+    );
+    // keep the only worker busy with high priority tasks
+    let start = Instant::now();
+    while !low.load(Ordering::Acquire) && start.elapsed() < Duration::from_secs(5) {
+        _ = task!(|()| busy(Duration::from_millis(2)), (), -1);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    // the low priority task waited about 100ms to catch up with the high priority tasks
+    assert!(low.load(Ordering::Acquire), "the low priority task starved");
+    let stats = open_coroutine::stats();
+    let band = stats
+        .task_bands
+        .iter()
+        .find(|band| band.priority == 100)
+        .expect("no band of priority 100");
+    assert!(band.max_wait >= 50_000_000, "{stats:?}");
+    assert!(stats.max_wait() >= Duration::from_nanos(band.max_wait));
+}
+
+// spin in short slices and yield between them, so the worker is never preempted while it
+// holds the locks of the runtime
+fn busy(dur: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < dur {
+        let slice = Instant::now();
+        while slice.elapsed() < Duration::from_millis(1) {}
+        std::thread::yield_now();
+    }
+}
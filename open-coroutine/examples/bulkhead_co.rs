@@ -0,0 +1,44 @@
+use open_coroutine::{task, Config};
+use std::time::{Duration, Instant};
+
+#[open_coroutine::main(event_loop_size = 1)]
+pub fn main() {
+    let mut config = Config::default();
+    _ = config
+        .set_event_loop_size(1)
+        .set_min_size(0)
+        .set_max_size(1);
+    let slow = open_coroutine::create_pool("slow", config).expect("create pool failed");
+    assert!(
+        open_coroutine::create_pool("slow", config).is_err(),
+        "the pool name can't be reused"
+    );
+    // the slow dependency occupies its own pool only
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            slow.crate_task(
+                |i| {
+                    std::thread::sleep(Duration::from_millis(200));
+                    i
+                },
+                i,
+                0,
+            )
+            .expect("submit to pool failed")
+        })
+        .collect();
+    let start = Instant::now();
+    assert_eq!(
+        Some(1),
+        task!(|i| i, 1, 0).join().expect("fast task failed")
+    );
+    assert!(
+        start.elapsed() < Duration::from_millis(200),
+        "the fast task should not wait for the slow pool"
+    );
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(Some(i), handle.join().expect("slow task failed"));
+    }
+    assert_eq!(1, slow.stats().loads.len());
+    slow.shutdown();
+}
@@ -0,0 +1,64 @@
+use open_coroutine::task;
+use std::io::{Error, Result};
+use std::time::{Duration, Instant};
+
+fn monotonic_now() -> libc::timespec {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    assert_eq!(0, unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &raw mut now)
+    });
+    now
+}
+
+fn sleep_millis(millis: libc::c_long, absolute: bool) -> libc::c_int {
+    let mut request = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: millis * 1_000_000,
+    };
+    let mut flags = 0;
+    if absolute {
+        let now = monotonic_now();
+        request.tv_sec = now.tv_sec;
+        request.tv_nsec += now.tv_nsec;
+        if request.tv_nsec >= 1_000_000_000 {
+            request.tv_sec += 1;
+            request.tv_nsec -= 1_000_000_000;
+        }
+        flags = libc::TIMER_ABSTIME;
+    }
+    unsafe {
+        libc::clock_nanosleep(
+            libc::CLOCK_MONOTONIC,
+            flags,
+            &raw const request,
+            std::ptr::null_mut(),
+        )
+    }
+}
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() -> Result<()> {
+    let start = Instant::now();
+    // both sleeps are parked on the same thread at the same time
+    let relative = task!(|_| sleep_millis(300, false), ());
+    let absolute = task!(|_| sleep_millis(300, true), ());
+    for handle in [relative, absolute] {
+        let r = handle
+            .timeout_join(Duration::from_secs(5))?
+            .ok_or_else(|| Error::other("join failed"))?;
+        assert_eq!(0, r);
+    }
+    let cost = start.elapsed();
+    assert!(cost >= Duration::from_millis(300), "{cost:?}");
+    assert!(cost < Duration::from_millis(600), "{cost:?}");
+    // invalid request returns the error number
+    let invalid = task!(|_| sleep_millis(1000, false), ());
+    assert_eq!(
+        Some(libc::EINVAL),
+        invalid.timeout_join(Duration::from_secs(5))?
+    );
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use open_coroutine::dag::{FailurePolicy, NodeStatus, TaskGraph};
+use std::time::Duration;
+
+#[open_coroutine::main(event_loop_size = 2, max_size = 2)]
+pub fn main() -> std::io::Result<()> {
+    let mut graph = TaskGraph::new(FailurePolicy::ContinueIndependent);
+    let a = graph.add_node("a", &[], |_| Ok(Some(1)))?;
+    let b = graph.add_node("b", &[a], |inputs| Ok(inputs[0].map(|v| v + 1)))?;
+    let c = graph.add_node("c", &[a], |inputs| {
+        std::thread::sleep(Duration::from_millis(10));
+        Ok(inputs[0].map(|v| v * 10))
+    })?;
+    let d = graph.add_node("d", &[b, c], |inputs| {
+        Ok(Some(inputs.into_iter().flatten().sum()))
+    })?;
+    let failed = graph.add_node("failed", &[a], |_| Err("test error, just ignore it"))?;
+    let dependent = graph.add_node("dependent", &[failed], |_| Ok(None))?;
+    assert!(graph.add_node("e", &[d + 10], |_| Ok(None)).is_err());
+    assert!(graph.add_node("nul\0", &[], |_| Ok(None)).is_err());
+    assert_eq!(6, graph.len());
+    let report = graph.submit().timeout_join(Duration::from_secs(5))?;
+    assert!(!report.is_success());
+    assert_eq!("d", report.nodes[d].name);
+    assert_eq!(NodeStatus::Completed, report.nodes[d].status);
+    assert_eq!(Some(Ok(Some(12))), report.nodes[d].result);
+    assert!(report.nodes[c]
+        .elapsed()
+        .is_some_and(|elapsed| elapsed >= Duration::from_millis(10)));
+    assert_eq!(NodeStatus::Failed, report.nodes[failed].status);
+    assert_eq!(
+        Some(Err("test error, just ignore it")),
+        report.nodes[failed].result
+    );
+    assert_eq!(NodeStatus::Cancelled, report.nodes[dependent].status);
+    Ok(())
+}
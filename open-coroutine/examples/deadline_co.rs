@@ -0,0 +1,67 @@
+use open_coroutine::{crate_task_with_deadline, task, DeadlineMissPolicy, TaskListener};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct MissCounter(Arc<AtomicU64>);
+
+impl TaskListener for MissCounter {
+    fn on_deadline_missed(&self, _: u64, _: &str, _: u64, started: bool) {
+        if !started {
+            _ = self.0.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 1)]
+pub fn main() {
+    let missed = Arc::new(AtomicU64::new(0));
+    open_coroutine::add_task_listener(MissCounter(missed.clone()));
+    // keep the only worker busy until all tasks are submitted
+    let started = Arc::new(AtomicBool::new(false));
+    let release = Arc::new(AtomicBool::new(false));
+    let blocker = task!(
+        |(started, release): (Arc<AtomicBool>, Arc<AtomicBool>)| {
+            started.store(true, Ordering::Release);
+            while !release.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        },
+        (started.clone(), release.clone()),
+        0
+    );
+    while !started.load(Ordering::Acquire) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let record = |order: Arc<Mutex<Vec<u32>>>, id: u32| move |()| order.lock().unwrap().push(id);
+    let now = Instant::now();
+    let normal = task!(record(order.clone(), 4), (), -100);
+    let mut handles = Vec::new();
+    for id in (1..=3).rev() {
+        handles.push(crate_task_with_deadline(
+            record(order.clone(), id),
+            (),
+            now + Duration::from_secs(u64::from(id)),
+            DeadlineMissPolicy::Drop,
+        ));
+    }
+    // already missed
+    let late = crate_task_with_deadline(record(order.clone(), 0), (), now, DeadlineMissPolicy::Run);
+    let dropped =
+        crate_task_with_deadline(record(order.clone(), 5), (), now, DeadlineMissPolicy::Drop);
+    std::thread::sleep(Duration::from_millis(10));
+    release.store(true, Ordering::Release);
+    assert_eq!(Some(()), blocker.join().expect("blocker failed"));
+    assert_eq!(Some(()), late.join().expect("late task failed"));
+    assert!(dropped.join().is_err(), "the missed task should be dropped");
+    for handle in handles {
+        assert_eq!(Some(()), handle.join().expect("deadline task failed"));
+    }
+    assert_eq!(Some(()), normal.join().expect("normal task failed"));
+    // earliest deadline first, and before the tasks without deadline
+    assert_eq!(vec![0, 1, 2, 3, 4], *order.lock().unwrap());
+    assert_eq!(2, missed.load(Ordering::Acquire));
+    assert!(open_coroutine::stats().deadline_misses >= 2);
+}
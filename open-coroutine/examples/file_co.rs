@@ -3,6 +3,7 @@ use std::fs::File;
 use std::io::{Error, IoSlice, IoSliceMut, Read, Result, Seek, SeekFrom, Write};
 use std::time::Duration;
 
+#[allow(clippy::unnecessary_mut_passed)]
 #[open_coroutine::main(event_loop_size = 1, max_size = 1)]
 pub fn main() -> Result<()> {
     let join_handle: JoinHandle<Result<()>> = task!(
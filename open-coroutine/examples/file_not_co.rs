@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io::{IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write};
 
+#[allow(clippy::unnecessary_mut_passed)]
 #[open_coroutine::main(event_loop_size = 1, max_size = 1)]
 pub fn main() -> std::io::Result<()> {
     const HELLO: &str = "Hello World!";
@@ -0,0 +1,57 @@
+use open_coroutine::task;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SLEEPERS: usize = 8;
+
+#[cfg(unix)]
+#[open_coroutine::main(event_loop_size = 2, max_size = 16, coroutine_migration = true)]
+pub fn main() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let finished = Arc::new(AtomicUsize::new(0));
+    for _ in 0..SLEEPERS {
+        _ = task!(
+            |(started, finished): (Arc<AtomicUsize>, Arc<AtomicUsize>)| {
+                _ = started.fetch_add(1, Ordering::Release);
+                std::thread::sleep(Duration::from_millis(200));
+                _ = finished.fetch_add(1, Ordering::Release);
+            },
+            (started.clone(), finished.clone()),
+        );
+    }
+    while started.load(Ordering::Acquire) < SLEEPERS {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    // keep one event-loop busy, the sleepers waiting in it are taken by the other one
+    let hog = task!(
+        |finished: Arc<AtomicUsize>| {
+            let start = Instant::now();
+            while finished.load(Ordering::Acquire) < SLEEPERS
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                std::hint::spin_loop();
+            }
+            start.elapsed()
+        },
+        finished.clone(),
+    );
+    let elapsed = hog.join().expect("hog failed").expect("no result");
+    assert_eq!(SLEEPERS, finished.load(Ordering::Acquire));
+    assert!(elapsed < Duration::from_secs(5), "the sleepers are stuck");
+    let stats = open_coroutine::stats();
+    assert_eq!(2, stats.loads.len());
+    let migrated_in = stats.loads.iter().map(|load| load.migrated_in).sum::<u64>();
+    assert!(migrated_in > 0, "no sleeper was migrated");
+    assert_eq!(
+        migrated_in,
+        stats
+            .loads
+            .iter()
+            .map(|load| load.migrated_out)
+            .sum::<u64>(),
+    );
+}
+
+#[cfg(not(unix))]
+pub fn main() {}
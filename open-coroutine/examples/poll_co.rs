@@ -0,0 +1,132 @@
+use open_coroutine::task;
+use std::ffi::c_int;
+use std::io::{Error, Result};
+use std::time::{Duration, Instant};
+
+fn pipe() -> Result<[c_int; 2]> {
+    let mut fds: [c_int; 2] = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+        return Err(Error::last_os_error());
+    }
+    Ok(fds)
+}
+
+fn close(fds: [c_int; 2]) {
+    for fd in fds {
+        _ = unsafe { libc::close(fd) };
+    }
+}
+
+/// The waiter parks while the writer, which runs on the same thread, makes the pipe readable.
+fn wait_pipe(name: &'static str, wait: fn(c_int, c_int) -> c_int) -> Result<()> {
+    let fds = pipe()?;
+    let waiter = task!(
+        move |_| {
+            let start = Instant::now();
+            let r = wait(fds[0], 3000);
+            println!("[{name}] ready:{r} cost:{:?}", start.elapsed());
+            assert!(
+                start.elapsed() < Duration::from_secs(3),
+                "{name} blocked the thread"
+            );
+            r
+        },
+        ()
+    );
+    let writer = task!(
+        move |_| {
+            std::thread::sleep(Duration::from_millis(100));
+            assert_eq!(1, unsafe { libc::write(fds[1], b"x".as_ptr().cast(), 1) });
+        },
+        ()
+    );
+    assert_eq!(Some(()), writer.timeout_join(Duration::from_secs(10))?);
+    assert_eq!(Some(1), waiter.timeout_join(Duration::from_secs(10))?);
+    close(fds);
+    Ok(())
+}
+
+fn poll(fd: c_int, timeout: c_int) -> c_int {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&raw mut pfd, 1, timeout) }
+}
+
+fn pselect(fd: c_int, timeout: c_int) -> c_int {
+    let timeout = libc::timespec {
+        tv_sec: libc::time_t::from(timeout / 1000),
+        tv_nsec: 0,
+    };
+    unsafe {
+        let mut set: libc::fd_set = std::mem::zeroed();
+        libc::FD_ZERO(&raw mut set);
+        libc::FD_SET(fd, &raw mut set);
+        libc::pselect(
+            fd + 1,
+            &raw mut set,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &raw const timeout,
+            std::ptr::null(),
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn epoll_wait(fd: c_int, timeout: c_int) -> c_int {
+    unsafe {
+        let epfd = libc::epoll_create1(libc::EPOLL_CLOEXEC);
+        let mut event = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: 0,
+        };
+        assert_eq!(
+            0,
+            libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &raw mut event)
+        );
+        let mut events: [libc::epoll_event; 1] = std::mem::zeroed();
+        let r = libc::epoll_wait(epfd, events.as_mut_ptr(), 1, timeout);
+        _ = libc::close(epfd);
+        r
+    }
+}
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() -> Result<()> {
+    wait_pipe("poll", poll)?;
+    wait_pipe("pselect", pselect)?;
+    #[cfg(target_os = "linux")]
+    wait_pipe("epoll_wait", epoll_wait)?;
+    // nothing written, the waiter wakes up by timeout
+    let fds = pipe()?;
+    let timeout = task!(move |_| poll(fds[0], 100), ());
+    assert_eq!(Some(0), timeout.timeout_join(Duration::from_secs(10))?);
+    close(fds);
+    // an invalid timeout fails like the kernel does
+    let invalid = task!(
+        |_| {
+            let timeout = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: -1,
+            };
+            let r = unsafe {
+                libc::pselect(
+                    0,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    &raw const timeout,
+                    std::ptr::null(),
+                )
+            };
+            assert_eq!(Some(libc::EINVAL), Error::last_os_error().raw_os_error());
+            r
+        },
+        ()
+    );
+    assert_eq!(Some(-1), invalid.timeout_join(Duration::from_secs(10))?);
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use open_coroutine::process::Command;
+use open_coroutine::task;
+use std::io::{Error, Read, Result, Write};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() -> Result<()> {
+    let start = Instant::now();
+    // the child and its pipes are waited in a coroutine, another coroutine keeps running
+    let child = task!(
+        |_| -> Result<String> {
+            let mut child = Command::new("sh")
+                .args(["-c", "read line; sleep 0.3; echo \"$line\"; echo oops >&2"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            child
+                .stdin
+                .take()
+                .ok_or_else(|| Error::other("no stdin"))?
+                .write_all(b"hello\n")?;
+            let output = child.wait_with_output()?;
+            assert!(output.status.success());
+            assert_eq!(b"oops\n", output.stderr.as_slice());
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        },
+        ()
+    );
+    let other = task!(move |_| start.elapsed(), ());
+    let elapsed = other
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join other failed"))?;
+    assert!(
+        elapsed < Duration::from_millis(300),
+        "the child blocked the event loop"
+    );
+    let output = child
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join child failed"))??;
+    assert_eq!("hello\n", output);
+
+    // timeout, kill on drop and hooked waitpid
+    let timeout = task!(
+        |_| -> Result<()> {
+            let mut child = Command::new("sleep").arg("10").kill_on_drop(true).spawn()?;
+            let start = Instant::now();
+            assert!(child.wait_timeout(Duration::from_millis(100))?.is_none());
+            assert!(start.elapsed() < Duration::from_secs(10));
+            let pid = child.id();
+            drop(child);
+            // already killed and reaped
+            assert_eq!(-1, unsafe {
+                libc::kill(pid.try_into().expect("overflow"), 0)
+            });
+            // the hooked waitpid of std parks instead of blocking
+            assert!(std::process::Command::new("true").status()?.success());
+            let mut out = String::new();
+            _ = Command::new("echo")
+                .arg("world")
+                .stdout(Stdio::piped())
+                .spawn()?
+                .stdout
+                .take()
+                .ok_or_else(|| Error::other("no stdout"))?
+                .read_to_string(&mut out)?;
+            assert_eq!("world\n", out);
+            Ok(())
+        },
+        ()
+    );
+    timeout
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join timeout failed"))?
+}
@@ -0,0 +1,65 @@
+use open_coroutine::{cancel_tasks, crate_named_task, find_tasks, get_task, TaskState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(10), "wait timeout");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() {
+    let sleeper = crate_named_task(
+        "sleeper",
+        |()| std::thread::sleep(Duration::from_millis(100)),
+        (),
+        0,
+    );
+    let sleeper_id = sleeper.id().expect("invalid task id");
+    wait_until(|| {
+        matches!(
+            get_task(sleeper_id).map(|info| info.state),
+            Some(TaskState::Syscall(_) | TaskState::Suspended)
+        )
+    });
+    // keep the other worker busy until all tasks are submitted
+    let started = Arc::new(AtomicBool::new(false));
+    let release = Arc::new(AtomicBool::new(false));
+    let blocker = crate_named_task(
+        "blocker",
+        |(started, release): (Arc<AtomicBool>, Arc<AtomicBool>)| {
+            started.store(true, Ordering::Release);
+            while !release.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        },
+        (started.clone(), release.clone()),
+        0,
+    );
+    wait_until(|| started.load(Ordering::Acquire));
+    let blocker_id = blocker.id().expect("invalid task id");
+    assert_eq!(
+        Some(TaskState::Running),
+        get_task(blocker_id).map(|info| info.state)
+    );
+    // the same name, but different tasks
+    let duplicates: Vec<_> = (0..2)
+        .map(|_| crate_named_task("duplicate", |()| {}, (), 0))
+        .collect();
+    let found = find_tasks("duplicate");
+    assert_eq!(2, found.len());
+    assert_ne!(found[0].id, found[1].id);
+    assert!(found.iter().all(|info| info.state == TaskState::Queued));
+    assert_eq!(2, cancel_tasks("duplicate"));
+    drop(duplicates);
+    release.store(true, Ordering::Release);
+    assert_eq!(Some(()), blocker.join().expect("blocker failed"));
+    assert_eq!(Some(()), sleeper.join().expect("sleeper failed"));
+    assert!(get_task(blocker_id).is_none());
+    assert!(get_task(sleeper_id).is_none());
+    wait_until(|| find_tasks("duplicate").is_empty());
+}
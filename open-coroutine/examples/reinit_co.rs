@@ -0,0 +1,27 @@
+use open_coroutine::{task, Config};
+use std::time::Duration;
+
+fn run(event_loop_size: usize) {
+    let mut config = Config::default();
+    _ = config.set_event_loop_size(event_loop_size);
+    open_coroutine::init(config);
+    assert_eq!(event_loop_size, open_coroutine::stats().loads.len());
+    let handle = task!(
+        |millis| {
+            std::thread::sleep(Duration::from_millis(millis));
+            millis
+        },
+        10,
+        0
+    );
+    assert_eq!(Some(10), handle.join().expect("join failed"));
+    open_coroutine::shutdown();
+    assert!(open_coroutine::stats().loads.is_empty());
+}
+
+pub fn main() {
+    // the runtime can be initialized again with a different config after shutdown
+    run(1);
+    run(2);
+    run(1);
+}
@@ -0,0 +1,55 @@
+use open_coroutine::{task, TaskListener};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct RejectCounter(Arc<AtomicU64>);
+
+impl TaskListener for RejectCounter {
+    fn on_rejected(&self, _: u64, _: &str) {
+        _ = self.0.fetch_add(1, Ordering::Release);
+    }
+}
+
+#[open_coroutine::main(
+    event_loop_size = 1,
+    max_size = 1,
+    task_queue_capacity = 2,
+    rejection_policy = "abort"
+)]
+pub fn main() {
+    let rejected = Arc::new(AtomicU64::new(0));
+    open_coroutine::add_task_listener(RejectCounter(rejected.clone()));
+    // keep the only worker busy until all tasks are submitted
+    let started = Arc::new(AtomicBool::new(false));
+    let release = Arc::new(AtomicBool::new(false));
+    let blocker = task!(
+        |(started, release): (Arc<AtomicBool>, Arc<AtomicBool>)| {
+            started.store(true, Ordering::Release);
+            while !release.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        },
+        (started.clone(), release.clone()),
+        0
+    );
+    while !started.load(Ordering::Acquire) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let queued: Vec<_> = (0..2).map(|i| task!(|i| i, i, 0)).collect();
+    let overflow = task!(|()| 2, (), 0);
+    assert!(overflow.is_rejected(), "the task queue should be full");
+    assert!(
+        overflow.join().is_err(),
+        "the rejected task can't be joined"
+    );
+    release.store(true, Ordering::Release);
+    assert_eq!(Some(()), blocker.join().expect("blocker failed"));
+    for (i, handle) in queued.into_iter().enumerate() {
+        assert!(!handle.is_rejected());
+        assert_eq!(Some(i), handle.join().expect("queued task failed"));
+    }
+    assert_eq!(1, rejected.load(Ordering::Acquire));
+    assert_eq!(1, open_coroutine::stats().rejections);
+}
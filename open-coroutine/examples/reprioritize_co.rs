@@ -0,0 +1,50 @@
+use open_coroutine::task;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 1)]
+pub fn main() {
+    // keep the only worker busy until all tasks are re-prioritized
+    let started = Arc::new(AtomicBool::new(false));
+    let release = Arc::new(AtomicBool::new(false));
+    let blocker = task!(
+        |(started, release): (Arc<AtomicBool>, Arc<AtomicBool>)| {
+            started.store(true, Ordering::Release);
+            while !release.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        },
+        (started.clone(), release.clone()),
+        0
+    );
+    while !started.load(Ordering::Acquire) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let handles: Vec<_> = (1..=3)
+        .map(|id| {
+            task!(
+                |(order, id): (Arc<Mutex<Vec<i64>>>, i64)| order.lock().unwrap().push(id),
+                (order.clone(), id),
+                id * 10
+            )
+        })
+        .collect();
+    // e.g. the request is authenticated
+    handles[2].set_priority(-1).expect("set priority failed");
+    handles[0].set_priority(100).expect("set priority failed");
+    release.store(true, Ordering::Release);
+    assert_eq!(Some(()), blocker.join().expect("blocker failed"));
+    for handle in &handles {
+        assert_eq!(
+            Some(()),
+            handle
+                .timeout_join(Duration::from_secs(5))
+                .expect("task failed")
+        );
+    }
+    assert_eq!(vec![3, 2, 1], *order.lock().unwrap());
+    // the finished task can't be re-prioritized
+    assert!(handles[0].set_priority(0).is_err());
+}
@@ -0,0 +1,35 @@
+use open_coroutine::task;
+use std::io::{Error, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() -> Result<()> {
+    let flag = Arc::new(AtomicBool::new(false));
+    // a spin-wait on a single thread only finishes if sched_yield switches coroutines
+    let waiter = task!(
+        |flag: Arc<AtomicBool>| -> usize {
+            let mut yields = 0;
+            while !flag.load(Ordering::Acquire) && yields < 1_000_000 {
+                assert_eq!(0, unsafe { libc::sched_yield() });
+                yields += 1;
+            }
+            yields
+        },
+        flag.clone()
+    );
+    let setter = task!(
+        |flag: Arc<AtomicBool>| flag.store(true, Ordering::Release),
+        flag.clone()
+    );
+    setter
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join setter failed"))?;
+    let yields = waiter
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join waiter failed"))?;
+    assert!(flag.load(Ordering::Acquire));
+    assert!(yields < 1_000_000, "sched_yield didn't switch coroutines");
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use open_coroutine::task;
+use std::io::{Error, Result};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 3, scheduling_policy = "fair_share")]
+pub fn main() -> Result<()> {
+    let started = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let spin = |(started, stop): (Arc<AtomicUsize>, Arc<AtomicBool>)| -> usize {
+        _ = started.fetch_add(1, Ordering::Release);
+        let mut yields = 0;
+        while !stop.load(Ordering::Acquire) {
+            assert_eq!(0, unsafe { libc::sched_yield() });
+            yields += 1;
+        }
+        yields
+    };
+    let first = task!(spin, (started.clone(), stop.clone()));
+    let second = task!(spin, (started.clone(), stop.clone()));
+    let stopper = task!(
+        |(started, stop): (Arc<AtomicUsize>, Arc<AtomicBool>)| {
+            while started.load(Ordering::Acquire) < 2 {
+                assert_eq!(0, unsafe { libc::sched_yield() });
+            }
+            std::thread::sleep(Duration::from_millis(100));
+            stop.store(true, Ordering::Release);
+        },
+        (started.clone(), stop.clone())
+    );
+    stopper
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join stopper failed"))?;
+    let first = first
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join first failed"))?;
+    let second = second
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join second failed"))?;
+    // both spinners got their turns on the single thread
+    assert!(first > 0 && second > 0, "{first} vs {second}");
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use open_coroutine::task;
+use std::io::{Error, Result};
+use std::time::{Duration, Instant};
+
+pub fn main() -> Result<()> {
+    // refuse the signals used by open-coroutine itself
+    assert!(open_coroutine::signal::listen(&[libc::SIGURG]).is_err());
+    // listen before init, so the threads of open-coroutine inherit the signal mask
+    let signals = open_coroutine::signal::listen(&[libc::SIGUSR2, libc::SIGHUP])?;
+    assert_eq!(None, signals.try_recv()?);
+    assert_eq!(None, signals.recv_timeout(Duration::from_millis(10))?);
+    open_coroutine::init(open_coroutine::Config::single());
+    let receiver = task!(
+        move |_| -> Result<Option<libc::c_int>> { signals.recv_timeout(Duration::from_secs(5)) },
+        ()
+    );
+    let sender = task!(
+        |_| {
+            // the receiver is parked on the same thread
+            assert_eq!(0, unsafe {
+                libc::pthread_kill(libc::pthread_self(), libc::SIGUSR2)
+            });
+        },
+        ()
+    );
+    let start = Instant::now();
+    sender
+        .timeout_join(Duration::from_secs(5))?
+        .ok_or_else(|| Error::other("join sender failed"))?;
+    let signal = receiver
+        .timeout_join(Duration::from_secs(5))?
+        .ok_or_else(|| Error::other("join receiver failed"))??;
+    assert_eq!(Some(libc::SIGUSR2), signal);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    open_coroutine::shutdown();
+    Ok(())
+}
@@ -20,8 +20,9 @@ pub fn start_co_server(
         cvar.notify_one();
     }
     for stream in listener.incoming() {
+        let server_finished = server_finished.clone();
         _ = task!(
-            |mut socket| {
+            move |mut socket| {
                 let mut buffer1 = [0; 256];
                 for _ in 0..3 {
                     assert_eq!(12, socket.read(&mut buffer1).expect("recv failed"));
@@ -0,0 +1,40 @@
+use open_coroutine::{DeadlineMissPolicy, TaskBuilder};
+use std::time::{Duration, Instant};
+
+fn recurse(i: u32, p: &mut [u8; 10240]) -> u32 {
+    // Ensure the stack allocation isn't optimized away.
+    unsafe { _ = std::ptr::read_volatile(&p) };
+    if i > 0 {
+        return recurse(i - 1, &mut [0; 10240]) + 1;
+    }
+    0
+}
+
+#[open_coroutine::main(event_loop_size = 2, max_size = 1)]
+pub fn main() {
+    // Use ~500KB of stack, more than the default stack size of the coroutines.
+    let deep = TaskBuilder::new()
+        .name("deep-recursion")
+        .stack_size(1024 * 1024)
+        .priority(0)
+        .spawn(|i| recurse(i, &mut [0; 10240]), 50);
+    assert_eq!(Some(50), deep.join().expect("deep recursion failed"));
+    let pinned = TaskBuilder::new()
+        .event_loop(1)
+        .deadline(
+            Instant::now() + Duration::from_secs(1),
+            DeadlineMissPolicy::Run,
+        )
+        .spawn(|i| i, 1);
+    assert_eq!(Some(1), pinned.join().expect("pinned task failed"));
+    assert!(
+        TaskBuilder::new()
+            .event_loop(2)
+            .spawn(|i| i, 2)
+            .join()
+            .is_err(),
+        "the event-loop 2 doesn't exist"
+    );
+    let detached = TaskBuilder::new().detached(true).spawn(|i| i, 3);
+    assert_eq!(None, detached.join().expect("detached task failed"));
+}
@@ -0,0 +1,80 @@
+use open_coroutine::{crate_task_with_timeout, TimeoutFrom};
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() {
+    let start = Instant::now();
+    // woken up from the sleep syscall and cancelled
+    let sleeping = crate_task_with_timeout(
+        |()| std::thread::sleep(Duration::from_secs(10)),
+        (),
+        0,
+        Duration::from_millis(100),
+        TimeoutFrom::Start,
+    );
+    let finished = crate_task_with_timeout(
+        |i| i + 1,
+        1,
+        0,
+        Duration::from_secs(10),
+        TimeoutFrom::Submit,
+    );
+    assert_eq!(Some(2), finished.join().expect("finished task failed"));
+    let error = sleeping.join().expect_err("the task should time out");
+    assert_eq!(ErrorKind::TimedOut, error.kind());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // the parked read fails with ECANCELED, then the task unwinds by itself
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind failed");
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let reading = crate_task_with_timeout(
+        |(addr, sender): (SocketAddr, Sender<std::io::Result<usize>>)| {
+            let mut stream = TcpStream::connect(addr).expect("connect failed");
+            let mut buf = [0; 8];
+            sender
+                .send(stream.read(&mut buf))
+                .expect("send result failed");
+        },
+        (
+            listener.local_addr().expect("get local addr failed"),
+            sender,
+        ),
+        0,
+        Duration::from_millis(100),
+        TimeoutFrom::Start,
+    );
+    let _accepted = listener.accept().expect("accept failed");
+    let error = reading.join().expect_err("the task should time out");
+    assert_eq!(ErrorKind::TimedOut, error.kind());
+    let error = receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("the task should unwind")
+        .expect_err("the read should be cancelled");
+    #[cfg(unix)]
+    assert_eq!(Some(libc::ECANCELED), error.raw_os_error());
+    #[cfg(windows)]
+    assert!(error.raw_os_error().is_some());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    #[cfg(feature = "preemptive")]
+    {
+        // preempted by the monitor, then cancelled
+        let spinning = crate_task_with_timeout(
+            |start: Instant| {
+                while start.elapsed() < Duration::from_secs(10) {
+                    std::hint::spin_loop();
+                }
+            },
+            Instant::now(),
+            0,
+            Duration::from_millis(100),
+            TimeoutFrom::Start,
+        );
+        let error = spinning.join().expect_err("the task should time out");
+        assert_eq!(ErrorKind::TimedOut, error.kind());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+    let expected = if cfg!(feature = "preemptive") { 3 } else { 2 };
+    assert_eq!(expected, open_coroutine::stats().timeouts);
+}
@@ -0,0 +1,50 @@
+use open_coroutine::{crate_task_with_deadline, task, DeadlineMissPolicy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+struct Tracked(Arc<AtomicUsize>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        _ = self.0.fetch_add(1, Ordering::Release);
+    }
+}
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() {
+    let greeting = task!(
+        |name: String| format!("hello {name}"),
+        String::from("world"),
+        0
+    );
+    assert_eq!(
+        Some(String::from("hello world")),
+        greeting.join().expect("join failed")
+    );
+    // the panic hook of this binary captures the backtrace in the coroutine, which may be
+    // preempted with the allocator locked, so keep the expected panic quiet
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let failed = task!(|_| panic!("test panic, just ignore it"), (), 0);
+    assert!(failed.join().is_err());
+    std::panic::set_hook(hook);
+
+    // the unclaimed result is dropped together with its handle
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let handle = task!(Tracked, dropped.clone(), 0);
+    while handle.timeout_join(Duration::from_millis(10)).is_err() {}
+    drop(handle);
+    let handle = task!(Tracked, dropped.clone(), 0);
+    drop(handle);
+    // the param of the task dropped without running is dropped too
+    let missed = crate_task_with_deadline(
+        |tracked: Tracked| drop(tracked),
+        Tracked(dropped.clone()),
+        Instant::now(),
+        DeadlineMissPolicy::Drop,
+    );
+    assert!(missed.join().is_err());
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(3, dropped.load(Ordering::Acquire));
+}
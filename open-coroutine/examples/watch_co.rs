@@ -0,0 +1,94 @@
+use open_coroutine::task;
+use open_coroutine::watch::{Event, Watcher};
+use std::io::{Error, Result};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn receive(mut watcher: Watcher, count: usize) -> Result<(Watcher, Vec<Event>)> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut events = Vec::new();
+    while events.len() < count {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match watcher.recv_timeout(left)? {
+            Some(event) => events.push(event),
+            None => break,
+        }
+    }
+    Ok((watcher, events))
+}
+
+fn assert_received(expected: &[Event], events: &[Event]) {
+    for event in expected {
+        assert!(events.contains(event), "{event:?} not in {events:?}");
+    }
+}
+
+#[open_coroutine::main(event_loop_size = 1, max_size = 2)]
+pub fn main() -> Result<()> {
+    let root = std::env::temp_dir().join(format!("open-coroutine-watch-{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root)?;
+    let watcher = open_coroutine::watch::watch(&root, true)?;
+    let sub = root.join("sub");
+    let file = sub.join("file");
+    let renamed = root.join("renamed");
+
+    // the new directory is watched after its creation is received
+    std::fs::create_dir(&sub)?;
+    let receiver = task!(|_| receive(watcher, 1), ());
+    let (watcher, events) = receiver
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join receiver failed"))??;
+    assert_received(&[Event::Create(sub.clone())], &events);
+
+    // the receiver is parked until the writer changes the files
+    let receiver = task!(|_| receive(watcher, 4), ());
+    let writer = task!(
+        |(file, renamed): (PathBuf, PathBuf)| -> Result<()> {
+            std::fs::write(&file, b"hello")?;
+            std::fs::rename(&file, &renamed)?;
+            std::fs::remove_file(&renamed)
+        },
+        (file.clone(), renamed.clone())
+    );
+    writer
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join writer failed"))??;
+    let (watcher, events) = receiver
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join receiver failed"))??;
+    assert_received(
+        &[
+            Event::Create(file.clone()),
+            Event::Modify(file),
+            Event::Move {
+                from: Some(sub.join("file")),
+                to: Some(renamed.clone()),
+            },
+            Event::Delete(renamed),
+        ],
+        &events,
+    );
+
+    // the renamed directory keeps its watch, its entries are reported with the new path
+    let moved = root.join("moved");
+    std::fs::rename(&sub, &moved)?;
+    std::fs::write(moved.join("file"), b"hello")?;
+    let receiver = task!(|_| receive(watcher, 2), ());
+    let (_, events) = receiver
+        .timeout_join(Duration::from_secs(10))?
+        .ok_or_else(|| Error::other("join receiver failed"))??;
+    std::fs::remove_dir_all(&root)?;
+    assert_received(
+        &[
+            Event::Move {
+                from: Some(sub),
+                to: Some(moved.clone()),
+            },
+            Event::Create(moved.join("file")),
+        ],
+        &events,
+    );
+    assert!(!events.contains(&Event::Create(moved)), "{events:?}");
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! Run tasks with dependencies.
+//!
+//! A node of the [`TaskGraph`] runs once all its dependencies completed, with their results as
+//! inputs, the ready nodes run in parallel across the event-loops.
+
+use open_coroutine_core::co_pool::task::UserTaskFree;
+use open_coroutine_core::common::get_timeout_time;
+pub use open_coroutine_core::net::dag::{
+    FailurePolicy, GraphReport, NodeReport, NodeStatus, REJECTED_ERROR,
+};
+use open_coroutine_core::net::dag::{RawNodeFunc, RawNodeReport, RawNodeResult};
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+extern "C" {
+    fn open_coroutine_graph_create(policy: FailurePolicy) -> *mut c_void;
+
+    fn open_coroutine_graph_add_node(
+        graph: *mut c_void,
+        name: *const c_char,
+        deps: *const usize,
+        len: usize,
+        f: RawNodeFunc,
+        param: usize,
+        free: Option<UserTaskFree>,
+    ) -> isize;
+
+    fn open_coroutine_graph_free(graph: *mut c_void);
+
+    fn open_coroutine_graph_submit(graph: *mut c_void) -> *mut c_void;
+
+    fn open_coroutine_graph_join(handle: *const c_void, timeout_time: u64) -> c_int;
+
+    fn open_coroutine_graph_node(
+        handle: *const c_void,
+        index: usize,
+        report: *mut RawNodeReport,
+    ) -> c_int;
+
+    fn open_coroutine_graph_handle_free(handle: *mut c_void);
+}
+
+/// A graph of tasks, see the [module level documentation](self).
+///
+/// # Examples
+///
+/// ```no_run
+/// use open_coroutine::dag::{FailurePolicy, TaskGraph};
+///
+/// let mut graph = TaskGraph::new(FailurePolicy::FailFast);
+/// let left = graph.add_node("left", &[], |_| Ok(Some(1))).expect("add node failed");
+/// let right = graph.add_node("right", &[], |_| Ok(Some(2))).expect("add node failed");
+/// _ = graph
+///     .add_node("sum", &[left, right], |inputs| {
+///         Ok(Some(inputs.into_iter().flatten().sum()))
+///     })
+///     .expect("add node failed");
+/// let report = graph.submit().join().expect("join graph failed");
+/// assert!(report.is_success());
+/// ```
+#[derive(Debug)]
+pub struct TaskGraph {
+    raw: *mut c_void,
+    names: Vec<String>,
+}
+
+// the graph is owned by the hook, it's only moved between threads
+unsafe impl Send for TaskGraph {}
+
+impl TaskGraph {
+    /// Create a new empty graph.
+    #[must_use]
+    pub fn new(policy: FailurePolicy) -> Self {
+        Self {
+            raw: unsafe { open_coroutine_graph_create(policy) },
+            names: Vec::new(),
+        }
+    }
+
+    /// Add a node which depends on the `deps` nodes, returns the index of the node.
+    ///
+    /// The node receives the results of `deps` in the same order. The dependencies must be
+    /// added before, so the graph can never contain a cycle.
+    ///
+    /// # Errors
+    /// if the `name` contains nul or any of the `deps` does not exist.
+    pub fn add_node<
+        F: FnOnce(Vec<Option<usize>>) -> Result<Option<usize>, &'static str> + Send + 'static,
+    >(
+        &mut self,
+        name: &str,
+        deps: &[usize],
+        func: F,
+    ) -> std::io::Result<usize> {
+        let c_name = CString::new(name).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let (f, param, free) = node_func(func);
+        // the param is freed by the hook if the node is not added
+        let index = unsafe {
+            open_coroutine_graph_add_node(
+                self.raw,
+                c_name.as_ptr(),
+                deps.as_ptr(),
+                deps.len(),
+                f,
+                param,
+                Some(free),
+            )
+        };
+        let index = usize::try_from(index).map_err(|_| Error::last_os_error())?;
+        self.names.push(name.to_string());
+        Ok(index)
+    }
+
+    /// Returns the number of nodes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns `true` if the graph has no node.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Submit the nodes without dependencies to the event-loops, the others are submitted once
+    /// their dependencies completed.
+    #[must_use]
+    pub fn submit(mut self) -> TaskGraphHandle {
+        let raw = std::mem::replace(&mut self.raw, std::ptr::null_mut());
+        TaskGraphHandle {
+            raw: unsafe { open_coroutine_graph_submit(raw) },
+            names: std::mem::take(&mut self.names),
+        }
+    }
+}
+
+impl Drop for TaskGraph {
+    fn drop(&mut self) {
+        unsafe { open_coroutine_graph_free(self.raw) };
+    }
+}
+
+/// The handle of a submitted [`TaskGraph`], the nodes keep running if it's dropped.
+#[derive(Debug)]
+pub struct TaskGraphHandle {
+    raw: *mut c_void,
+    names: Vec<String>,
+}
+
+// the handle is shared by all threads in the hook, just like the event-loops
+unsafe impl Send for TaskGraphHandle {}
+
+unsafe impl Sync for TaskGraphHandle {}
+
+impl TaskGraphHandle {
+    /// Returns `true` if all nodes finished.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        // a join never waits with the timeout time 0
+        0 == unsafe { open_coroutine_graph_join(self.raw, 0) }
+    }
+
+    /// Get the current status and timings of all nodes.
+    #[must_use]
+    pub fn report(&self) -> GraphReport {
+        GraphReport {
+            nodes: self
+                .names
+                .iter()
+                .enumerate()
+                .filter_map(|(index, name)| unsafe {
+                    NodeReport::read_raw(name.clone(), |raw| {
+                        open_coroutine_graph_node(self.raw, index, raw)
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Wait until all nodes finished, returns the report of the graph.
+    ///
+    /// # Errors
+    /// if join timeout.
+    pub fn timeout_join(&self, dur: Duration) -> std::io::Result<GraphReport> {
+        self.timeout_at_join(get_timeout_time(dur))
+    }
+
+    /// Wait until all nodes finished, returns the report of the graph.
+    ///
+    /// # Errors
+    /// see [`TaskGraphHandle::timeout_join`].
+    pub fn join(&self) -> std::io::Result<GraphReport> {
+        self.timeout_at_join(u64::MAX)
+    }
+
+    fn timeout_at_join(&self, timeout_time: u64) -> std::io::Result<GraphReport> {
+        if 0 != unsafe { open_coroutine_graph_join(self.raw, timeout_time) } {
+            return Err(Error::last_os_error());
+        }
+        Ok(self.report())
+    }
+}
+
+impl Drop for TaskGraphHandle {
+    fn drop(&mut self) {
+        unsafe { open_coroutine_graph_handle_free(self.raw) };
+    }
+}
+
+fn node_func<
+    F: FnOnce(Vec<Option<usize>>) -> Result<Option<usize>, &'static str> + Send + 'static,
+>(
+    func: F,
+) -> (RawNodeFunc, usize, UserTaskFree) {
+    extern "C" fn node_main<
+        F: FnOnce(Vec<Option<usize>>) -> Result<Option<usize>, &'static str> + Send + 'static,
+    >(
+        param: usize,
+        inputs: *const RawNodeResult,
+        len: usize,
+        output: *mut RawNodeResult,
+    ) {
+        let func = *unsafe { Box::from_raw((param as *mut c_void).cast::<F>()) };
+        let inputs = if inputs.is_null() {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(inputs, len) }
+                .iter()
+                .map(|input| unsafe { input.into_result() }.ok().flatten())
+                .collect()
+        };
+        // never unwind across the C ABI
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| func(inputs)))
+            .unwrap_or_else(|e| {
+                Err(e
+                    .downcast_ref::<&'static str>()
+                    .map_or("Graph node failed without message", |msg| *msg))
+            });
+        unsafe { output.write(result.into()) };
+    }
+    // the node is dropped without running
+    extern "C" fn node_free<F>(param: usize) {
+        drop(unsafe { Box::from_raw((param as *mut c_void).cast::<F>()) });
+    }
+    (
+        node_main::<F>,
+        Box::into_raw(Box::new(func)).cast::<c_void>() as usize,
+        node_free::<F>,
+    )
+}
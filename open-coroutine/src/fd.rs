@@ -0,0 +1,77 @@
+use open_coroutine_core::common::constants::SLICE;
+use std::ffi::{c_int, c_short};
+use std::io::{Error, ErrorKind};
+use std::os::fd::RawFd;
+use std::time::{Duration, Instant};
+
+/// Put the fd into non-blocking mode.
+pub(crate) fn set_non_blocking(fd: RawFd) -> std::io::Result<()> {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0 {
+            return Err(Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Wait for the fd to become ready, returns `false` if timeout.
+///
+/// The hooked `poll` parks the current coroutine on the selector of its `EventLoop`,
+/// so this won't block the thread when it's called from a coroutine.
+pub(crate) fn wait_fd(
+    fd: RawFd,
+    events: c_short,
+    timeout: Option<Duration>,
+) -> std::io::Result<bool> {
+    let deadline = timeout.and_then(|t| Instant::now().checked_add(t));
+    loop {
+        let left = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => SLICE,
+        };
+        let mut pfd = libc::pollfd {
+            fd,
+            events,
+            revents: 0,
+        };
+        // round up, or the last millisecond polls with 0 and spins until the deadline
+        let millis = c_int::try_from(left.as_nanos().div_ceil(1_000_000)).unwrap_or(c_int::MAX);
+        match unsafe { libc::poll(&raw mut pfd, 1, millis) } {
+            -1 => {
+                let e = Error::last_os_error();
+                if e.kind() != ErrorKind::Interrupted {
+                    return Err(e);
+                }
+            }
+            0 => {
+                if deadline.is_some() && left.is_zero() {
+                    return Ok(false);
+                }
+            }
+            _ => return Ok(true),
+        }
+    }
+}
+
+/// Retry `f` on `WouldBlock` until the fd is ready, the `timeout` covers all the retries.
+pub(crate) fn retry<R>(
+    fd: RawFd,
+    events: c_short,
+    timeout: Option<Duration>,
+    mut f: impl FnMut() -> std::io::Result<R>,
+) -> std::io::Result<R> {
+    let deadline = timeout.and_then(|t| Instant::now().checked_add(t));
+    loop {
+        match f() {
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                let left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+                if !wait_fd(fd, events, left)? {
+                    return Err(Error::new(ErrorKind::TimedOut, "wait fd timeout"));
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            r => return r,
+        }
+    }
+}
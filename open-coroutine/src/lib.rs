@@ -50,19 +50,46 @@
 )]
 //! see `https://github.com/acl-dev/open-coroutine`
 
-use open_coroutine_core::co_pool::task::UserTaskFunc;
+pub use open_coroutine_core::co_pool::adaptive::AdaptiveConfig;
+use open_coroutine_core::co_pool::listener::RawTaskListener;
+pub use open_coroutine_core::co_pool::listener::TaskListener;
+pub use open_coroutine_core::co_pool::task::{
+    DeadlineMissPolicy, RejectionPolicy, TaskInfo, TaskState, TimeoutFrom,
+};
+use open_coroutine_core::co_pool::task::{RawTaskInfo, UserTaskFree, UserTaskFunc};
 use open_coroutine_core::common::constants::SLICE;
+pub use open_coroutine_core::common::ordered_work_steal::BandStats;
 pub use open_coroutine_core::common::ordered_work_steal::DEFAULT_PRECEDENCE;
+pub use open_coroutine_core::common::topology::PinPolicy;
 pub use open_coroutine_core::config::Config;
-use open_coroutine_core::net::UserFunc;
+use open_coroutine_core::net::stats::RawRuntimeStats;
+pub use open_coroutine_core::net::stats::RuntimeStats;
+use open_coroutine_core::net::{RawTaskOptions, TaskOptions, UserFunc};
+pub use open_coroutine_core::scheduler::policy::SchedulingPolicyKind;
+pub use open_coroutine_core::scheduler::SchedulerLoad;
 pub use open_coroutine_macros::*;
 use std::cmp::Ordering;
-use std::ffi::{c_int, c_longlong, c_uint, c_void};
+use std::ffi::{c_char, c_int, c_longlong, c_uint, c_void, CString};
 use std::io::{Error, ErrorKind};
-use std::marker::PhantomData;
+use std::mem::MaybeUninit;
 use std::net::{TcpStream, ToSocketAddrs};
 use std::ops::Deref;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub mod dag;
+
+#[cfg(unix)]
+mod fd;
+
+#[cfg(unix)]
+pub mod process;
+
+#[cfg(target_os = "linux")]
+pub mod signal;
+
+#[cfg(target_os = "linux")]
+pub mod watch;
 
 extern "C" {
     fn open_coroutine_init(config: Config) -> c_int;
@@ -79,20 +106,83 @@ extern "C" {
 
 #[allow(improper_ctypes)]
 extern "C" {
-    fn task_crate(
+    fn task_crate_with_free(
         f: UserTaskFunc,
         param: usize,
+        free: Option<UserTaskFree>,
         priority: c_longlong,
     ) -> open_coroutine_core::net::join::JoinHandle;
 
     fn task_join(handle: &open_coroutine_core::net::join::JoinHandle) -> c_longlong;
 
+    fn open_coroutine_stats(stats: *mut RawRuntimeStats) -> c_int;
+
+    fn task_crate_named(
+        name: *const c_char,
+        f: UserTaskFunc,
+        param: usize,
+        free: Option<UserTaskFree>,
+        priority: c_longlong,
+    ) -> open_coroutine_core::net::join::JoinHandle;
+
+    fn open_coroutine_get_task(task_id: u64, info: *mut RawTaskInfo) -> c_int;
+
+    fn open_coroutine_find_tasks(name: *const c_char, ids: *mut u64, capacity: usize) -> usize;
+
+    fn open_coroutine_cancel_tasks(name: *const c_char) -> usize;
+
+    fn open_coroutine_add_task_listener(listener: RawTaskListener);
+
+    fn task_crate_with_deadline(
+        f: UserTaskFunc,
+        param: usize,
+        free: Option<UserTaskFree>,
+        deadline: u64,
+        miss_policy: DeadlineMissPolicy,
+    ) -> open_coroutine_core::net::join::JoinHandle;
+
+    fn task_crate_with_timeout(
+        f: UserTaskFunc,
+        param: usize,
+        free: Option<UserTaskFree>,
+        priority: c_longlong,
+        timeout: u64,
+        from: TimeoutFrom,
+    ) -> open_coroutine_core::net::join::JoinHandle;
+
+    fn task_crate_with_options(
+        f: UserTaskFunc,
+        param: usize,
+        free: Option<UserTaskFree>,
+        options: *const RawTaskOptions,
+    ) -> open_coroutine_core::net::join::JoinHandle;
+
     fn task_cancel(handle: &open_coroutine_core::net::join::JoinHandle) -> c_longlong;
 
+    fn task_set_priority(
+        handle: &open_coroutine_core::net::join::JoinHandle,
+        priority: c_longlong,
+    ) -> c_longlong;
+
     fn task_timeout_join(
         handle: &open_coroutine_core::net::join::JoinHandle,
         ns_time: u64,
     ) -> c_longlong;
+
+    fn open_coroutine_create_pool(name: *const c_char, config: Config) -> c_int;
+
+    fn open_coroutine_stop_pool(name: *const c_char, secs: c_uint) -> c_int;
+
+    fn open_coroutine_pool_stats(name: *const c_char, stats: *mut RawRuntimeStats) -> c_int;
+
+    fn task_crate_in(
+        pool: *const c_char,
+        f: UserTaskFunc,
+        param: usize,
+        free: Option<UserTaskFree>,
+        priority: c_longlong,
+        handle: *mut open_coroutine_core::net::join::JoinHandle,
+    ) -> c_int;
 }
 
 /// Init the open-coroutine.
@@ -106,11 +196,119 @@ pub fn init(config: Config) {
     open_coroutine_core::common::ci::init();
 }
 
-/// Shutdown the open-coroutine.
+/// Shutdown the open-coroutine, then it can be initialized again by [`init`], even with a
+/// different config.
 pub fn shutdown() {
     unsafe { _ = open_coroutine_stop(30) };
 }
 
+/// Get the statistics of the open-coroutine.
+#[must_use]
+pub fn stats() -> RuntimeStats {
+    RuntimeStats::read_raw(|stats| unsafe { open_coroutine_stats(stats) }).unwrap_or_default()
+}
+
+/// Get the live task by id, the task is removed after its result is taken.
+#[must_use]
+pub fn get_task(task_id: u64) -> Option<TaskInfo> {
+    TaskInfo::read_raw(|info| unsafe { open_coroutine_get_task(task_id, info) })
+}
+
+/// Find the live tasks by name, ordered by id.
+#[must_use]
+pub fn find_tasks(name: &str) -> Vec<TaskInfo> {
+    let Ok(name) = CString::new(name) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<u64> = Vec::new();
+    loop {
+        let len =
+            unsafe { open_coroutine_find_tasks(name.as_ptr(), ids.as_mut_ptr(), ids.capacity()) };
+        if len <= ids.capacity() {
+            unsafe { ids.set_len(len) };
+            break;
+        }
+        ids.reserve(len);
+    }
+    // the finished tasks are skipped
+    ids.into_iter().filter_map(get_task).collect()
+}
+
+/// Try to cancel the unfinished tasks with the name, returns the number of cancelling tasks.
+#[must_use]
+pub fn cancel_tasks(name: &str) -> usize {
+    CString::new(name).map_or(0, |name| unsafe {
+        open_coroutine_cancel_tasks(name.as_ptr())
+    })
+}
+
+/// Add a task listener, for example, to report the tasks missed their deadlines.
+pub fn add_task_listener(listener: impl TaskListener + 'static) {
+    unsafe { open_coroutine_add_task_listener(RawTaskListener::new(listener)) };
+}
+
+/// Create an isolated named pool with its own event-loops, coroutines and queues, the tasks
+/// of a pool are never run by other pools. The name can be reused after the pool is shut down.
+pub fn create_pool(name: &str, config: Config) -> std::io::Result<Pool> {
+    let name = CString::new(name).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    if 0 == unsafe { open_coroutine_create_pool(name.as_ptr(), config) } {
+        return Ok(Pool(name));
+    }
+    Err(Error::last_os_error())
+}
+
+/// The handle of an isolated named pool, see [`create_pool`].
+#[repr(C)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Pool(CString);
+
+impl Pool {
+    /// Get the name of the pool.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.0.to_str().expect("the pool name is created from str")
+    }
+
+    /// Create a task in the pool.
+    pub fn crate_task<P: Send + 'static, R: Send + 'static, F: FnOnce(P) -> R + Send + 'static>(
+        &self,
+        f: F,
+        param: P,
+        priority: c_longlong,
+    ) -> std::io::Result<JoinHandle<R>> {
+        let (f, param, free, slot) = task_func(f, param);
+        let mut handle = MaybeUninit::uninit();
+        if 0 != unsafe {
+            task_crate_in(
+                self.0.as_ptr(),
+                f,
+                param,
+                Some(free),
+                priority,
+                handle.as_mut_ptr(),
+            )
+        } {
+            let error = Error::last_os_error();
+            // the task is not created
+            free(param);
+            return Err(error);
+        }
+        Ok(JoinHandle::new(unsafe { handle.assume_init() }, slot))
+    }
+
+    /// Get the statistics of the pool.
+    #[must_use]
+    pub fn stats(&self) -> RuntimeStats {
+        RuntimeStats::read_raw(|stats| unsafe { open_coroutine_pool_stats(self.0.as_ptr(), stats) })
+            .unwrap_or_default()
+    }
+
+    /// Shutdown the pool, the default runtime and other pools keep running.
+    pub fn shutdown(&self) {
+        unsafe { _ = open_coroutine_stop_pool(self.0.as_ptr(), 30) };
+    }
+}
+
 /// Create a task.
 #[macro_export]
 macro_rules! task {
@@ -123,67 +321,316 @@ macro_rules! task {
 }
 
 /// Create a task.
-pub fn crate_task<P: 'static, R: 'static, F: FnOnce(P) -> R>(
+pub fn crate_task<P: Send + 'static, R: Send + 'static, F: FnOnce(P) -> R + Send + 'static>(
     f: F,
     param: P,
     priority: c_longlong,
 ) -> JoinHandle<R> {
-    extern "C" fn task_main<P: 'static, R: 'static, F: FnOnce(P) -> R>(input: usize) -> usize {
+    let (f, param, free, slot) = task_func(f, param);
+    JoinHandle::new(
+        unsafe { task_crate_with_free(f, param, Some(free), priority) },
+        slot,
+    )
+}
+
+/// Create a task with the `name`, the name may be shared by multiple tasks,
+/// see [`find_tasks`].
+///
+/// # Panics
+/// if the `name` contains nul.
+pub fn crate_named_task<
+    P: Send + 'static,
+    R: Send + 'static,
+    F: FnOnce(P) -> R + Send + 'static,
+>(
+    name: &str,
+    f: F,
+    param: P,
+    priority: c_longlong,
+) -> JoinHandle<R> {
+    let (f, param, free, slot) = task_func(f, param);
+    let name = CString::new(name).expect("the task name contains nul");
+    JoinHandle::new(
+        unsafe { task_crate_named(name.as_ptr(), f, param, Some(free), priority) },
+        slot,
+    )
+}
+
+/// Create a task with the absolute `deadline`, the tasks with deadlines run in
+/// earliest-deadline-first order before the tasks with priorities.
+pub fn crate_task_with_deadline<
+    P: Send + 'static,
+    R: Send + 'static,
+    F: FnOnce(P) -> R + Send + 'static,
+>(
+    f: F,
+    param: P,
+    deadline: Instant,
+    miss_policy: DeadlineMissPolicy,
+) -> JoinHandle<R> {
+    let deadline = open_coroutine_core::common::get_timeout_time(
+        deadline.saturating_duration_since(Instant::now()),
+    );
+    let (f, param, free, slot) = task_func(f, param);
+    JoinHandle::new(
+        unsafe { task_crate_with_deadline(f, param, Some(free), deadline, miss_policy) },
+        slot,
+    )
+}
+
+/// Create a task with the max run time `timeout`, measured from the start of the task or from
+/// now. Once it's exceeded, the task is cancelled and the joiner gets the
+/// [`ErrorKind::TimedOut`] error.
+pub fn crate_task_with_timeout<
+    P: Send + 'static,
+    R: Send + 'static,
+    F: FnOnce(P) -> R + Send + 'static,
+>(
+    f: F,
+    param: P,
+    priority: c_longlong,
+    timeout: Duration,
+    from: TimeoutFrom,
+) -> JoinHandle<R> {
+    let (f, param, free, slot) = task_func(f, param);
+    JoinHandle::new(
         unsafe {
-            let ptr = &mut *((input as *mut c_void).cast::<(F, P)>());
-            let data = std::ptr::read_unaligned(ptr);
-            let result: &'static mut std::io::Result<R> = Box::leak(Box::new(
-                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| (data.0)(data.1)))
-                    .map_err(|e| {
-                        Error::other(
-                            e.downcast_ref::<&'static str>()
-                                .map_or("task failed without message", |msg| *msg),
-                        )
-                    }),
-            ));
-            std::ptr::from_mut(result).cast::<c_void>() as usize
-        }
+            task_crate_with_timeout(
+                f,
+                param,
+                Some(free),
+                priority,
+                timeout.as_nanos().try_into().expect("overflow"),
+                from,
+            )
+        },
+        slot,
+    )
+}
+
+/// The builder of a task, to configure its name, stack size, priority, event-loop, deadline
+/// and whether it's detached.
+///
+/// # Examples
+///
+/// ```no_run
+/// let handle = open_coroutine::TaskBuilder::new()
+///     .name("deep-recursion")
+///     .stack_size(1024 * 1024)
+///     .spawn(|depth: usize| depth, 10_000);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TaskBuilder {
+    options: TaskOptions,
+    detached: bool,
+}
+
+impl TaskBuilder {
+    /// Create a builder with the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
     }
-    let inner = Box::leak(Box::new((f, param)));
-    unsafe {
-        task_crate(
-            task_main::<P, R, F>,
-            std::ptr::from_mut(inner).cast::<c_void>() as usize,
-            priority,
+
+    /// Set the name of the task, the name may be shared by multiple tasks, see [`find_tasks`].
+    /// [`TaskBuilder::spawn`] panics if the name contains nul.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Set the min stack size of the task, the task runs on a bigger stack if the stack of its
+    /// coroutine doesn't have enough room, other tasks keep [`Config::stack_size`].
+    #[must_use]
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.options.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Set the priority of the task.
+    #[must_use]
+    pub fn priority(mut self, priority: c_longlong) -> Self {
+        self.options.priority = Some(priority);
+        self
+    }
+
+    /// Submit the task to the event-loop `index`, the task is rejected if it doesn't exist.
+    #[must_use]
+    pub fn event_loop(mut self, index: usize) -> Self {
+        self.options.event_loop = Some(index);
+        self
+    }
+
+    /// Set the absolute `deadline` of the task, the tasks with deadlines run in
+    /// earliest-deadline-first order before the tasks with priorities.
+    #[must_use]
+    pub fn deadline(mut self, deadline: Instant, miss_policy: DeadlineMissPolicy) -> Self {
+        let deadline = open_coroutine_core::common::get_timeout_time(
+            deadline.saturating_duration_since(Instant::now()),
+        );
+        self.options.deadline = Some((deadline, miss_policy));
+        self
+    }
+
+    /// Detach the task, its result is dropped once it finishes and the handle only joins
+    /// `None`, the handle can still cancel it.
+    #[must_use]
+    pub fn detached(mut self, detached: bool) -> Self {
+        self.detached = detached;
+        self
+    }
+
+    /// Create the task.
+    pub fn spawn<P: Send + 'static, R: Send + 'static, F: FnOnce(P) -> R + Send + 'static>(
+        self,
+        f: F,
+        param: P,
+    ) -> JoinHandle<R> {
+        let (f, param, free, slot) = if self.detached {
+            let (f, param, free) = detached_task_func(f, param);
+            // never filled, the handle joins `None`
+            (f, param, free, ResultSlot::default())
+        } else {
+            task_func(f, param)
+        };
+        JoinHandle::new(
+            self.options.with_raw(|options| unsafe {
+                task_crate_with_options(f, param, Some(free), options)
+            }),
+            slot,
         )
-        .into()
     }
 }
 
+// the result and the panic of the detached task are dropped, the task returns 0
+fn detached_task_func<P: Send + 'static, R: Send + 'static, F: FnOnce(P) -> R + Send + 'static>(
+    f: F,
+    param: P,
+) -> (UserTaskFunc, usize, UserTaskFree) {
+    extern "C" fn task_main<
+        P: Send + 'static,
+        R: Send + 'static,
+        F: FnOnce(P) -> R + Send + 'static,
+    >(
+        input: usize,
+    ) -> usize {
+        let (f, param) = *unsafe { Box::from_raw((input as *mut c_void).cast::<(F, P)>()) };
+        _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(param)));
+        0
+    }
+    // the task is dropped without running
+    extern "C" fn task_free<P, F>(input: usize) {
+        drop(unsafe { Box::from_raw((input as *mut c_void).cast::<(F, P)>()) });
+    }
+    (
+        task_main::<P, R, F>,
+        Box::into_raw(Box::new((f, param))).cast::<c_void>() as usize,
+        task_free::<P, F>,
+    )
+}
+
+// the typed result of a task, shared by the task and its handle. The result is only moved to
+// the joiner, the unclaimed one is dropped with the last owner.
+type ResultSlot<R> = Arc<Mutex<Option<std::io::Result<R>>>>;
+
+// where the joiner takes the result of the task from
+#[derive(Debug)]
+enum ResultFrom<R> {
+    // the typed result filled by the task
+    Slot(ResultSlot<R>),
+    // the raw result of the task created by the core
+    Raw(fn(usize) -> R),
+}
+
+// the task returns it once its result is in the slot
+const TASK_RESULT_READY: usize = 1;
+
+fn task_func<P: Send + 'static, R: Send + 'static, F: FnOnce(P) -> R + Send + 'static>(
+    f: F,
+    param: P,
+) -> (UserTaskFunc, usize, UserTaskFree, ResultSlot<R>) {
+    extern "C" fn task_main<
+        P: Send + 'static,
+        R: Send + 'static,
+        F: FnOnce(P) -> R + Send + 'static,
+    >(
+        input: usize,
+    ) -> usize {
+        let (f, param, slot) =
+            *unsafe { Box::from_raw((input as *mut c_void).cast::<(F, P, ResultSlot<R>)>()) };
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(param))).map_err(|e| {
+                Error::other(
+                    e.downcast_ref::<&'static str>()
+                        .map_or("task failed without message", |msg| *msg),
+                )
+            });
+        *slot.lock().expect("lock failed") = Some(result);
+        TASK_RESULT_READY
+    }
+    // the task is dropped without running, the joiner gets an error instead
+    extern "C" fn task_free<P, R, F>(input: usize) {
+        drop(unsafe { Box::from_raw((input as *mut c_void).cast::<(F, P, ResultSlot<R>)>()) });
+    }
+    let slot = ResultSlot::default();
+    (
+        task_main::<P, R, F>,
+        Box::into_raw(Box::new((f, param, slot.clone()))).cast::<c_void>() as usize,
+        task_free::<P, R, F>,
+        slot,
+    )
+}
+
+// the join result of the task cancelled due to timeout
+const TASK_TIMED_OUT: c_longlong = -2;
+
 #[allow(missing_docs)]
 #[repr(C)]
 #[derive(Debug)]
-pub struct JoinHandle<R>(open_coroutine_core::net::join::JoinHandle, PhantomData<R>);
+pub struct JoinHandle<R>(open_coroutine_core::net::join::JoinHandle, ResultFrom<R>);
 
 #[allow(missing_docs)]
 impl<R> JoinHandle<R> {
+    fn new(handle: open_coroutine_core::net::join::JoinHandle, slot: ResultSlot<R>) -> Self {
+        Self(handle, ResultFrom::Slot(slot))
+    }
+
     pub fn timeout_join(&self, dur: Duration) -> std::io::Result<Option<R>> {
-        unsafe {
-            let ptr = task_timeout_join(self, dur.as_nanos().try_into().expect("overflow"));
-            match ptr.cmp(&0) {
-                Ordering::Less => Err(Error::other("timeout join failed")),
-                Ordering::Equal => Ok(None),
-                Ordering::Greater => Ok(Some((*Box::from_raw(ptr as *mut std::io::Result<R>))?)),
-            }
-        }
+        let r = unsafe { task_timeout_join(self, dur.as_nanos().try_into().expect("overflow")) };
+        self.take(r, "timeout join failed")
     }
 
     pub fn join(self) -> std::io::Result<Option<R>> {
-        unsafe {
-            let ptr = task_join(&self);
-            match ptr.cmp(&0) {
-                Ordering::Less => Err(Error::other("join failed")),
-                Ordering::Equal => Ok(None),
-                Ordering::Greater => Ok(Some((*Box::from_raw(ptr as *mut std::io::Result<R>))?)),
-            }
+        let r = unsafe { task_join(&self) };
+        self.take(r, "join failed")
+    }
+
+    // take the typed result by the join result of the C ABI
+    fn take(&self, r: c_longlong, error: &str) -> std::io::Result<Option<R>> {
+        match r.cmp(&0) {
+            Ordering::Less if r == TASK_TIMED_OUT => Err(Self::timed_out()),
+            Ordering::Less => Err(Error::other(error.to_string())),
+            Ordering::Equal => Ok(None),
+            Ordering::Greater => match &self.1 {
+                ResultFrom::Slot(slot) => slot
+                    .lock()
+                    .expect("lock failed")
+                    .take()
+                    .ok_or_else(|| Error::other("the result was already taken"))?
+                    .map(Some),
+                ResultFrom::Raw(from) => Ok(Some(from(usize::try_from(r).expect("overflow")))),
+            },
         }
     }
 
+    fn timed_out() -> Error {
+        Error::new(
+            ErrorKind::TimedOut,
+            open_coroutine_core::co_pool::task::TIMEOUT_ERROR,
+        )
+    }
+
     pub fn any_timeout_join(dur: Duration, slice: &[Self]) -> std::io::Result<Option<R>> {
         if slice.is_empty() {
             return Ok(None);
@@ -218,11 +665,24 @@ impl<R> JoinHandle<R> {
             _ => Err(Error::other("cancel failed")),
         }
     }
+
+    /// Change the priority of this task. The queued task is re-sorted immediately, the running
+    /// task takes effect the next time it's resumed.
+    ///
+    /// # Errors
+    /// if the task is finished, or waiting for its deadline.
+    pub fn set_priority(&self, priority: c_longlong) -> std::io::Result<()> {
+        let r = unsafe { task_set_priority(self, priority) };
+        match r.cmp(&0) {
+            Ordering::Equal => Ok(()),
+            _ => Err(Error::other("set priority failed")),
+        }
+    }
 }
 
-impl<R> From<open_coroutine_core::net::join::JoinHandle> for JoinHandle<R> {
+impl From<open_coroutine_core::net::join::JoinHandle> for JoinHandle<usize> {
     fn from(val: open_coroutine_core::net::join::JoinHandle) -> Self {
-        Self(val, PhantomData)
+        Self(val, ResultFrom::Raw(|r| r))
     }
 }
 
@@ -379,9 +839,9 @@ mod tests {
     #[test]
     fn test() {
         init(Config::single());
-        _ = any_join!(task!(|_| 1, ()), task!(|_| 2, ()), task!(|_| 3, ()));
+        _ = any_join!(task!(|()| 1, ()), task!(|()| 2, ()), task!(|()| 3, ()));
         task!(
-            |_| {
+            |()| {
                 unreachable!("Try cancel!");
             },
             (),
@@ -389,7 +849,7 @@ mod tests {
         .try_cancel()
         .expect("cancel failed");
         let join = task!(
-            |_| {
+            |()| {
                 println!("Hello, world!");
             },
             (),
@@ -0,0 +1,331 @@
+//! Coroutine-aware child processes.
+//!
+//! The API mirrors [`std::process`], but the pipes of the child are non-blocking and
+//! waiting for them or for the exit of the child parks the current coroutine instead of
+//! blocking the thread of its `EventLoop`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use open_coroutine::process::Command;
+//! use std::process::Stdio;
+//! use std::io::Read;
+//!
+//! let mut child = Command::new("echo")
+//!     .arg("hello")
+//!     .stdout(Stdio::piped())
+//!     .kill_on_drop(true)
+//!     .spawn()
+//!     .expect("spawn failed");
+//! let mut output = String::new();
+//! _ = child.stdout.take().expect("no stdout").read_to_string(&mut output);
+//! assert!(child.wait().expect("wait failed").success());
+//! ```
+
+use crate::fd::{retry, set_non_blocking, wait_fd};
+use open_coroutine_core::common::constants::SLICE;
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::process::{ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
+
+/// A process builder, see [`std::process::Command`].
+#[derive(Debug)]
+pub struct Command {
+    inner: std::process::Command,
+    kill_on_drop: bool,
+}
+
+impl Command {
+    /// Constructs a new `Command` for launching the program at path `program`.
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Self {
+            inner: std::process::Command::new(program),
+            kill_on_drop: false,
+        }
+    }
+
+    /// Adds an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        _ = self.inner.arg(arg);
+        self
+    }
+
+    /// Adds multiple arguments to pass to the program.
+    pub fn args<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(&mut self, args: I) -> &mut Self {
+        _ = self.inner.args(args);
+        self
+    }
+
+    /// Inserts or updates an environment variable.
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, val: V) -> &mut Self {
+        _ = self.inner.env(key, val);
+        self
+    }
+
+    /// Sets the working directory for the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        _ = self.inner.current_dir(dir);
+        self
+    }
+
+    /// Configuration for the child process's standard input handle.
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        _ = self.inner.stdin(cfg);
+        self
+    }
+
+    /// Configuration for the child process's standard output handle.
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        _ = self.inner.stdout(cfg);
+        self
+    }
+
+    /// Configuration for the child process's standard error handle.
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        _ = self.inner.stderr(cfg);
+        self
+    }
+
+    /// Kill the child process if it's still running when the `Child` is dropped.
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    /// Executes the command as a child process, returning a handle to it.
+    pub fn spawn(&mut self) -> std::io::Result<Child> {
+        let mut inner = self.inner.spawn()?;
+        Ok(Child {
+            pidfd: pidfd_open(inner.id()),
+            stdin: inner.stdin.take().map(Pipe::new).transpose()?,
+            stdout: inner.stdout.take().map(Pipe::new).transpose()?,
+            stderr: inner.stderr.take().map(Pipe::new).transpose()?,
+            kill_on_drop: self.kill_on_drop,
+            inner,
+        })
+    }
+
+    /// Executes the command as a child process, waiting for it to finish and collecting its status.
+    pub fn status(&mut self) -> std::io::Result<ExitStatus> {
+        self.spawn()?.wait()
+    }
+
+    /// Executes the command as a child process, waiting for it to finish and collecting all of its output.
+    pub fn output(&mut self) -> std::io::Result<Output> {
+        _ = self
+            .inner
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        self.spawn()?.wait_with_output()
+    }
+}
+
+/// A non-blocking pipe connected to a child process.
+#[derive(Debug)]
+pub struct Pipe<T> {
+    inner: T,
+    timeout: Option<Duration>,
+}
+
+/// A handle to a child process's standard input.
+pub type ChildStdin = Pipe<std::process::ChildStdin>;
+
+/// A handle to a child process's standard output.
+pub type ChildStdout = Pipe<std::process::ChildStdout>;
+
+/// A handle to a child process's standard error.
+pub type ChildStderr = Pipe<std::process::ChildStderr>;
+
+impl<T: AsRawFd> Pipe<T> {
+    fn new(inner: T) -> std::io::Result<Self> {
+        set_non_blocking(inner.as_raw_fd())?;
+        Ok(Self {
+            inner,
+            timeout: None,
+        })
+    }
+
+    /// Set the timeout of each read or write, `None` means wait forever.
+    /// Operations exceed the timeout fail with [`ErrorKind::TimedOut`].
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for Pipe<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<T: AsRawFd + Read> Read for Pipe<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let fd = self.inner.as_raw_fd();
+        retry(fd, libc::POLLIN, self.timeout, || self.inner.read(buf))
+    }
+}
+
+impl<T: AsRawFd + Write> Write for Pipe<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let fd = self.inner.as_raw_fd();
+        retry(fd, libc::POLLOUT, self.timeout, || self.inner.write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Representation of a running or exited child process, see [`std::process::Child`].
+#[derive(Debug)]
+pub struct Child {
+    inner: std::process::Child,
+    pidfd: Option<OwnedFd>,
+    kill_on_drop: bool,
+    /// The handle for writing to the child's standard input, if it has been captured.
+    pub stdin: Option<ChildStdin>,
+    /// The handle for reading from the child's standard output, if it has been captured.
+    pub stdout: Option<ChildStdout>,
+    /// The handle for reading from the child's standard error, if it has been captured.
+    pub stderr: Option<ChildStderr>,
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if self.kill_on_drop && matches!(self.inner.try_wait(), Ok(None)) {
+            _ = self.inner.kill();
+            // reap it, the hooked waitpid parks instead of blocking
+            _ = self.inner.wait();
+        }
+    }
+}
+
+impl Child {
+    /// Returns the OS-assigned process identifier associated with this child.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.inner.id()
+    }
+
+    /// Forces the child process to exit.
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        self.inner.kill()
+    }
+
+    /// Attempts to collect the exit status of the child if it has already exited.
+    pub fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        self.inner.try_wait()
+    }
+
+    /// Waits for the child to exit completely, returning the status that it exited with.
+    /// The stdin handle is closed before waiting to avoid deadlock.
+    pub fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        self.wait_until(None)?
+            .ok_or_else(|| Error::other("wait child failed"))
+    }
+
+    /// Waits for the child to exit at most `dur`, returns `None` if timeout.
+    /// The stdin handle is closed before waiting to avoid deadlock.
+    pub fn wait_timeout(&mut self, dur: Duration) -> std::io::Result<Option<ExitStatus>> {
+        self.wait_until(Instant::now().checked_add(dur))
+    }
+
+    fn wait_until(&mut self, deadline: Option<Instant>) -> std::io::Result<Option<ExitStatus>> {
+        drop(self.stdin.take());
+        loop {
+            if let Some(status) = self.inner.try_wait()? {
+                return Ok(Some(status));
+            }
+            let left = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return Ok(None);
+                    }
+                    left.min(SLICE)
+                }
+                None => SLICE,
+            };
+            if let Some(pidfd) = &self.pidfd {
+                // the pidfd becomes readable when the child exits
+                _ = wait_fd(pidfd.as_raw_fd(), libc::POLLIN, Some(left))?;
+            } else {
+                std::thread::sleep(left);
+            }
+        }
+    }
+
+    /// Simultaneously waits for the child to exit and collect all remaining output on the
+    /// stdout/stderr handles, returning an `Output` instance.
+    pub fn wait_with_output(mut self) -> std::io::Result<Output> {
+        drop(self.stdin.take());
+        let (out, err) = (self.stdout.take(), self.stderr.take());
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let mut pipes = [
+            (out.as_ref().map(AsRawFd::as_raw_fd), &mut stdout),
+            (err.as_ref().map(AsRawFd::as_raw_fd), &mut stderr),
+        ];
+        let mut buf = [0u8; 4096];
+        while pipes.iter().any(|(fd, _)| fd.is_some()) {
+            let mut pending = Vec::new();
+            for (pipe, output) in &mut pipes {
+                let Some(fd) = *pipe else {
+                    continue;
+                };
+                match unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) } {
+                    0 => *pipe = None,
+                    -1 => {
+                        let e = Error::last_os_error();
+                        match e.kind() {
+                            ErrorKind::WouldBlock => pending.push(libc::pollfd {
+                                fd,
+                                events: libc::POLLIN,
+                                revents: 0,
+                            }),
+                            ErrorKind::Interrupted => {}
+                            _ => return Err(e),
+                        }
+                    }
+                    n => output.extend_from_slice(&buf[..n.unsigned_abs()]),
+                }
+            }
+            if !pending.is_empty() {
+                let nfds = libc::nfds_t::try_from(pending.len()).expect("overflow");
+                // the hooked poll parks until any of the pipes is readable
+                if unsafe { libc::poll(pending.as_mut_ptr(), nfds, -1) } == -1 {
+                    let e = Error::last_os_error();
+                    if e.kind() != ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        drop((out, err));
+        let status = self.wait()?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+/// Open a pidfd for the child, so that its exit can be waited through the selector.
+#[cfg(target_os = "linux")]
+fn pidfd_open(pid: u32) -> Option<OwnedFd> {
+    use std::os::fd::FromRawFd;
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    RawFd::try_from(fd)
+        .ok()
+        .filter(|fd| *fd >= 0)
+        .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pidfd_open(_: u32) -> Option<OwnedFd> {
+    None
+}
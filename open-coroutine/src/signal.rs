@@ -0,0 +1,155 @@
+//! Receive signals in coroutines.
+//!
+//! The signals are delivered through a `signalfd`, so a coroutine waiting for them is parked on
+//! the selector of its `EventLoop` instead of blocking the thread.
+
+use crate::fd::{retry, wait_fd};
+use std::ffi::c_int;
+use std::io::{Error, ErrorKind};
+use std::mem::{size_of, MaybeUninit};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::time::{Duration, Instant};
+
+/// Signals used by the runtime itself, they can't be listened.
+/// - `SIGURG` is used to preempt coroutines.
+/// - `SIGVTALRM` is used to cancel coroutines.
+/// - `SIGSEGV` and `SIGBUS` are used to detect stack overflow of coroutines.
+/// - `SIGKILL` and `SIGSTOP` can't be caught.
+pub const RESERVED: [c_int; 6] = [
+    libc::SIGURG,
+    libc::SIGVTALRM,
+    libc::SIGSEGV,
+    libc::SIGBUS,
+    libc::SIGKILL,
+    libc::SIGSTOP,
+];
+
+/// Listen the `signals`.
+///
+/// The signals are blocked in the calling thread, only threads blocking them will not take
+/// them away from the `signalfd`. Call this before `open_coroutine::init`, the threads spawned
+/// by the runtime inherit the signal mask, so process-directed signals won't be lost.
+///
+/// # Errors
+/// if any of the `signals` is reserved by the runtime, or the `signalfd` can't be created.
+pub fn listen(signals: &[c_int]) -> std::io::Result<Signals> {
+    if signals.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "no signal to listen"));
+    }
+    let mut set = MaybeUninit::<libc::sigset_t>::uninit();
+    unsafe {
+        if libc::sigemptyset(set.as_mut_ptr()) == -1 {
+            return Err(Error::last_os_error());
+        }
+    }
+    for &signal in signals {
+        if RESERVED.contains(&signal) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("signal {signal} is reserved by open-coroutine"),
+            ));
+        }
+        if unsafe { libc::sigaddset(set.as_mut_ptr(), signal) } == -1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid signal {signal}"),
+            ));
+        }
+    }
+    let set = unsafe { set.assume_init() };
+    let r = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &raw const set, std::ptr::null_mut()) };
+    if r != 0 {
+        return Err(Error::from_raw_os_error(r));
+    }
+    let fd = unsafe { libc::signalfd(-1, &raw const set, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) };
+    if fd == -1 {
+        return Err(Error::last_os_error());
+    }
+    Ok(Signals {
+        fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        signals: signals.to_vec(),
+    })
+}
+
+/// The listened signals.
+#[derive(Debug)]
+pub struct Signals {
+    fd: OwnedFd,
+    signals: Vec<c_int>,
+}
+
+impl Signals {
+    /// The listened signals.
+    #[must_use]
+    pub fn signals(&self) -> &[c_int] {
+        &self.signals
+    }
+
+    /// Receive a delivered signal without waiting.
+    ///
+    /// # Errors
+    /// if read the `signalfd` failed.
+    pub fn try_recv(&self) -> std::io::Result<Option<c_int>> {
+        match self.read() {
+            Ok(signal) => Ok(Some(signal)),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Wait until a signal is delivered.
+    ///
+    /// # Errors
+    /// if read the `signalfd` failed.
+    pub fn recv(&self) -> std::io::Result<c_int> {
+        retry(self.as_raw_fd(), libc::POLLIN, None, || self.read())
+    }
+
+    /// Wait until a signal is delivered or `timeout`, returns `None` if timeout.
+    ///
+    /// # Errors
+    /// if read the `signalfd` failed.
+    pub fn recv_timeout(&self, timeout: Duration) -> std::io::Result<Option<c_int>> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if let Some(signal) = self.try_recv()? {
+                return Ok(Some(signal));
+            }
+            let left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if !wait_fd(self.as_raw_fd(), libc::POLLIN, left)? {
+                return self.try_recv();
+            }
+        }
+    }
+
+    fn read(&self) -> std::io::Result<c_int> {
+        let mut info = MaybeUninit::<libc::signalfd_siginfo>::uninit();
+        let size = size_of::<libc::signalfd_siginfo>();
+        let r = unsafe { libc::read(self.as_raw_fd(), info.as_mut_ptr().cast(), size) };
+        if r == -1 {
+            return Err(Error::last_os_error());
+        }
+        if usize::try_from(r).expect("unexpected read result") != size {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "partial signalfd_siginfo",
+            ));
+        }
+        let info = unsafe { info.assume_init() };
+        Ok(c_int::try_from(info.ssi_signo).expect("invalid signal number"))
+    }
+}
+
+impl AsRawFd for Signals {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl Iterator for Signals {
+    type Item = std::io::Result<c_int>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.recv())
+    }
+}
@@ -0,0 +1,416 @@
+//! Watch filesystem changes in coroutines.
+//!
+//! The changes are read from an `inotify` fd, so a coroutine waiting for them is parked on the
+//! selector of its `EventLoop` instead of blocking the thread.
+
+use crate::fd::{retry, wait_fd};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{c_int, CString, OsStr};
+use std::io::{Error, ErrorKind};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_MODIFY
+    | libc::IN_DELETE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF;
+
+const HEADER_SIZE: usize = size_of::<libc::inotify_event>();
+
+// how long a move waits for its pair, the pair may be split across reads
+const MOVE_EXPIRY: Duration = Duration::from_millis(10);
+
+/// A filesystem change.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// The path was created.
+    Create(PathBuf),
+    /// The file was modified.
+    Modify(PathBuf),
+    /// The path was deleted.
+    Delete(PathBuf),
+    /// The path was moved, `from` is `None` if it's moved in from an unwatched directory,
+    /// `to` is `None` if it's moved out to an unwatched directory.
+    Move {
+        /// the old path
+        from: Option<PathBuf>,
+        /// the new path
+        to: Option<PathBuf>,
+    },
+    /// The kernel event queue overflowed, some events are lost.
+    Overflow,
+}
+
+/// Watch `path`, watch all its subdirectories if `recursive` is true.
+///
+/// # Errors
+/// if create the `inotify` fd or add the watch failed.
+pub fn watch<P: AsRef<Path>>(path: P, recursive: bool) -> std::io::Result<Watcher> {
+    let mut watcher = Watcher::new()?;
+    watcher.add(path, recursive)?;
+    Ok(watcher)
+}
+
+// a decoded change, the moved out path waits for its pair in place to keep the kernel order
+#[derive(Debug)]
+enum Queued {
+    Ready(Event),
+    MovedFrom {
+        cookie: u32,
+        from: PathBuf,
+        is_dir: bool,
+        expiry: Instant,
+    },
+}
+
+/// The filesystem watcher.
+#[derive(Debug)]
+pub struct Watcher {
+    fd: OwnedFd,
+    // watch descriptor -> (watched path, recursive)
+    watches: HashMap<c_int, (PathBuf, bool)>,
+    // watch descriptors added by `add`
+    roots: HashSet<c_int>,
+    events: VecDeque<Queued>,
+}
+
+impl Watcher {
+    /// Create a watcher without any watch.
+    ///
+    /// # Errors
+    /// if create the `inotify` fd failed.
+    pub fn new() -> std::io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok(Watcher {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            watches: HashMap::new(),
+            roots: HashSet::new(),
+            events: VecDeque::new(),
+        })
+    }
+
+    /// Watch `path`, watch all its subdirectories if `recursive` is true.
+    ///
+    /// # Errors
+    /// if add the watch failed.
+    pub fn add<P: AsRef<Path>>(&mut self, path: P, recursive: bool) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let wd = self.add_watch(path, recursive)?;
+        _ = self.roots.insert(wd);
+        if recursive && path.is_dir() {
+            self.add_subdirectories(path, false)?;
+        }
+        Ok(())
+    }
+
+    /// Stop watching `path` and its watched subdirectories.
+    ///
+    /// # Errors
+    /// if `path` is not watched.
+    pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let removed: Vec<c_int> = self
+            .watches
+            .iter()
+            .filter(|(_, (p, _))| p.starts_with(path))
+            .map(|(wd, _)| *wd)
+            .collect();
+        if removed.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{} is not watched", path.display()),
+            ));
+        }
+        for wd in removed {
+            _ = self.watches.remove(&wd);
+            _ = self.roots.remove(&wd);
+            _ = unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), wd) };
+        }
+        Ok(())
+    }
+
+    /// Receive a change without waiting.
+    ///
+    /// # Errors
+    /// if read the `inotify` fd failed.
+    pub fn try_recv(&mut self) -> std::io::Result<Option<Event>> {
+        if let Some(event) = self.pop() {
+            return Ok(Some(event));
+        }
+        match self.read() {
+            Ok(()) => Ok(self.pop()),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Wait until a change happens.
+    ///
+    /// # Errors
+    /// if read the `inotify` fd failed.
+    pub fn recv(&mut self) -> std::io::Result<Event> {
+        loop {
+            if let Some(event) = self.try_recv()? {
+                return Ok(event);
+            }
+            let fd = self.fd.as_raw_fd();
+            match self.pending() {
+                // wait for the pair of the move until it expires
+                Some(left) => _ = wait_fd(fd, libc::POLLIN, Some(left))?,
+                None => retry(fd, libc::POLLIN, None, || self.read())?,
+            }
+        }
+    }
+
+    /// Wait until a change happens or `timeout`, returns `None` if timeout.
+    ///
+    /// # Errors
+    /// if read the `inotify` fd failed.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> std::io::Result<Option<Event>> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if let Some(event) = self.try_recv()? {
+                return Ok(Some(event));
+            }
+            let left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            let wait = match (left, self.pending()) {
+                (Some(left), Some(pending)) => Some(left.min(pending)),
+                (left, pending) => left.or(pending),
+            };
+            if !wait_fd(self.fd.as_raw_fd(), libc::POLLIN, wait)?
+                && deadline.is_some_and(|d| d <= Instant::now())
+            {
+                return self.try_recv();
+            }
+        }
+    }
+
+    fn add_watch(&mut self, path: &Path, recursive: bool) -> std::io::Result<c_int> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let wd =
+            unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_path.as_ptr(), WATCH_MASK) };
+        if wd == -1 {
+            return Err(Error::last_os_error());
+        }
+        _ = self.watches.insert(wd, (path.to_path_buf(), recursive));
+        Ok(wd)
+    }
+
+    /// Watch the subdirectories of `dir`, report the entries as created if `created` is true,
+    /// because they may be created before the watch was added.
+    fn add_subdirectories(&mut self, dir: &Path, created: bool) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if created {
+                self.events
+                    .push_back(Queued::Ready(Event::Create(path.clone())));
+            }
+            if entry.file_type()?.is_dir() {
+                _ = self.add_watch(&path, true)?;
+                self.add_subdirectories(&path, created)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> std::io::Result<()> {
+        // enough for at least one event with the longest name
+        let mut buf = [0u8; 16 * (HEADER_SIZE + 256)];
+        let r = unsafe { libc::read(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+        if r == -1 {
+            return Err(Error::last_os_error());
+        }
+        let len = usize::try_from(r).expect("unexpected read result");
+        let mut offset = 0;
+        while offset + HEADER_SIZE <= len {
+            let event: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(buf.as_ptr().add(offset).cast()) };
+            let name_len = usize::try_from(event.len).expect("overflow");
+            let name = &buf[offset + HEADER_SIZE..offset + HEADER_SIZE + name_len];
+            offset += HEADER_SIZE + name_len;
+            self.decode(&event, name);
+        }
+        Ok(())
+    }
+
+    // pop the first change, the move is popped once its pair is decoded or it expires
+    fn pop(&mut self) -> Option<Event> {
+        if let Queued::MovedFrom { expiry, .. } = self.events.front()? {
+            if *expiry > Instant::now() {
+                return None;
+            }
+        }
+        match self.events.pop_front()? {
+            Queued::Ready(event) => Some(event),
+            Queued::MovedFrom { from, is_dir, .. } => {
+                // moved out to an unwatched directory
+                if is_dir {
+                    self.forget(&from);
+                }
+                Some(Event::Move {
+                    from: Some(from),
+                    to: None,
+                })
+            }
+        }
+    }
+
+    // the time left for the first change waiting for its pair
+    fn pending(&self) -> Option<Duration> {
+        match self.events.front()? {
+            Queued::MovedFrom { expiry, .. } => {
+                Some(expiry.saturating_duration_since(Instant::now()))
+            }
+            Queued::Ready(_) => None,
+        }
+    }
+
+    fn decode(&mut self, event: &libc::inotify_event, name: &[u8]) {
+        let mask = event.mask;
+        if mask & libc::IN_Q_OVERFLOW != 0 {
+            self.events.push_back(Queued::Ready(Event::Overflow));
+            return;
+        }
+        if mask & libc::IN_IGNORED != 0 {
+            _ = self.watches.remove(&event.wd);
+            _ = self.roots.remove(&event.wd);
+            return;
+        }
+        let Some((dir, recursive)) = self.watches.get(&event.wd).cloned() else {
+            return;
+        };
+        // the name is padded with NUL
+        let name = name.split(|b| *b == 0).next().unwrap_or_default();
+        let path = if name.is_empty() {
+            dir
+        } else {
+            dir.join(OsStr::from_bytes(name))
+        };
+        let is_dir = mask & libc::IN_ISDIR != 0;
+        if mask & libc::IN_CREATE != 0 {
+            self.events
+                .push_back(Queued::Ready(Event::Create(path.clone())));
+            if recursive && is_dir {
+                self.watch_new_directory(&path);
+            }
+        } else if mask & libc::IN_MODIFY != 0 {
+            self.events.push_back(Queued::Ready(Event::Modify(path)));
+        } else if mask & libc::IN_DELETE != 0 {
+            self.events.push_back(Queued::Ready(Event::Delete(path)));
+        } else if mask & libc::IN_MOVED_FROM != 0 {
+            self.events.push_back(Queued::MovedFrom {
+                cookie: event.cookie,
+                from: path,
+                is_dir,
+                expiry: Instant::now() + MOVE_EXPIRY,
+            });
+        } else if mask & libc::IN_MOVED_TO != 0 {
+            self.moved_to(event.cookie, path, is_dir, recursive);
+        } else if mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF) != 0
+            && self.roots.contains(&event.wd)
+        {
+            // the changes of subdirectories are reported by their parents
+            if mask & libc::IN_DELETE_SELF != 0 {
+                self.events.push_back(Queued::Ready(Event::Delete(path)));
+            } else {
+                self.events.push_back(Queued::Ready(Event::Move {
+                    from: Some(path),
+                    to: None,
+                }));
+            }
+        }
+    }
+
+    // complete the move in place of its moved out half
+    fn moved_to(&mut self, cookie: u32, to: PathBuf, is_dir: bool, recursive: bool) {
+        let index = self.events.iter().position(
+            |queued| matches!(queued, Queued::MovedFrom { cookie: c, .. } if *c == cookie),
+        );
+        let Some(index) = index else {
+            // moved in from an unwatched directory
+            self.events.push_back(Queued::Ready(Event::Move {
+                from: None,
+                to: Some(to.clone()),
+            }));
+            if recursive && is_dir {
+                self.watch_new_directory(&to);
+            }
+            return;
+        };
+        let Queued::MovedFrom { from, .. } = &self.events[index] else {
+            unreachable!("the move is matched by its cookie");
+        };
+        let from = from.clone();
+        if is_dir {
+            self.rename(&from, &to, recursive);
+        }
+        self.events[index] = Queued::Ready(Event::Move {
+            from: Some(from),
+            to: Some(to),
+        });
+    }
+
+    /// Re-key the watches of the directory renamed inside the watched directories, the kernel
+    /// keeps watching it by inode, so its entries are not reported as created.
+    fn rename(&mut self, from: &Path, to: &Path, recursive: bool) {
+        if !recursive {
+            self.forget(from);
+            return;
+        }
+        let mut renamed = false;
+        for (wd, (path, _)) in &mut self.watches {
+            if self.roots.contains(wd) {
+                continue;
+            }
+            if let Ok(rest) = path.strip_prefix(from) {
+                *path = if rest.as_os_str().is_empty() {
+                    to.to_path_buf()
+                } else {
+                    to.join(rest)
+                };
+                renamed = true;
+            }
+        }
+        // moved from a directory not watched recursively
+        if !renamed && self.add_watch(to, true).is_ok() {
+            _ = self.add_subdirectories(to, false);
+        }
+    }
+
+    fn watch_new_directory(&mut self, path: &Path) {
+        // the directory may be removed already
+        if self.add_watch(path, true).is_ok() {
+            _ = self.add_subdirectories(path, true);
+        }
+    }
+
+    /// Stop watching the subdirectories moved out, the kernel keeps watching them by inode.
+    fn forget(&mut self, path: &Path) {
+        let removed: Vec<c_int> = self
+            .watches
+            .iter()
+            .filter(|(wd, (p, _))| p.starts_with(path) && !self.roots.contains(wd))
+            .map(|(wd, _)| *wd)
+            .collect();
+        for wd in removed {
+            _ = self.watches.remove(&wd);
+            _ = unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), wd) };
+        }
+    }
+}
+
+impl AsRawFd for Watcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
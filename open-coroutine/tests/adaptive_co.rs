@@ -0,0 +1,6 @@
+include!("../examples/adaptive_co.rs");
+
+#[test]
+fn adaptive_co() {
+    main();
+}
@@ -0,0 +1,6 @@
+include!("../examples/aging_co.rs");
+
+#[test]
+fn aging_co() {
+    main();
+}
@@ -0,0 +1,6 @@
+include!("../examples/bulkhead_co.rs");
+
+#[test]
+fn bulkhead_co() {
+    main();
+}
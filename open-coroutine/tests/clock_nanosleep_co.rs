@@ -0,0 +1,8 @@
+#[cfg(target_os = "linux")]
+include!("../examples/clock_nanosleep_co.rs");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn clock_nanosleep_co() -> std::io::Result<()> {
+    main()
+}
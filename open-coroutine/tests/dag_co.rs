@@ -0,0 +1,6 @@
+include!("../examples/dag_co.rs");
+
+#[test]
+fn dag_co() -> std::io::Result<()> {
+    main()
+}
@@ -0,0 +1,6 @@
+include!("../examples/deadline_co.rs");
+
+#[test]
+fn deadline_co() {
+    main();
+}
@@ -0,0 +1,6 @@
+include!("../examples/migration_co.rs");
+
+#[test]
+fn migration_co() {
+    main();
+}
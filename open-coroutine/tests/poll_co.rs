@@ -0,0 +1,8 @@
+#[cfg(unix)]
+include!("../examples/poll_co.rs");
+
+#[cfg(unix)]
+#[test]
+fn poll_co() -> std::io::Result<()> {
+    main()
+}
@@ -0,0 +1,8 @@
+#[cfg(unix)]
+include!("../examples/process_co.rs");
+
+#[cfg(unix)]
+#[test]
+fn process_co() -> std::io::Result<()> {
+    main()
+}
@@ -0,0 +1,6 @@
+include!("../examples/registry_co.rs");
+
+#[test]
+fn registry_co() {
+    main();
+}
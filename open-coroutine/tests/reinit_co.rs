@@ -0,0 +1,6 @@
+include!("../examples/reinit_co.rs");
+
+#[test]
+fn reinit_co() {
+    main();
+}
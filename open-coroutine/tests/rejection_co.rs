@@ -0,0 +1,6 @@
+include!("../examples/rejection_co.rs");
+
+#[test]
+fn rejection_co() {
+    main();
+}
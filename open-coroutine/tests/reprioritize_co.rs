@@ -0,0 +1,6 @@
+include!("../examples/reprioritize_co.rs");
+
+#[test]
+fn reprioritize_co() {
+    main();
+}
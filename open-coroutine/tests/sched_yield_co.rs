@@ -0,0 +1,8 @@
+#[cfg(unix)]
+include!("../examples/sched_yield_co.rs");
+
+#[cfg(unix)]
+#[test]
+fn sched_yield_co() -> std::io::Result<()> {
+    main()
+}
@@ -0,0 +1,8 @@
+#[cfg(unix)]
+include!("../examples/scheduling_policy_co.rs");
+
+#[cfg(unix)]
+#[test]
+fn scheduling_policy_co() -> std::io::Result<()> {
+    main()
+}
@@ -0,0 +1,8 @@
+#[cfg(target_os = "linux")]
+include!("../examples/signal_co.rs");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn signal_co() -> std::io::Result<()> {
+    main()
+}
@@ -0,0 +1,6 @@
+include!("../examples/task_builder_co.rs");
+
+#[test]
+fn task_builder_co() {
+    main();
+}
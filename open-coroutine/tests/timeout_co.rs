@@ -0,0 +1,6 @@
+include!("../examples/timeout_co.rs");
+
+#[test]
+fn timeout_co() {
+    main();
+}
@@ -0,0 +1,6 @@
+include!("../examples/typed_result_co.rs");
+
+#[test]
+fn typed_result_co() {
+    main();
+}
@@ -0,0 +1,8 @@
+#[cfg(target_os = "linux")]
+include!("../examples/watch_co.rs");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_co() -> std::io::Result<()> {
+    main()
+}